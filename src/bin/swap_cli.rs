@@ -1,13 +1,31 @@
+use std::str::FromStr as _;
+
 use anyhow::{Context as _, Result};
 use clap::{Parser as _, Subcommand};
+use ln_liquid_swap::liquid::chain::{ElectrumChain, LiquidChain as _, confirmations};
+use ln_liquid_swap::liquid::fee::{FeeEstimator, MIN_FEE_RATE_SAT_VB, build_at_fee_rate};
+use ln_liquid_swap::liquid::htlc::{
+    HtlcBlinding, HtlcFunding, HtlcSpec, claim_tx_from_witness_script, pubkey_hash160,
+    pubkey_hash160_from_p2wpkh_address, sha256_preimage,
+};
+use ln_liquid_swap::liquid::taproot::{TaprootHtlcSpec, taproot_claim_tx_script_path};
+use ln_liquid_swap::proto::v1::admin_service_client::AdminServiceClient;
 use ln_liquid_swap::proto::v1::swap_service_client::SwapServiceClient;
 use ln_liquid_swap::proto::v1::{
-    CreateAssetClaimRequest, CreateLightningPaymentRequest, CreateQuoteRequest, CreateSwapRequest,
-    GetQuoteRequest, GetSwapRequest, SwapDirection, SwapRole, SwapStatus,
+    CreateApiKeyRequest, CreateAssetClaimRequest, CreateLightningPaymentRequest,
+    CreateQuoteRequest, CreateSwapRequest, GetQuoteRequest, GetSwapRequest, HtlcVersion,
+    ListApiKeysRequest, ListOfferVersionsRequest, ListOffersRequest, ListQuotesRequest,
+    ListSwapsRequest, Offer, PauseOfferRequest, ResumeOfferRequest, RevokeApiKeyRequest,
+    SwapDirection, SwapRole, SwapStatus, UpdateOfferRequest, WatchSwapRequest,
 };
+use ln_liquid_swap::swap::verify::{self, SwapExpectations};
+use lwk_wollet::elements::bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lwk_wollet::elements::secp256k1_zkp::SecretKey as ZkpSecretKey;
+use lwk_wollet::elements::{Address, AssetId, BlockHash, Script, Txid};
 use serde_json::json;
 use tonic::Request;
 use tonic::metadata::MetadataValue;
+use tonic::transport::Endpoint;
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
 enum DirectionArg {
@@ -24,6 +42,70 @@ impl DirectionArg {
     }
 }
 
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum StatusArg {
+    Created,
+    Funded,
+    Paid,
+    Claimed,
+    RefundPending,
+    Refunded,
+    Failed,
+}
+
+impl StatusArg {
+    fn to_proto(self) -> SwapStatus {
+        match self {
+            StatusArg::Created => SwapStatus::Created,
+            StatusArg::Funded => SwapStatus::Funded,
+            StatusArg::Paid => SwapStatus::Paid,
+            StatusArg::Claimed => SwapStatus::Claimed,
+            StatusArg::RefundPending => SwapStatus::RefundPending,
+            StatusArg::Refunded => SwapStatus::Refunded,
+            StatusArg::Failed => SwapStatus::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum RoleArg {
+    Buyer,
+    Seller,
+}
+
+impl RoleArg {
+    fn to_proto(self) -> SwapRole {
+        match self {
+            RoleArg::Buyer => SwapRole::Buyer,
+            RoleArg::Seller => SwapRole::Seller,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum HtlcVersionArg {
+    P2wsh,
+    Taproot,
+}
+
+impl HtlcVersionArg {
+    fn to_proto(self) -> HtlcVersion {
+        match self {
+            HtlcVersionArg::P2wsh => HtlcVersion::P2wsh,
+            HtlcVersionArg::Taproot => HtlcVersion::Taproot,
+        }
+    }
+}
+
+/// The HTLC a `claim-local` spends, with what each version needs to sign its claim.
+enum ClaimHtlc {
+    P2wsh(Script),
+    Taproot {
+        spec: TaprootHtlcSpec,
+        genesis_hash: BlockHash,
+    },
+}
+
 #[derive(Debug, clap::Parser)]
 struct Args {
     #[arg(long, default_value = "http://127.0.0.1:50051")]
@@ -38,6 +120,53 @@ struct Args {
 
 #[derive(Debug, Subcommand)]
 enum Command {
+    ListOffers,
+    UpdateOffer {
+        #[arg(long)]
+        asset_id: String,
+
+        #[arg(long = "direction", value_enum)]
+        directions: Vec<DirectionArg>,
+
+        #[arg(long)]
+        price_msat_per_asset_unit: u64,
+
+        #[arg(long)]
+        fee_subsidy_sats: u64,
+
+        #[arg(long)]
+        refund_delta_blocks: u32,
+
+        #[arg(long, default_value_t = 0)]
+        min_asset_amount: u64,
+
+        #[arg(long, default_value_t = 0)]
+        max_asset_amount: u64,
+    },
+    PauseOffer {
+        #[arg(long)]
+        asset_id: String,
+    },
+    ResumeOffer {
+        #[arg(long)]
+        asset_id: String,
+    },
+    ListOfferVersions {
+        #[arg(long)]
+        asset_id: String,
+    },
+    CreateApiKey {
+        #[arg(long, value_enum)]
+        role: RoleArg,
+
+        #[arg(long)]
+        owner: String,
+    },
+    RevokeApiKey {
+        #[arg(long)]
+        key_id: String,
+    },
+    ListApiKeys,
     CreateQuote {
         #[arg(long)]
         direction: DirectionArg,
@@ -55,6 +184,25 @@ enum Command {
         #[arg(long)]
         quote_id: String,
     },
+    ListQuotes {
+        #[arg(long)]
+        direction: Option<DirectionArg>,
+
+        #[arg(long)]
+        asset_id: Option<String>,
+
+        #[arg(long)]
+        created_after: Option<u64>,
+
+        #[arg(long)]
+        created_before: Option<u64>,
+
+        #[arg(long, default_value_t = 0)]
+        page_size: u32,
+
+        #[arg(long, default_value = "")]
+        page_token: String,
+    },
     CreateSwap {
         #[arg(long)]
         quote_id: String,
@@ -64,11 +212,46 @@ enum Command {
 
         #[arg(long, default_value = "")]
         buyer_bolt11_invoice: String,
+
+        #[arg(long, default_value = "")]
+        buyer_claim_pubkey: String,
+
+        #[arg(long, default_value = "p2wsh")]
+        htlc_version: HtlcVersionArg,
+
+        #[arg(long)]
+        confidential: bool,
     },
     GetSwap {
         #[arg(long)]
         swap_id: String,
     },
+    ListSwaps {
+        #[arg(long)]
+        status: Option<StatusArg>,
+
+        #[arg(long)]
+        direction: Option<DirectionArg>,
+
+        #[arg(long)]
+        asset_id: Option<String>,
+
+        #[arg(long)]
+        created_after: Option<u64>,
+
+        #[arg(long)]
+        created_before: Option<u64>,
+
+        #[arg(long, default_value_t = 0)]
+        page_size: u32,
+
+        #[arg(long, default_value = "")]
+        page_token: String,
+    },
+    Watch {
+        #[arg(long)]
+        swap_id: String,
+    },
     CreateLightningPayment {
         #[arg(long)]
         swap_id: String,
@@ -80,9 +263,71 @@ enum Command {
         #[arg(long)]
         swap_id: String,
 
-        #[arg(long, default_value_t = 500)]
+        #[arg(long, default_value_t = 0)]
         claim_fee_sats: u64,
     },
+    ClaimLocal {
+        #[arg(long)]
+        swap_id: String,
+
+        #[arg(long)]
+        claim_secret_key: String,
+
+        #[arg(long)]
+        preimage: String,
+
+        #[arg(long)]
+        destination_address: String,
+
+        #[arg(long, conflicts_with = "fee_rate_sat_vb")]
+        claim_fee_sats: Option<u64>,
+
+        #[arg(long)]
+        fee_rate_sat_vb: Option<f64>,
+
+        #[arg(long)]
+        policy_asset: String,
+
+        #[arg(long)]
+        electrum_url: Option<String>,
+    },
+    VerifySwap {
+        #[arg(long)]
+        swap_id: String,
+
+        #[arg(long)]
+        electrum_url: String,
+
+        #[arg(long)]
+        user_liquid_address: Option<String>,
+
+        #[arg(long)]
+        claim_pubkey: Option<String>,
+
+        #[arg(long)]
+        policy_asset: String,
+
+        #[arg(long)]
+        asset_id: String,
+
+        #[arg(long)]
+        asset_amount: u64,
+
+        #[arg(long)]
+        total_price_msat: u64,
+
+        #[arg(long)]
+        fee_subsidy_sats: u64,
+
+        #[arg(long, default_value_t = 12)]
+        min_refund_blocks: u32,
+
+        #[arg(long)]
+        pay: bool,
+
+        #[arg(long, default_value_t = 60)]
+        payment_timeout_secs: u32,
+    },
 }
 
 #[tokio::main]
@@ -90,11 +335,135 @@ async fn main() -> Result<()> {
     ln_liquid_swap::logging::init().ok();
     let args = Args::parse();
 
-    let mut client = SwapServiceClient::connect(args.grpc_url)
+    let channel = Endpoint::from_shared(args.grpc_url)
+        .context("parse grpc_url")?
+        .connect()
         .await
         .context("connect gRPC")?;
+    let mut client = SwapServiceClient::new(channel.clone());
+    let mut admin = AdminServiceClient::new(channel);
 
     let out = match args.command {
+        Command::ListOffers => {
+            let resp = client
+                .list_offers(with_auth(&args.auth_token, ListOffersRequest {}))
+                .await
+                .context("ListOffers")?
+                .into_inner();
+
+            json!({
+              "offers": resp.offers.into_iter().map(offer_json).collect::<Vec<_>>(),
+            })
+        }
+        Command::UpdateOffer {
+            asset_id,
+            directions,
+            price_msat_per_asset_unit,
+            fee_subsidy_sats,
+            refund_delta_blocks,
+            min_asset_amount,
+            max_asset_amount,
+        } => {
+            let directions = if directions.is_empty() {
+                vec![DirectionArg::LnToLiquid, DirectionArg::LiquidToLn]
+            } else {
+                directions
+            };
+            let version = admin
+                .update_offer(with_auth(
+                    &args.auth_token,
+                    UpdateOfferRequest {
+                        offer: Some(Offer {
+                            asset_id,
+                            supported_directions: directions
+                                .into_iter()
+                                .map(|d| d.to_proto() as i32)
+                                .collect(),
+                            price_msat_per_asset_unit,
+                            fee_subsidy_sats,
+                            refund_delta_blocks,
+                            min_asset_amount,
+                            max_asset_amount,
+                            ..Default::default()
+                        }),
+                    },
+                ))
+                .await
+                .context("UpdateOffer")?
+                .into_inner();
+
+            offer_version_json(version)
+        }
+        Command::PauseOffer { asset_id } => {
+            let version = admin
+                .pause_offer(with_auth(&args.auth_token, PauseOfferRequest { asset_id }))
+                .await
+                .context("PauseOffer")?
+                .into_inner();
+
+            offer_version_json(version)
+        }
+        Command::ResumeOffer { asset_id } => {
+            let version = admin
+                .resume_offer(with_auth(&args.auth_token, ResumeOfferRequest { asset_id }))
+                .await
+                .context("ResumeOffer")?
+                .into_inner();
+
+            offer_version_json(version)
+        }
+        Command::ListOfferVersions { asset_id } => {
+            let resp = admin
+                .list_offer_versions(with_auth(
+                    &args.auth_token,
+                    ListOfferVersionsRequest { asset_id },
+                ))
+                .await
+                .context("ListOfferVersions")?
+                .into_inner();
+
+            json!({
+              "versions": resp.versions.into_iter().map(offer_version_json).collect::<Vec<_>>(),
+            })
+        }
+        Command::CreateApiKey { role, owner } => {
+            let resp = admin
+                .create_api_key(with_auth(
+                    &args.auth_token,
+                    CreateApiKeyRequest {
+                        role: role.to_proto() as i32,
+                        owner,
+                    },
+                ))
+                .await
+                .context("CreateApiKey")?
+                .into_inner();
+
+            json!({
+              "token": resp.token,
+              "key": resp.key.map(api_key_json),
+            })
+        }
+        Command::RevokeApiKey { key_id } => {
+            let key = admin
+                .revoke_api_key(with_auth(&args.auth_token, RevokeApiKeyRequest { key_id }))
+                .await
+                .context("RevokeApiKey")?
+                .into_inner();
+
+            api_key_json(key)
+        }
+        Command::ListApiKeys => {
+            let resp = admin
+                .list_api_keys(with_auth(&args.auth_token, ListApiKeysRequest {}))
+                .await
+                .context("ListApiKeys")?
+                .into_inner();
+
+            json!({
+              "keys": resp.keys.into_iter().map(api_key_json).collect::<Vec<_>>(),
+            })
+        }
         Command::CreateQuote {
             direction,
             asset_id,
@@ -116,31 +485,7 @@ async fn main() -> Result<()> {
                 .context("CreateQuote")?
                 .into_inner();
 
-            json!({
-              "quote_id": quote.quote_id,
-              "offer_id": quote.offer_id,
-              "asset_id": quote.asset_id,
-              "asset_amount": quote.asset_amount,
-              "min_funding_confs": quote.min_funding_confs,
-              "total_price_msat": quote.total_price_msat,
-              "direction": format!("{direction:?}"),
-              "parties": quote.parties.map(|p| json!({
-                "ln_payer": SwapRole::try_from(p.ln_payer).ok().map(|r| format!("{r:?}")),
-                "ln_payee": SwapRole::try_from(p.ln_payee).ok().map(|r| format!("{r:?}")),
-                "liquid_funder": SwapRole::try_from(p.liquid_funder).ok().map(|r| format!("{r:?}")),
-                "liquid_claimer": SwapRole::try_from(p.liquid_claimer).ok().map(|r| format!("{r:?}")),
-                "liquid_refunder": SwapRole::try_from(p.liquid_refunder).ok().map(|r| format!("{r:?}")),
-              })),
-              "offer": quote.offer.map(|o| json!({
-                "asset_id": o.asset_id,
-                "supported_directions": o.supported_directions.iter().filter_map(|d| SwapDirection::try_from(*d).ok()).map(|d| format!("{d:?}")).collect::<Vec<_>>(),
-                "price_msat_per_asset_unit": o.price_msat_per_asset_unit,
-                "fee_subsidy_sats": o.fee_subsidy_sats,
-                "refund_delta_blocks": o.refund_delta_blocks,
-                "invoice_expiry_secs": o.invoice_expiry_secs,
-                "max_min_funding_confs": o.max_min_funding_confs,
-              })),
-            })
+            quote_json(quote)
         }
         Command::GetQuote { quote_id } => {
             let quote = client
@@ -149,37 +494,47 @@ async fn main() -> Result<()> {
                 .context("GetQuote")?
                 .into_inner();
 
+            quote_json(quote)
+        }
+        Command::ListQuotes {
+            direction,
+            asset_id,
+            created_after,
+            created_before,
+            page_size,
+            page_token,
+        } => {
+            let resp = client
+                .list_quotes(with_auth(
+                    &args.auth_token,
+                    ListQuotesRequest {
+                        page_size,
+                        page_token,
+                        direction: direction.map(|d| d.to_proto() as i32).unwrap_or_default(),
+                        asset_id: asset_id.unwrap_or_default(),
+                        created_after: created_after.unwrap_or_default(),
+                        created_before: created_before.unwrap_or_default(),
+                    },
+                ))
+                .await
+                .context("ListQuotes")?
+                .into_inner();
+
             json!({
-              "quote_id": quote.quote_id,
-              "offer_id": quote.offer_id,
-              "asset_id": quote.asset_id,
-              "asset_amount": quote.asset_amount,
-              "min_funding_confs": quote.min_funding_confs,
-              "total_price_msat": quote.total_price_msat,
-              "direction": SwapDirection::try_from(quote.direction).ok().map(|d| format!("{d:?}")),
-              "parties": quote.parties.map(|p| json!({
-                "ln_payer": SwapRole::try_from(p.ln_payer).ok().map(|r| format!("{r:?}")),
-                "ln_payee": SwapRole::try_from(p.ln_payee).ok().map(|r| format!("{r:?}")),
-                "liquid_funder": SwapRole::try_from(p.liquid_funder).ok().map(|r| format!("{r:?}")),
-                "liquid_claimer": SwapRole::try_from(p.liquid_claimer).ok().map(|r| format!("{r:?}")),
-                "liquid_refunder": SwapRole::try_from(p.liquid_refunder).ok().map(|r| format!("{r:?}")),
-              })),
-              "offer": quote.offer.map(|o| json!({
-                "asset_id": o.asset_id,
-                "supported_directions": o.supported_directions.iter().filter_map(|d| SwapDirection::try_from(*d).ok()).map(|d| format!("{d:?}")).collect::<Vec<_>>(),
-                "price_msat_per_asset_unit": o.price_msat_per_asset_unit,
-                "fee_subsidy_sats": o.fee_subsidy_sats,
-                "refund_delta_blocks": o.refund_delta_blocks,
-                "invoice_expiry_secs": o.invoice_expiry_secs,
-                "max_min_funding_confs": o.max_min_funding_confs,
-              })),
+              "quotes": resp.quotes.into_iter().map(quote_json).collect::<Vec<_>>(),
+              "next_page_token": (!resp.next_page_token.is_empty()).then_some(resp.next_page_token),
             })
         }
         Command::CreateSwap {
             quote_id,
             buyer_liquid_address,
             buyer_bolt11_invoice,
+            buyer_claim_pubkey,
+            htlc_version,
+            confidential,
         } => {
+            let buyer_claim_pubkey =
+                hex::decode(buyer_claim_pubkey.trim()).context("decode buyer_claim_pubkey")?;
             let swap = client
                 .create_swap(with_auth(
                     &args.auth_token,
@@ -187,6 +542,9 @@ async fn main() -> Result<()> {
                         quote_id,
                         buyer_liquid_address,
                         buyer_bolt11_invoice,
+                        buyer_claim_pubkey,
+                        htlc_version: htlc_version.to_proto() as i32,
+                        confidential,
                     },
                 ))
                 .await
@@ -204,6 +562,53 @@ async fn main() -> Result<()> {
 
             swap_json(swap)
         }
+        Command::ListSwaps {
+            status,
+            direction,
+            asset_id,
+            created_after,
+            created_before,
+            page_size,
+            page_token,
+        } => {
+            let resp = client
+                .list_swaps(with_auth(
+                    &args.auth_token,
+                    ListSwapsRequest {
+                        page_size,
+                        page_token,
+                        status: status.map(|s| s.to_proto() as i32).unwrap_or_default(),
+                        direction: direction.map(|d| d.to_proto() as i32).unwrap_or_default(),
+                        asset_id: asset_id.unwrap_or_default(),
+                        created_after: created_after.unwrap_or_default(),
+                        created_before: created_before.unwrap_or_default(),
+                    },
+                ))
+                .await
+                .context("ListSwaps")?
+                .into_inner();
+
+            json!({
+              "swaps": resp.swaps.into_iter().map(swap_json).collect::<Vec<_>>(),
+              "next_page_token": (!resp.next_page_token.is_empty()).then_some(resp.next_page_token),
+            })
+        }
+        Command::Watch { swap_id } => {
+            let mut events = client
+                .watch_swap(with_auth(&args.auth_token, WatchSwapRequest { swap_id }))
+                .await
+                .context("WatchSwap")?
+                .into_inner();
+
+            while let Some(event) = events.message().await.context("WatchSwap")? {
+                let out = json!({
+                  "funding_confirmations": event.funding_confirmations,
+                  "swap": event.swap.map(swap_json),
+                });
+                println!("{}", serde_json::to_string(&out)?);
+            }
+            return Ok(());
+        }
         Command::CreateLightningPayment {
             swap_id,
             payment_timeout_secs,
@@ -245,12 +650,326 @@ async fn main() -> Result<()> {
               "claim_txid": resp.claim_txid,
             })
         }
+        Command::ClaimLocal {
+            swap_id,
+            claim_secret_key,
+            preimage,
+            destination_address,
+            claim_fee_sats,
+            fee_rate_sat_vb,
+            policy_asset,
+            electrum_url,
+        } => {
+            let swap = client
+                .get_swap(with_auth(&args.auth_token, GetSwapRequest { swap_id }))
+                .await
+                .context("GetSwap")?
+                .into_inner();
+            let liquid = swap.liquid.context("swap is missing liquid details")?;
+
+            let claimer_secret_key = SecretKey::from_slice(
+                &hex::decode(claim_secret_key.trim()).context("decode claim_secret_key")?,
+            )
+            .context("parse claim_secret_key")?;
+            let preimage: [u8; 32] = hex::decode(preimage.trim())
+                .context("decode preimage")?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("preimage must be 32 bytes"))?;
+
+            let mut chain = electrum_url
+                .map(|electrum_url| ElectrumChain::new(&electrum_url).context("connect electrum"))
+                .transpose()?;
+            let claimer_pubkey = claimer_secret_key.public_key(&Secp256k1::new());
+            let (payment_hash, htlc) = if liquid.htlc_version == HtlcVersion::Taproot as i32 {
+                let spec = TaprootHtlcSpec::parse_encoded_leaves(&liquid.witness_script)
+                    .context("parse taproot leaves")?;
+                anyhow::ensure!(
+                    claimer_pubkey.x_only_public_key().0 == spec.claimer_pubkey,
+                    "claim_secret_key does not match the HTLC claim key"
+                );
+                let genesis_hash = chain
+                    .as_mut()
+                    .context("--electrum-url is required to claim a taproot HTLC")?
+                    .genesis_hash()
+                    .context("get genesis hash")?;
+                (spec.payment_hash, ClaimHtlc::Taproot { spec, genesis_hash })
+            } else {
+                let witness_script = Script::from(liquid.witness_script);
+                let spec = HtlcSpec::parse_witness_script(&witness_script)
+                    .context("parse witness_script")?;
+                anyhow::ensure!(
+                    pubkey_hash160(&claimer_pubkey.serialize()) == spec.claimer_pubkey_hash160,
+                    "claim_secret_key does not match the HTLC claim key"
+                );
+                (spec.payment_hash, ClaimHtlc::P2wsh(witness_script))
+            };
+            anyhow::ensure!(
+                sha256_preimage(&preimage) == payment_hash,
+                "preimage does not match the HTLC payment hash"
+            );
+
+            let policy_asset = AssetId::from_str(&policy_asset).context("parse policy_asset")?;
+            let funding_txid =
+                Txid::from_str(&liquid.funding_txid).context("parse funding_txid")?;
+            let blinding = if liquid.blinding_key.is_empty() {
+                None
+            } else {
+                let chain = chain
+                    .as_ref()
+                    .context("--electrum-url is required to claim a confidential HTLC")?;
+                Some(
+                    HtlcBlinding::from_funding_tx(
+                        &chain
+                            .get_transaction(&funding_txid)
+                            .context("fetch funding tx")?,
+                        liquid.asset_vout,
+                        liquid.lbtc_vout,
+                        ZkpSecretKey::from_slice(&liquid.blinding_key)
+                            .context("parse blinding_key")?,
+                    )
+                    .context("load htlc blinding")?,
+                )
+            };
+            let funding = HtlcFunding {
+                funding_txid,
+                asset_vout: liquid.asset_vout,
+                lbtc_vout: liquid.lbtc_vout,
+                asset_id: AssetId::from_str(&liquid.asset_id).context("parse asset_id")?,
+                asset_amount: liquid.asset_amount,
+                policy_asset,
+                fee_subsidy_sats: liquid.fee_subsidy_sats,
+                blinding,
+            };
+            let destination_address =
+                Address::from_str(&destination_address).context("parse destination_address")?;
+
+            let build = |fee_sats| match &htlc {
+                ClaimHtlc::P2wsh(witness_script) => claim_tx_from_witness_script(
+                    witness_script,
+                    &funding,
+                    &destination_address,
+                    &claimer_secret_key,
+                    preimage,
+                    fee_sats,
+                )
+                .context("build claim tx"),
+                ClaimHtlc::Taproot { spec, genesis_hash } => taproot_claim_tx_script_path(
+                    spec,
+                    &funding,
+                    &destination_address,
+                    &claimer_secret_key,
+                    preimage,
+                    fee_sats,
+                    *genesis_hash,
+                )
+                .context("build taproot claim tx"),
+            };
+            let tx = match claim_fee_sats {
+                Some(claim_fee_sats) => build(claim_fee_sats)?,
+                None => {
+                    let estimated = match &chain {
+                        Some(chain) => chain
+                            .estimate_fee_rate(FeeEstimator::default().target_blocks)
+                            .context("estimate fee rate")?,
+                        None => None,
+                    };
+                    let fee_rate = fee_rate_sat_vb
+                        .or(estimated)
+                        .unwrap_or(MIN_FEE_RATE_SAT_VB)
+                        .max(MIN_FEE_RATE_SAT_VB);
+                    build_at_fee_rate(fee_rate, build)?
+                }
+            };
+
+            let claim_txid = match chain {
+                Some(chain) => Some(
+                    chain
+                        .broadcast(&tx)
+                        .context("broadcast claim tx")?
+                        .to_string(),
+                ),
+                None => None,
+            };
+
+            json!({
+              "claim_tx_hex": lwk_wollet::elements::encode::serialize_hex(&tx),
+              "claim_txid": claim_txid.unwrap_or_else(|| tx.txid().to_string()),
+              "broadcast": claim_txid.is_some(),
+            })
+        }
+        Command::VerifySwap {
+            swap_id,
+            electrum_url,
+            user_liquid_address,
+            claim_pubkey,
+            policy_asset,
+            asset_id,
+            asset_amount,
+            total_price_msat,
+            fee_subsidy_sats,
+            min_refund_blocks,
+            pay,
+            payment_timeout_secs,
+        } => {
+            let swap = client
+                .get_swap(with_auth(
+                    &args.auth_token,
+                    GetSwapRequest {
+                        swap_id: swap_id.clone(),
+                    },
+                ))
+                .await
+                .context("GetSwap")?
+                .into_inner();
+            let user_pubkey_hash160 = match (claim_pubkey, user_liquid_address) {
+                (Some(claim_pubkey), _) => pubkey_hash160(
+                    &hex::decode(claim_pubkey.trim()).context("decode claim_pubkey")?,
+                ),
+                (None, Some(address)) => pubkey_hash160_from_p2wpkh_address(
+                    &Address::from_str(&address).context("parse user_liquid_address")?,
+                )
+                .context("user_liquid_address must be a P2WPKH address")?,
+                (None, None) => {
+                    anyhow::bail!("either --claim-pubkey or --user-liquid-address is required")
+                }
+            };
+            let policy_asset = AssetId::from_str(&policy_asset).context("parse policy_asset")?;
+
+            let mut chain = ElectrumChain::new(&electrum_url).context("connect electrum")?;
+            let tip_height = chain.tip_height().context("get tip height")?;
+            let funding_tx = chain
+                .get_transaction(&verify::funding_txid(&swap)?)
+                .context("fetch funding tx")?;
+
+            let expected = SwapExpectations {
+                user_pubkey_hash160,
+                asset_id: AssetId::from_str(&asset_id).context("parse asset_id")?,
+                asset_amount,
+                total_price_msat,
+                fee_subsidy_sats,
+                policy_asset,
+                tip_height,
+                min_refund_blocks,
+            };
+            let htlc = verify::verify_swap(&swap, &expected, &funding_tx)
+                .context("swap verification failed; refusing to pay")?;
+
+            let liquid = swap
+                .liquid
+                .as_ref()
+                .context("swap is missing liquid details")?;
+            let funding_txid = funding_tx.txid();
+            let funding_height = chain
+                .script_history(&funding_tx.output[liquid.asset_vout as usize].script_pubkey)
+                .context("get HTLC script history")?
+                .into_iter()
+                .find(|entry| entry.txid == funding_txid)
+                .and_then(|entry| entry.height);
+            let funding_confs = confirmations(funding_height, tip_height);
+            anyhow::ensure!(
+                funding_confs >= liquid.min_funding_confs,
+                "funding tx has {funding_confs} confirmations, need {}; refusing to pay",
+                liquid.min_funding_confs
+            );
+
+            let payment = if pay {
+                let resp = client
+                    .create_lightning_payment(with_auth(
+                        &args.auth_token,
+                        CreateLightningPaymentRequest {
+                            swap_id,
+                            payment_timeout_secs,
+                        },
+                    ))
+                    .await
+                    .context("CreateLightningPayment")?
+                    .into_inner();
+                Some(json!({
+                  "payment_id": resp.payment_id,
+                  "preimage_hex": hex::encode(resp.preimage),
+                }))
+            } else {
+                None
+            };
+
+            json!({
+              "verified": true,
+              "payment_hash": hex::encode(htlc.payment_hash),
+              "refund_lock_height": htlc.refund_lock_height,
+              "tip_height": tip_height,
+              "funding_confirmations": funding_confs,
+              "payment": payment,
+            })
+        }
     };
 
     println!("{}", serde_json::to_string_pretty(&out)?);
     Ok(())
 }
 
+fn quote_json(quote: ln_liquid_swap::proto::v1::Quote) -> serde_json::Value {
+    json!({
+      "quote_id": quote.quote_id,
+      "offer_id": quote.offer_id,
+      "asset_id": quote.asset_id,
+      "asset_amount": quote.asset_amount,
+      "min_funding_confs": quote.min_funding_confs,
+      "total_price_msat": quote.total_price_msat,
+      "direction": SwapDirection::try_from(quote.direction).ok().map(|d| format!("{d:?}")),
+      "created_at": quote.created_at,
+      "expires_at": quote.expires_at,
+      "price_source": quote.price_source,
+      "source_price_msat_per_asset_unit": quote.source_price_msat_per_asset_unit,
+      "spread_bps": quote.spread_bps,
+      "priced_at": quote.priced_at,
+      "parties": quote.parties.map(|p| json!({
+        "ln_payer": SwapRole::try_from(p.ln_payer).ok().map(|r| format!("{r:?}")),
+        "ln_payee": SwapRole::try_from(p.ln_payee).ok().map(|r| format!("{r:?}")),
+        "liquid_funder": SwapRole::try_from(p.liquid_funder).ok().map(|r| format!("{r:?}")),
+        "liquid_claimer": SwapRole::try_from(p.liquid_claimer).ok().map(|r| format!("{r:?}")),
+        "liquid_refunder": SwapRole::try_from(p.liquid_refunder).ok().map(|r| format!("{r:?}")),
+      })),
+      "offer": quote.offer.map(offer_json),
+    })
+}
+
+fn offer_version_json(version: ln_liquid_swap::proto::v1::OfferVersion) -> serde_json::Value {
+    json!({
+      "asset_id": version.asset_id,
+      "version": version.version,
+      "offer_id": version.offer_id,
+      "paused": version.paused,
+      "created_at": version.created_at,
+      "offer": version.offer.map(offer_json),
+    })
+}
+
+fn api_key_json(key: ln_liquid_swap::proto::v1::ApiKey) -> serde_json::Value {
+    json!({
+      "key_id": key.key_id,
+      "role": SwapRole::try_from(key.role).ok().map(|r| format!("{r:?}")),
+      "owner": key.owner,
+      "created_at": key.created_at,
+      "revoked_at": (key.revoked_at > 0).then_some(key.revoked_at),
+    })
+}
+
+fn offer_json(offer: ln_liquid_swap::proto::v1::Offer) -> serde_json::Value {
+    json!({
+      "asset_id": offer.asset_id,
+      "supported_directions": offer.supported_directions.iter().filter_map(|d| SwapDirection::try_from(*d).ok()).map(|d| format!("{d:?}")).collect::<Vec<_>>(),
+      "price_msat_per_asset_unit": offer.price_msat_per_asset_unit,
+      "fee_subsidy_sats": offer.fee_subsidy_sats,
+      "refund_delta_blocks": offer.refund_delta_blocks,
+      "invoice_expiry_secs": offer.invoice_expiry_secs,
+      "max_min_funding_confs": offer.max_min_funding_confs,
+      "min_asset_amount": offer.min_asset_amount,
+      "max_asset_amount": offer.max_asset_amount,
+      "max_open_notional_msat": offer.max_open_notional_msat,
+      "max_buyer_open_notional_msat": offer.max_buyer_open_notional_msat,
+    })
+}
+
 fn swap_json(swap: ln_liquid_swap::proto::v1::Swap) -> serde_json::Value {
     let status_str = SwapStatus::try_from(swap.status)
         .ok()
@@ -266,6 +985,7 @@ fn swap_json(swap: ln_liquid_swap::proto::v1::Swap) -> serde_json::Value {
       "quote_id": swap.quote_id,
       "status": status_str,
       "direction": direction_str,
+      "created_at": swap.created_at,
       "parties": swap.parties.map(|p| json!({
         "ln_payer": SwapRole::try_from(p.ln_payer).ok().map(|r| format!("{r:?}")),
         "ln_payee": SwapRole::try_from(p.ln_payee).ok().map(|r| format!("{r:?}")),
@@ -275,6 +995,7 @@ fn swap_json(swap: ln_liquid_swap::proto::v1::Swap) -> serde_json::Value {
       })),
       "bolt11_invoice": swap.bolt11_invoice,
       "payment_hash": swap.payment_hash,
+      "refund_txid": (!swap.refund_txid.is_empty()).then_some(swap.refund_txid),
       "liquid": swap.liquid.map(|l| json!({
         "asset_id": l.asset_id,
         "asset_amount": l.asset_amount,
@@ -285,6 +1006,9 @@ fn swap_json(swap: ln_liquid_swap::proto::v1::Swap) -> serde_json::Value {
         "asset_vout": l.asset_vout,
         "lbtc_vout": l.lbtc_vout,
         "min_funding_confs": l.min_funding_confs,
+        "buyer_claim_pubkey": (!l.buyer_claim_pubkey.is_empty()).then(|| hex::encode(&l.buyer_claim_pubkey)),
+        "htlc_version": HtlcVersion::try_from(l.htlc_version).ok().map(|v| format!("{v:?}")),
+        "blinding_key": (!l.blinding_key.is_empty()).then(|| hex::encode(&l.blinding_key)),
       })),
     })
 }
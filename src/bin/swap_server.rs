@@ -6,14 +6,20 @@ use std::time::Duration;
 
 use anyhow::{Context as _, Result};
 use clap::Parser as _;
+use ln_liquid_swap::lightning::backend::LightningBackend;
 use ln_liquid_swap::lightning::ldk::LdkLightningClient;
-use ln_liquid_swap::liquid::htlc::{HtlcFunding, refund_tx_from_witness_script};
-use ln_liquid_swap::liquid::keys::derive_secret_key;
+use ln_liquid_swap::liquid::fee::{FeeEstimator, MIN_FEE_RATE_SAT_VB};
 use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1::admin_service_server::AdminServiceServer;
 use ln_liquid_swap::proto::v1::swap_service_server::SwapServiceServer;
+use ln_liquid_swap::swap::SwapDirection;
+use ln_liquid_swap::swap::admin::AdminServiceImpl;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::{
+    FilePriceSource, HttpPriceSource, PriceSource, PricingPolicy, SizeTier,
+};
 use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
 use ln_liquid_swap::swap::store::SqliteStore;
-use ln_liquid_swap::swap::{SwapDirection, SwapStatus};
 use lwk_wollet::ElementsNetwork;
 use tonic::transport::Server;
 
@@ -40,8 +46,11 @@ struct Args {
     #[arg(long)]
     slip77: String,
 
-    #[arg(long)]
-    sell_asset_id: String,
+    #[arg(long, conflicts_with = "sell_asset_id")]
+    offers_file: Option<PathBuf>,
+
+    #[arg(long, required_unless_present = "offers_file")]
+    sell_asset_id: Option<String>,
 
     #[arg(long, default_value_t = 1)]
     price_msat_per_asset_unit: u64,
@@ -52,9 +61,51 @@ struct Args {
     #[arg(long, default_value_t = 144)]
     refund_delta_blocks: u32,
 
+    #[arg(long, default_value_t = 0)]
+    min_asset_amount: u64,
+
+    #[arg(long, default_value_t = 0)]
+    max_asset_amount: u64,
+
+    #[arg(long, conflicts_with = "price_oracle_url")]
+    price_file: Option<PathBuf>,
+
+    #[arg(long)]
+    price_oracle_url: Option<String>,
+
+    #[arg(long, default_value_t = 0)]
+    spread_bps: u32,
+
+    #[arg(long = "size-tier")]
+    size_tiers: Vec<SizeTier>,
+
+    #[arg(long, default_value_t = 0)]
+    min_notional_msat: u64,
+
+    #[arg(long, default_value_t = 0)]
+    max_notional_msat: u64,
+
+    #[arg(long, default_value_t = 0)]
+    max_price_age_secs: u64,
+
+    #[arg(long, default_value_t = 0)]
+    max_open_notional_msat: u64,
+
+    #[arg(long, default_value_t = 0)]
+    max_buyer_open_notional_msat: u64,
+
     #[arg(long, default_value_t = 3600)]
     invoice_expiry_secs: u32,
 
+    #[arg(long, default_value_t = 10)]
+    min_timelock_margin_blocks: u32,
+
+    #[arg(long, default_value_t = 10)]
+    payment_safety_blocks: u32,
+
+    #[arg(long, default_value_t = 600)]
+    quote_ttl_secs: u32,
+
     #[arg(long, default_value_t = 0)]
     seller_key_index: u32,
 
@@ -64,14 +115,29 @@ struct Args {
     #[arg(long, default_value_t = 5)]
     refund_poll_interval_secs: u64,
 
-    #[arg(long, default_value_t = 500)]
-    refund_fee_sats: u64,
+    #[arg(long, default_value_t = 2)]
+    fee_target_blocks: usize,
+
+    #[arg(long, default_value_t = 0.1)]
+    fallback_fee_rate_sat_vb: f64,
+
+    #[arg(long, default_value_t = 2)]
+    settlement_poll_interval_secs: u64,
+
+    #[arg(long, default_value_t = 5)]
+    spend_poll_interval_secs: u64,
+
+    #[arg(long, default_value_t = 5)]
+    executor_poll_interval_secs: u64,
 
     #[arg(long)]
     seller_token: String,
 
     #[arg(long)]
-    buyer_token: String,
+    buyer_token: Option<String>,
+
+    #[arg(long)]
+    admin_token: Option<String>,
 }
 
 #[tokio::main]
@@ -85,13 +151,32 @@ async fn main() -> Result<()> {
         !args.seller_token.trim().is_empty(),
         "seller_token must not be empty"
     );
+    let buyer_token = args.buyer_token.clone().unwrap_or_default();
+    if args.buyer_token.is_some() {
+        anyhow::ensure!(
+            !buyer_token.trim().is_empty(),
+            "buyer_token must not be empty"
+        );
+        anyhow::ensure!(
+            args.seller_token != buyer_token,
+            "seller_token and buyer_token must be different"
+        );
+    }
+    let admin_token = args
+        .admin_token
+        .clone()
+        .unwrap_or_else(|| args.seller_token.clone());
     anyhow::ensure!(
-        !args.buyer_token.trim().is_empty(),
-        "buyer_token must not be empty"
+        !admin_token.trim().is_empty(),
+        "admin_token must not be empty"
     );
     anyhow::ensure!(
-        args.seller_token != args.buyer_token,
-        "seller_token and buyer_token must be different"
+        admin_token != buyer_token,
+        "admin_token and buyer_token must be different"
+    );
+    anyhow::ensure!(
+        args.fallback_fee_rate_sat_vb >= MIN_FEE_RATE_SAT_VB,
+        "fallback_fee_rate_sat_vb must be at least {MIN_FEE_RATE_SAT_VB}"
     );
 
     std::fs::create_dir_all(&args.wallet_dir).context("create wallet_dir")?;
@@ -99,8 +184,37 @@ async fn main() -> Result<()> {
         std::fs::create_dir_all(parent).context("create store parent dir")?;
     }
 
-    let sell_asset_id = lwk_wollet::elements::AssetId::from_str(&args.sell_asset_id)
-        .context("parse sell_asset_id")?;
+    let offers = match (&args.offers_file, &args.sell_asset_id) {
+        (Some(path), _) => OfferBook::from_json_file(path).context("load offers")?,
+        (None, Some(sell_asset_id)) => OfferBook::new([AssetOffer {
+            asset_id: lwk_wollet::elements::AssetId::from_str(sell_asset_id)
+                .context("parse sell_asset_id")?,
+            supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+            price_msat_per_asset_unit: args.price_msat_per_asset_unit,
+            fee_subsidy_sats: args.fee_subsidy_sats,
+            refund_delta_blocks: args.refund_delta_blocks,
+            min_asset_amount: args.min_asset_amount,
+            max_asset_amount: args.max_asset_amount,
+        }])?,
+        (None, None) => anyhow::bail!("either offers_file or sell_asset_id is required"),
+    };
+
+    let pricing = PricingPolicy {
+        spread_bps: args.spread_bps,
+        size_tiers: args.size_tiers.clone(),
+        min_notional_msat: args.min_notional_msat,
+        max_notional_msat: args.max_notional_msat,
+        max_price_age_secs: args.max_price_age_secs,
+    };
+    pricing.validate().context("validate pricing policy")?;
+    let price_source: Option<Arc<dyn PriceSource>> =
+        match (&args.price_file, &args.price_oracle_url) {
+            (Some(path), _) => Some(Arc::new(FilePriceSource::new(path))),
+            (None, Some(url)) => Some(Arc::new(
+                HttpPriceSource::new(url).context("parse price_oracle_url")?,
+            )),
+            (None, None) => None,
+        };
 
     let network = ElementsNetwork::default_regtest();
     let wallet = LiquidWallet::new(
@@ -136,34 +250,61 @@ async fn main() -> Result<()> {
     let store = Arc::new(Mutex::new(store));
 
     let cfg = SwapServiceConfig {
-        sell_asset_id,
-        price_msat_per_asset_unit: args.price_msat_per_asset_unit,
-        fee_subsidy_sats: args.fee_subsidy_sats,
-        refund_delta_blocks: args.refund_delta_blocks,
+        offers,
         invoice_expiry_secs: args.invoice_expiry_secs,
+        min_timelock_margin_blocks: args.min_timelock_margin_blocks,
+        payment_safety_blocks: args.payment_safety_blocks,
+        quote_ttl_secs: args.quote_ttl_secs,
+        pricing,
+        check_inventory: true,
+        max_open_notional_msat: args.max_open_notional_msat,
+        max_buyer_open_notional_msat: args.max_buyer_open_notional_msat,
         seller_key_index: args.seller_key_index,
         buyer_key_index: args.buyer_key_index,
         seller_token: args.seller_token,
-        buyer_token: args.buyer_token,
+        buyer_token,
     };
+    for offer in cfg.offers.iter() {
+        cfg.check_offer_timelock(offer)
+            .context("check offer timelock")?;
+    }
 
-    let ln = LdkLightningClient::new(args.ldk_rest_addr);
+    let ln: Arc<dyn LightningBackend> = Arc::new(LdkLightningClient::new(args.ldk_rest_addr));
 
-    let svc = SwapServiceImpl::new(cfg.clone(), ln, wallet.clone(), store.clone());
+    let mut svc = SwapServiceImpl::new(cfg, ln, wallet, store.clone());
+    if let Some(source) = price_source {
+        svc = svc.with_price_source(source);
+    }
+    svc = svc.with_fee_estimator(FeeEstimator {
+        target_blocks: args.fee_target_blocks,
+        fallback_sat_per_vb: args.fallback_fee_rate_sat_vb,
+    });
 
     spawn_refund_worker(
-        wallet.clone(),
-        store.clone(),
-        cfg.seller_key_index,
-        cfg.buyer_key_index,
+        svc.clone(),
         Duration::from_secs(args.refund_poll_interval_secs),
-        args.refund_fee_sats,
+    );
+
+    spawn_settlement_watcher(
+        svc.clone(),
+        Duration::from_secs(args.settlement_poll_interval_secs),
+    );
+    spawn_spend_watcher(
+        svc.clone(),
+        Duration::from_secs(args.spend_poll_interval_secs),
+    );
+    spawn_swap_executor(
+        svc.clone(),
+        Duration::from_secs(args.executor_poll_interval_secs),
     );
 
     tracing::info!(%listen_addr, "starting swap gRPC server");
 
+    let admin = AdminServiceImpl::new(svc.clone(), store, admin_token);
+
     Server::builder()
         .add_service(SwapServiceServer::new(svc))
+        .add_service(AdminServiceServer::new(admin))
         .serve(listen_addr)
         .await
         .context("serve gRPC")?;
@@ -171,30 +312,11 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn spawn_refund_worker(
-    wallet: Arc<Mutex<LiquidWallet>>,
-    store: Arc<Mutex<SqliteStore>>,
-    seller_key_index: u32,
-    buyer_key_index: u32,
-    poll_interval: Duration,
-    fee_sats: u64,
-) {
+fn spawn_settlement_watcher(svc: SwapServiceImpl, poll_interval: Duration) {
     tokio::spawn(async move {
         loop {
-            match tokio::task::spawn_blocking({
-                let wallet = wallet.clone();
-                let store = store.clone();
-                move || refund_once(wallet, store, seller_key_index, buyer_key_index, fee_sats)
-            })
-            .await
-            {
-                Ok(Ok(())) => {}
-                Ok(Err(err)) => {
-                    tracing::warn!(error = %err, "refund worker error");
-                }
-                Err(err) => {
-                    tracing::warn!(error = %err, "refund worker join error");
-                }
+            if let Err(err) = svc.sync_inbound_settlements().await {
+                tracing::warn!(error = %err, "settlement watcher error");
             }
 
             tokio::time::sleep(poll_interval).await;
@@ -202,91 +324,37 @@ fn spawn_refund_worker(
     });
 }
 
-fn refund_once(
-    wallet: Arc<Mutex<LiquidWallet>>,
-    store: Arc<Mutex<SqliteStore>>,
-    seller_key_index: u32,
-    buyer_key_index: u32,
-    fee_sats: u64,
-) -> Result<()> {
-    let mut wallet = wallet.lock().expect("wallet mutex poisoned");
-    wallet.sync().context("sync wallet")?;
-    let tip_height = wallet.tip_height();
-
-    let swaps = store
-        .lock()
-        .expect("store mutex poisoned")
-        .list_swaps()
-        .context("list swaps")?;
-    for s in swaps {
-        if !matches!(s.status, SwapStatus::Created | SwapStatus::Funded) {
-            continue;
-        }
-        if tip_height < s.refund_lock_height {
-            continue;
-        }
+fn spawn_spend_watcher(svc: SwapServiceImpl, poll_interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = svc.sync_htlc_spends().await {
+                tracing::warn!(error = %err, "spend watcher error");
+            }
 
-        let funding_txid =
-            lwk_wollet::elements::Txid::from_str(&s.funding_txid).context("parse funding_txid")?;
-        let asset_id =
-            lwk_wollet::elements::AssetId::from_str(&s.asset_id).context("parse asset_id")?;
-        let policy_asset = wallet.policy_asset();
-        let witness_script: lwk_wollet::elements::Script = s
-            .witness_script_hex
-            .parse()
-            .map_err(|e| anyhow::anyhow!("parse witness_script: {e:?}"))?;
-
-        let funding = HtlcFunding {
-            funding_txid,
-            asset_vout: s.asset_vout,
-            lbtc_vout: s.lbtc_vout,
-            asset_id,
-            asset_amount: s.asset_amount,
-            policy_asset,
-            fee_subsidy_sats: s.fee_subsidy_sats,
-        };
+            tokio::time::sleep(poll_interval).await;
+        }
+    });
+}
 
-        let (refunder_key_index, expected_address) = match s.direction {
-            SwapDirection::LnToLiquid => (seller_key_index, None),
-            SwapDirection::LiquidToLn => (buyer_key_index, Some(s.buyer_liquid_address.as_str())),
-        };
+fn spawn_swap_executor(svc: SwapServiceImpl, poll_interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = svc.drive_swaps().await {
+                tracing::warn!(error = %err, "swap executor error");
+            }
 
-        let refunder_receive = wallet
-            .address_at(refunder_key_index)
-            .context("get refunder receive address")?;
-        if let Some(expected_address) = expected_address {
-            anyhow::ensure!(
-                refunder_receive.to_string() == expected_address,
-                "buyer_liquid_address mismatch"
-            );
+            tokio::time::sleep(poll_interval).await;
         }
+    });
+}
 
-        let refunder_secret_key = derive_secret_key(wallet.signer(), refunder_key_index)
-            .context("derive refunder secret key")?;
-
-        let tx = refund_tx_from_witness_script(
-            &witness_script,
-            s.refund_lock_height,
-            &funding,
-            &refunder_receive,
-            &refunder_secret_key,
-            fee_sats,
-        )
-        .context("build refund tx")?;
-
-        match wallet.broadcast_transaction(&tx) {
-            Ok(txid) => {
-                tracing::info!(swap_id = %s.swap_id, refund_txid = %txid, "broadcast refund tx");
-                let mut store = store.lock().expect("store mutex poisoned");
-                store
-                    .update_swap_status(&s.swap_id, SwapStatus::Refunded)
-                    .context("update swap status (refunded)")?;
-            }
-            Err(err) => {
-                tracing::warn!(swap_id = %s.swap_id, error = %err, "refund broadcast failed");
+fn spawn_refund_worker(svc: SwapServiceImpl, poll_interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = svc.refund_expired_swaps().await {
+                tracing::warn!(error = %err, "refund worker error");
             }
+            tokio::time::sleep(poll_interval).await;
         }
-    }
-
-    Ok(())
+    });
 }
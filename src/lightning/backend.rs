@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+pub const PAYMENT_UPDATES_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentState {
+    Pending,
+    Succeeded([u8; 32]),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentUpdate {
+    pub payment_id: String,
+    pub state: PaymentState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundSettlement {
+    pub payment_id: String,
+    pub preimage: [u8; 32],
+}
+
+#[tonic::async_trait]
+pub trait LightningBackend: Send + Sync {
+    async fn create_invoice(
+        &self,
+        amount_msat: u64,
+        description: String,
+        expiry_secs: u32,
+    ) -> Result<String>;
+
+    async fn pay_invoice(&self, invoice: String) -> Result<String>;
+
+    /// State of the outbound payment `payment_id` started by `pay_invoice`, or `None` if unknown.
+    async fn payment_state(&self, payment_id: &str) -> Result<Option<PaymentState>>;
+
+    fn subscribe_payments(&self) -> broadcast::Receiver<PaymentUpdate>;
+
+    async fn inbound_settlement(
+        &self,
+        payment_hash: &[u8; 32],
+    ) -> Result<Option<InboundSettlement>>;
+}
+
+pub async fn wait_preimage(
+    ln: &dyn LightningBackend,
+    updates: &mut broadcast::Receiver<PaymentUpdate>,
+    payment_id: &str,
+    timeout: Duration,
+) -> Result<[u8; 32]> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut state = ln
+        .payment_state(payment_id)
+        .await
+        .context("get payment state")?;
+    loop {
+        match state {
+            Some(PaymentState::Succeeded(preimage)) => return Ok(preimage),
+            Some(PaymentState::Failed(reason)) => {
+                anyhow::bail!("payment failed: payment_id={payment_id} reason={reason}")
+            }
+            Some(PaymentState::Pending) | None => {}
+        }
+
+        state = match tokio::time::timeout_at(deadline, updates.recv()).await {
+            Err(_) => anyhow::bail!("timeout waiting for preimage: payment_id={payment_id}"),
+            Ok(Ok(update)) if update.payment_id == payment_id => Some(update.state),
+            Ok(Ok(_)) => continue,
+            Ok(Err(RecvError::Lagged(_))) => ln
+                .payment_state(payment_id)
+                .await
+                .context("get payment state")?,
+            Ok(Err(RecvError::Closed)) => {
+                anyhow::bail!("payment updates closed: payment_id={payment_id}")
+            }
+        };
+    }
+}
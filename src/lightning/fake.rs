@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use bitcoin::hashes::{Hash as _, sha256};
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lightning_invoice::{Currency, InvoiceBuilder, PaymentSecret};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::lightning::backend::{
+    InboundSettlement, LightningBackend, PAYMENT_UPDATES_CAPACITY, PaymentState, PaymentUpdate,
+};
+use crate::lightning::invoice::{is_expired_bolt11, payment_hash_from_bolt11};
+
+const FAKE_NODE_SECRET_KEY: [u8; 32] = [0x11; 32];
+const FAKE_MIN_FINAL_CLTV_EXPIRY_DELTA: u64 = 144;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FakePaymentBehavior {
+    #[default]
+    Succeed,
+    Fail(String),
+    Hang,
+    WrongPreimage,
+}
+
+#[derive(Debug, Default)]
+struct FakeLightningState {
+    preimages: HashMap<[u8; 32], [u8; 32]>,
+    payments: HashMap<String, PaymentState>,
+    received: HashMap<[u8; 32], InboundSettlement>,
+    paid_invoices: Vec<String>,
+    behavior: FakePaymentBehavior,
+}
+
+#[derive(Clone)]
+pub struct FakeLightningNode {
+    node_secret_key: SecretKey,
+    state: Arc<Mutex<FakeLightningState>>,
+    updates: broadcast::Sender<PaymentUpdate>,
+}
+
+impl Default for FakeLightningNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeLightningNode {
+    pub fn new() -> Self {
+        let (updates, _) = broadcast::channel(PAYMENT_UPDATES_CAPACITY);
+        Self {
+            node_secret_key: SecretKey::from_slice(&FAKE_NODE_SECRET_KEY)
+                .expect("fake node secret key must be valid"),
+            state: Arc::new(Mutex::new(FakeLightningState::default())),
+            updates,
+        }
+    }
+
+    pub fn set_pay_behavior(&self, behavior: FakePaymentBehavior) {
+        self.state.lock().expect("fake ln mutex poisoned").behavior = behavior;
+    }
+
+    pub fn learn_preimage(&self, preimage: [u8; 32]) {
+        let payment_hash = sha256::Hash::hash(&preimage).to_byte_array();
+        self.state
+            .lock()
+            .expect("fake ln mutex poisoned")
+            .preimages
+            .insert(payment_hash, preimage);
+    }
+
+    pub fn preimage_for(&self, payment_hash: &[u8; 32]) -> Option<[u8; 32]> {
+        self.state
+            .lock()
+            .expect("fake ln mutex poisoned")
+            .preimages
+            .get(payment_hash)
+            .copied()
+    }
+
+    pub fn paid_invoices(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .expect("fake ln mutex poisoned")
+            .paid_invoices
+            .clone()
+    }
+
+    pub fn receive_payment(&self, payment_hash: &[u8; 32]) -> Result<InboundSettlement> {
+        let mut state = self.state.lock().expect("fake ln mutex poisoned");
+        let preimage = *state
+            .preimages
+            .get(payment_hash)
+            .context("fake node did not issue an invoice for this payment hash")?;
+        let settlement = InboundSettlement {
+            payment_id: hex::encode(payment_hash),
+            preimage,
+        };
+        state.received.insert(*payment_hash, settlement.clone());
+        Ok(settlement)
+    }
+
+    pub fn set_payment_state(&self, payment_id: &str, state: PaymentState) {
+        self.state
+            .lock()
+            .expect("fake ln mutex poisoned")
+            .payments
+            .insert(payment_id.to_string(), state.clone());
+        let _ = self.updates.send(PaymentUpdate {
+            payment_id: payment_id.to_string(),
+            state,
+        });
+    }
+
+    fn sign_invoice(
+        &self,
+        payment_hash: [u8; 32],
+        amount_msat: u64,
+        description: String,
+        expiry_secs: u32,
+    ) -> Result<String> {
+        let secp = Secp256k1::new();
+        let payment_secret = sha256::Hash::hash(Uuid::new_v4().as_bytes()).to_byte_array();
+        let invoice = InvoiceBuilder::new(Currency::Regtest)
+            .description(description)
+            .payment_hash(sha256::Hash::from_byte_array(payment_hash))
+            .payment_secret(PaymentSecret(payment_secret))
+            .current_timestamp()
+            .min_final_cltv_expiry_delta(FAKE_MIN_FINAL_CLTV_EXPIRY_DELTA)
+            .amount_milli_satoshis(amount_msat)
+            .expiry_time(Duration::from_secs(u64::from(expiry_secs)))
+            .build_signed(|msg| secp.sign_ecdsa_recoverable(msg, &self.node_secret_key))
+            .map_err(|e| anyhow::anyhow!("sign fake BOLT11 invoice: {e:?}"))?;
+        Ok(invoice.to_string())
+    }
+}
+
+#[tonic::async_trait]
+impl LightningBackend for FakeLightningNode {
+    async fn create_invoice(
+        &self,
+        amount_msat: u64,
+        description: String,
+        expiry_secs: u32,
+    ) -> Result<String> {
+        let preimage = sha256::Hash::hash(Uuid::new_v4().as_bytes()).to_byte_array();
+        self.learn_preimage(preimage);
+        let payment_hash = sha256::Hash::hash(&preimage).to_byte_array();
+        self.sign_invoice(payment_hash, amount_msat, description, expiry_secs)
+    }
+
+    async fn pay_invoice(&self, invoice: String) -> Result<String> {
+        let payment_hash = payment_hash_from_bolt11(&invoice).context("parse invoice")?;
+        anyhow::ensure!(!is_expired_bolt11(&invoice)?, "invoice is expired");
+
+        let payment_state = {
+            let mut state = self.state.lock().expect("fake ln mutex poisoned");
+            state.paid_invoices.push(invoice);
+            match state.behavior.clone() {
+                FakePaymentBehavior::Succeed => match state.preimages.get(&payment_hash) {
+                    Some(preimage) => PaymentState::Succeeded(*preimage),
+                    None => PaymentState::Failed("no route to unknown payment hash".to_string()),
+                },
+                FakePaymentBehavior::Fail(reason) => PaymentState::Failed(reason),
+                FakePaymentBehavior::Hang => PaymentState::Pending,
+                FakePaymentBehavior::WrongPreimage => {
+                    PaymentState::Succeeded(sha256::Hash::hash(&payment_hash).to_byte_array())
+                }
+            }
+        };
+
+        let payment_id = Uuid::new_v4().to_string();
+        self.set_payment_state(&payment_id, payment_state);
+        Ok(payment_id)
+    }
+
+    async fn payment_state(&self, payment_id: &str) -> Result<Option<PaymentState>> {
+        Ok(self
+            .state
+            .lock()
+            .expect("fake ln mutex poisoned")
+            .payments
+            .get(payment_id)
+            .cloned())
+    }
+
+    fn subscribe_payments(&self) -> broadcast::Receiver<PaymentUpdate> {
+        self.updates.subscribe()
+    }
+
+    async fn inbound_settlement(
+        &self,
+        payment_hash: &[u8; 32],
+    ) -> Result<Option<InboundSettlement>> {
+        Ok(self
+            .state
+            .lock()
+            .expect("fake ln mutex poisoned")
+            .received
+            .get(payment_hash)
+            .cloned())
+    }
+}
@@ -27,3 +27,22 @@ pub fn is_expired_bolt11(invoice: &str) -> Result<bool> {
         .unwrap_or(Duration::from_secs(0));
     Ok(now >= expires_at)
 }
+
+/// Seconds until the invoice expires; `0` once it has, `u64::MAX` if it never does.
+pub fn secs_until_expiry_bolt11(invoice: &str) -> Result<u64> {
+    let invoice = Bolt11Invoice::from_str(invoice)
+        .map_err(|e| anyhow::anyhow!("parse BOLT11 invoice: {e:?}"))?;
+    let Some(expires_at) = invoice.expires_at() else {
+        return Ok(u64::MAX);
+    };
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0));
+    Ok(expires_at.saturating_sub(now).as_secs())
+}
+
+pub fn min_final_cltv_expiry_delta_from_bolt11(invoice: &str) -> Result<u64> {
+    let invoice = Bolt11Invoice::from_str(invoice)
+        .map_err(|e| anyhow::anyhow!("parse BOLT11 invoice: {e:?}"))?;
+    Ok(invoice.min_final_cltv_expiry_delta())
+}
@@ -1,26 +1,100 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use anyhow::{Context as _, Result};
 use ldk_server_client::client::LdkServerClient;
-use ldk_server_protos::api::{Bolt11ReceiveRequest, Bolt11SendRequest, ListPaymentsRequest};
+use ldk_server_protos::api::{Bolt11ReceiveRequest, Bolt11SendRequest, GetPaymentDetailsRequest};
 use ldk_server_protos::types::{
-    Bolt11InvoiceDescription, PaymentDirection, PaymentStatus, bolt11_invoice_description,
+    Bolt11InvoiceDescription, Payment, PaymentDirection, PaymentStatus, bolt11_invoice_description,
     payment_kind,
 };
+use tokio::sync::broadcast;
+
+use crate::lightning::backend::{
+    InboundSettlement, LightningBackend, PAYMENT_UPDATES_CAPACITY, PaymentState, PaymentUpdate,
+};
+
+const PAYMENT_TRACK_INTERVAL: Duration = Duration::from_millis(200);
 
 #[derive(Clone)]
 pub struct LdkLightningClient {
     client: LdkServerClient,
+    updates: broadcast::Sender<PaymentUpdate>,
 }
 
 impl LdkLightningClient {
     pub fn new(rest_service_address: String) -> Self {
+        let (updates, _) = broadcast::channel(PAYMENT_UPDATES_CAPACITY);
         Self {
             client: LdkServerClient::new(rest_service_address),
+            updates,
         }
     }
 
-    pub async fn create_invoice(
+    async fn get_payment(&self, payment_id: &str) -> Result<Option<Payment>> {
+        let resp = self
+            .client
+            .get_payment_details(GetPaymentDetailsRequest {
+                payment_id: payment_id.to_string(),
+            })
+            .await
+            .context("GetPaymentDetails")?;
+        Ok(resp.payment)
+    }
+
+    fn track_payment(&self, payment_id: String) {
+        let ln = self.clone();
+        tokio::spawn(async move {
+            let mut last_state = None;
+            while ln.updates.receiver_count() > 0 {
+                match ln.payment_state(&payment_id).await {
+                    Ok(Some(state)) if last_state.as_ref() != Some(&state) => {
+                        let terminal = !matches!(state, PaymentState::Pending);
+                        last_state = Some(state.clone());
+                        let _ = ln.updates.send(PaymentUpdate {
+                            payment_id: payment_id.clone(),
+                            state,
+                        });
+                        if terminal {
+                            return;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!(%payment_id, "track payment: {e:#}");
+                    }
+                }
+                tokio::time::sleep(PAYMENT_TRACK_INTERVAL).await;
+            }
+        });
+    }
+}
+
+fn payment_state_from_proto(payment: &Payment) -> Result<PaymentState> {
+    if payment.status == PaymentStatus::Failed as i32 {
+        return Ok(PaymentState::Failed(
+            "ldk-server reported payment failure".to_string(),
+        ));
+    }
+    if payment.status != PaymentStatus::Succeeded as i32 {
+        return Ok(PaymentState::Pending);
+    }
+
+    let preimage_hex = match payment.kind.as_ref().and_then(|k| k.kind.as_ref()) {
+        Some(payment_kind::Kind::Bolt11(b)) => b.preimage.clone(),
+        _ => anyhow::bail!("payment is not a BOLT11 payment"),
+    }
+    .context("missing payment preimage")?;
+
+    let bytes = hex::decode(preimage_hex).context("decode preimage hex")?;
+    let preimage: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("preimage must be 32 bytes"))?;
+    Ok(PaymentState::Succeeded(preimage))
+}
+
+#[tonic::async_trait]
+impl LightningBackend for LdkLightningClient {
+    async fn create_invoice(
         &self,
         amount_msat: u64,
         description: String,
@@ -43,7 +117,7 @@ impl LdkLightningClient {
         Ok(resp.invoice)
     }
 
-    pub async fn pay_invoice(&self, invoice: String) -> Result<String> {
+    async fn pay_invoice(&self, invoice: String) -> Result<String> {
         let resp = self
             .client
             .bolt11_send(Bolt11SendRequest {
@@ -53,48 +127,43 @@ impl LdkLightningClient {
             })
             .await
             .context("Bolt11Send")?;
+        self.track_payment(resp.payment_id.clone());
         Ok(resp.payment_id)
     }
 
-    pub async fn wait_preimage(&self, payment_id: &str, timeout: Duration) -> Result<[u8; 32]> {
-        let deadline = Instant::now() + timeout;
-        loop {
-            let payments = self
-                .client
-                .list_payments(ListPaymentsRequest { page_token: None })
-                .await
-                .context("ListPayments")?
-                .payments;
-
-            if let Some(p) = payments.into_iter().find(|p| p.id == payment_id)
-                && p.direction == PaymentDirection::Outbound as i32
-                && p.status == PaymentStatus::Succeeded as i32
-                && matches!(
-                    p.kind.as_ref().and_then(|k| k.kind.as_ref()),
-                    Some(payment_kind::Kind::Bolt11(_))
-                )
-            {
-                let preimage_hex = p
-                    .kind
-                    .and_then(|k| k.kind)
-                    .and_then(|k| match k {
-                        payment_kind::Kind::Bolt11(b) => b.preimage,
-                        _ => None,
-                    })
-                    .context("missing payment preimage")?;
-
-                let bytes = hex::decode(preimage_hex).context("decode preimage hex")?;
-                let preimage: [u8; 32] = bytes
-                    .try_into()
-                    .map_err(|_| anyhow::anyhow!("preimage must be 32 bytes"))?;
-                return Ok(preimage);
-            }
+    async fn payment_state(&self, payment_id: &str) -> Result<Option<PaymentState>> {
+        self.get_payment(payment_id)
+            .await?
+            .filter(|p| p.direction == PaymentDirection::Outbound as i32)
+            .as_ref()
+            .map(payment_state_from_proto)
+            .transpose()
+    }
 
-            if Instant::now() >= deadline {
-                anyhow::bail!("timeout waiting for preimage: payment_id={payment_id}");
-            }
+    fn subscribe_payments(&self) -> broadcast::Receiver<PaymentUpdate> {
+        self.updates.subscribe()
+    }
+
+    async fn inbound_settlement(
+        &self,
+        payment_hash: &[u8; 32],
+    ) -> Result<Option<InboundSettlement>> {
+        // ldk-server keys inbound BOLT11 payments by their payment hash; a miss means the invoice
+        // has not been paid yet.
+        let Some(payment) = self
+            .get_payment(&hex::encode(payment_hash))
+            .await?
+            .filter(|p| p.direction == PaymentDirection::Inbound as i32)
+        else {
+            return Ok(None);
+        };
 
-            tokio::time::sleep(Duration::from_millis(200)).await;
+        match payment_state_from_proto(&payment)? {
+            PaymentState::Succeeded(preimage) => Ok(Some(InboundSettlement {
+                payment_id: payment.id,
+                preimage,
+            })),
+            PaymentState::Pending | PaymentState::Failed(_) => Ok(None),
         }
     }
 }
@@ -1,2 +1,4 @@
+pub mod backend;
+pub mod fake;
 pub mod invoice;
 pub mod ldk;
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use anyhow::{Context as _, Result};
+use electrum_client::ElectrumApi as _;
+use lwk_wollet::blocking::BlockchainBackend as _;
+use lwk_wollet::elements::{BlockHash, Script, Transaction, Txid};
+use lwk_wollet::{ElectrumClient, ElectrumUrl, Wollet, full_scan_with_electrum_client};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptHistoryEntry {
+    pub txid: Txid,
+    pub height: Option<u32>,
+}
+
+pub trait LiquidChain: Send {
+    fn sync_wollet(&mut self, wollet: &mut Wollet) -> Result<()>;
+
+    fn tip_height(&mut self) -> Result<u32>;
+
+    fn genesis_hash(&mut self) -> Result<BlockHash>;
+
+    fn script_history(&self, script_pubkey: &Script) -> Result<Vec<ScriptHistoryEntry>>;
+
+    fn get_transaction(&self, txid: &Txid) -> Result<Transaction>;
+
+    fn broadcast(&self, tx: &Transaction) -> Result<Txid>;
+
+    /// Fee rate in sat/vB to confirm within `target_blocks`, or `None` if the backend has no
+    /// estimate.
+    fn estimate_fee_rate(&self, target_blocks: usize) -> Result<Option<f64>>;
+}
+
+pub fn confirmations(height: Option<u32>, tip_height: u32) -> u32 {
+    match height {
+        Some(height) if height <= tip_height => tip_height - height + 1,
+        _ => 0,
+    }
+}
+
+pub struct ElectrumChain {
+    client: ElectrumClient,
+    // The lwk client does not expose fee estimates.
+    fee_client: electrum_client::Client,
+}
+
+impl ElectrumChain {
+    pub fn new(url: &str) -> Result<Self> {
+        let endpoint = url.trim_start_matches("tcp://");
+        let electrum_url = ElectrumUrl::new(endpoint, false, false)
+            .with_context(|| format!("parse electrum url {endpoint}"))?;
+        let client = ElectrumClient::new(&electrum_url).context("create electrum client")?;
+        let fee_client = electrum_client::Client::new(&format!("tcp://{endpoint}"))
+            .context("create electrum fee client")?;
+        Ok(Self { client, fee_client })
+    }
+}
+
+impl LiquidChain for ElectrumChain {
+    fn sync_wollet(&mut self, wollet: &mut Wollet) -> Result<()> {
+        full_scan_with_electrum_client(wollet, &mut self.client).context("sync wollet via electrum")
+    }
+
+    fn tip_height(&mut self) -> Result<u32> {
+        let tip = self.client.tip().context("get electrum tip")?;
+        Ok(tip.height)
+    }
+
+    fn genesis_hash(&mut self) -> Result<BlockHash> {
+        let mut headers = self
+            .client
+            .get_headers(&[0], &HashMap::new())
+            .context("get electrum genesis header")?;
+        Ok(headers
+            .pop()
+            .context("genesis header not found")?
+            .block_hash())
+    }
+
+    fn script_history(&self, script_pubkey: &Script) -> Result<Vec<ScriptHistoryEntry>> {
+        let mut histories = self
+            .client
+            .get_scripts_history(&[script_pubkey])
+            .context("get script history")?;
+        let history = histories.pop().unwrap_or_default();
+        Ok(history
+            .into_iter()
+            .map(|h| ScriptHistoryEntry {
+                txid: h.txid,
+                height: u32::try_from(h.height).ok().filter(|height| *height > 0),
+            })
+            .collect())
+    }
+
+    fn get_transaction(&self, txid: &Txid) -> Result<Transaction> {
+        let mut txs = self
+            .client
+            .get_transactions(&[*txid])
+            .context("get transaction")?;
+        txs.pop().context("transaction not found")
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> Result<Txid> {
+        self.client.broadcast(tx).context("broadcast tx")
+    }
+
+    fn estimate_fee_rate(&self, target_blocks: usize) -> Result<Option<f64>> {
+        let btc_per_kvb = self
+            .fee_client
+            .estimate_fee(target_blocks)
+            .context("estimate fee")?;
+        // Electrum reports BTC/kvB and -1 when it has no estimate.
+        Ok((btc_per_kvb > 0.0).then(|| btc_per_kvb * 100_000.0))
+    }
+}
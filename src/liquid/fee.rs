@@ -0,0 +1,89 @@
+use anyhow::Result;
+use lwk_wollet::elements::Transaction;
+
+use crate::liquid::wallet::LiquidWallet;
+
+/// Liquid's default minimum relay fee rate. A replacement must also raise the fee rate by at least
+/// this much.
+pub const MIN_FEE_RATE_SAT_VB: f64 = 0.1;
+
+const DEFAULT_TARGET_BLOCKS: usize = 2;
+
+pub fn tx_vsize(tx: &Transaction) -> usize {
+    tx.weight().div_ceil(4)
+}
+
+pub fn fee_for_vsize(vsize: usize, sat_per_vb: f64) -> u64 {
+    (vsize as f64 * sat_per_vb).ceil() as u64
+}
+
+/// The sum of the explicit fee outputs of `tx`.
+pub fn tx_fee(tx: &Transaction) -> u64 {
+    tx.output
+        .iter()
+        .filter(|output| output.is_fee())
+        .filter_map(|output| output.value.explicit())
+        .sum()
+}
+
+pub fn tx_fee_rate(tx: &Transaction) -> f64 {
+    tx_fee(tx) as f64 / tx_vsize(tx) as f64
+}
+
+/// The fee rate a child of `child_vsize` vbytes needs so that it and `parent` together pay
+/// `sat_per_vb`, never below the minimum relay rate.
+pub fn cpfp_child_fee_rate(parent: &Transaction, child_vsize: usize, sat_per_vb: f64) -> f64 {
+    let package_fee = fee_for_vsize(tx_vsize(parent) + child_vsize, sat_per_vb);
+    let child_fee = package_fee.saturating_sub(tx_fee(parent));
+    (child_fee as f64 / child_vsize as f64).max(MIN_FEE_RATE_SAT_VB)
+}
+
+/// Builds a transaction whose fee covers its own vsize at `sat_per_vb`.
+///
+/// `build` is called with a placeholder fee to measure the signed transaction, then again with the
+/// fee that size needs until the fee covers it.
+pub fn build_at_fee_rate(
+    sat_per_vb: f64,
+    mut build: impl FnMut(u64) -> Result<Transaction>,
+) -> Result<Transaction> {
+    let mut fee_sats = 1;
+    loop {
+        let tx = build(fee_sats)?;
+        let needed = fee_for_vsize(tx_vsize(&tx), sat_per_vb);
+        if fee_sats >= needed {
+            return Ok(tx);
+        }
+        fee_sats = needed;
+    }
+}
+
+/// Picks the fee rate for claim and refund transactions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeEstimator {
+    /// Confirmation target passed to the chain backend.
+    pub target_blocks: usize,
+    /// Used when the backend has no estimate or cannot be reached.
+    pub fallback_sat_per_vb: f64,
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        Self {
+            target_blocks: DEFAULT_TARGET_BLOCKS,
+            fallback_sat_per_vb: MIN_FEE_RATE_SAT_VB,
+        }
+    }
+}
+
+impl FeeEstimator {
+    pub fn fee_rate(&self, wallet: &LiquidWallet) -> f64 {
+        match wallet.estimate_fee_rate(self.target_blocks) {
+            Ok(Some(sat_per_vb)) => sat_per_vb.max(MIN_FEE_RATE_SAT_VB),
+            Ok(None) => self.fallback_sat_per_vb,
+            Err(err) => {
+                tracing::warn!(error = %format!("{err:#}"), "fee estimation failed; using fallback rate");
+                self.fallback_sat_per_vb
+            }
+        }
+    }
+}
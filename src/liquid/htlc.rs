@@ -7,7 +7,8 @@ use lwk_wollet::elements::bitcoin::secp256k1::SecretKey as BitcoinSecretKey;
 use lwk_wollet::elements::bitcoin::secp256k1::ecdsa::Signature as BitcoinEcdsaSignature;
 use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
 use lwk_wollet::elements::opcodes;
-use lwk_wollet::elements::script::{Builder, Script};
+use lwk_wollet::elements::script::{self, Builder, Script};
+use lwk_wollet::elements::secp256k1_zkp::{Secp256k1 as ZkpSecp256k1, SecretKey as ZkpSecretKey};
 use lwk_wollet::elements::sighash::SighashCache;
 use lwk_wollet::elements::{
     Address, AddressParams, AssetId, EcdsaSighashType, LockTime, OutPoint, Sequence, Transaction,
@@ -55,83 +56,6 @@ impl HtlcSpec {
     }
 
     pub fn parse_witness_script(witness_script: &Script) -> Result<Self> {
-        use lwk_wollet::elements::script::Instruction;
-
-        fn next_instruction<'a>(
-            iter: &mut impl Iterator<
-                Item = std::result::Result<Instruction<'a>, lwk_wollet::elements::script::Error>,
-            >,
-        ) -> Result<Instruction<'a>> {
-            iter.next()
-                .transpose()
-                .map_err(|e| anyhow::anyhow!("decode witness script instruction: {e:?}"))?
-                .context("unexpected end of witness script")
-        }
-
-        fn expect_op(actual: Instruction<'_>, expected: opcodes::All) -> Result<()> {
-            match actual {
-                Instruction::Op(op) if op == expected => Ok(()),
-                other => {
-                    anyhow::bail!("unexpected instruction: expected {expected:?}, got {other:?}")
-                }
-            }
-        }
-
-        fn parse_script_num(actual: Instruction<'_>) -> Result<i64> {
-            match actual {
-                Instruction::PushBytes(bytes) => decode_script_num(bytes),
-                Instruction::Op(op) => {
-                    let code = op.into_u8();
-                    if code == opcodes::all::OP_PUSHNUM_NEG1.into_u8() {
-                        return Ok(-1);
-                    }
-
-                    let one = opcodes::all::OP_PUSHNUM_1.into_u8();
-                    let sixteen = opcodes::all::OP_PUSHNUM_16.into_u8();
-                    if (one..=sixteen).contains(&code) {
-                        return Ok((code - one + 1) as i64);
-                    }
-
-                    anyhow::bail!("unexpected opcode where script number expected: {op:?}");
-                }
-            }
-        }
-
-        fn decode_script_num(bytes: &[u8]) -> Result<i64> {
-            if bytes.is_empty() {
-                return Ok(0);
-            }
-            if bytes.len() > 8 {
-                anyhow::bail!("script number too large: {} bytes", bytes.len());
-            }
-
-            let mut magnitude = bytes.to_vec();
-            let negative = magnitude.last().is_some_and(|b| (b & 0x80) != 0);
-            if let Some(last) = magnitude.last_mut() {
-                *last &= 0x7f;
-            }
-
-            let mut value: i64 = 0;
-            for (i, b) in magnitude.iter().enumerate() {
-                value |= (*b as i64) << (8 * i);
-            }
-
-            Ok(if negative { -value } else { value })
-        }
-
-        fn expect_push<const N: usize>(actual: Instruction<'_>) -> Result<[u8; N]> {
-            match actual {
-                Instruction::PushBytes(bytes) if bytes.len() == N => {
-                    let mut out = [0u8; N];
-                    out.copy_from_slice(bytes);
-                    Ok(out)
-                }
-                other => {
-                    anyhow::bail!("unexpected instruction: expected push {N} bytes, got {other:?}")
-                }
-            }
-        }
-
         let mut iter = witness_script.instructions_minimal();
 
         expect_op(next_instruction(&mut iter)?, opcodes::all::OP_IF)?;
@@ -181,6 +105,79 @@ impl HtlcSpec {
     }
 }
 
+pub(crate) fn next_instruction<'a>(
+    iter: &mut impl Iterator<Item = std::result::Result<script::Instruction<'a>, script::Error>>,
+) -> Result<script::Instruction<'a>> {
+    iter.next()
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("decode witness script instruction: {e:?}"))?
+        .context("unexpected end of witness script")
+}
+
+pub(crate) fn expect_op(actual: script::Instruction<'_>, expected: opcodes::All) -> Result<()> {
+    match actual {
+        script::Instruction::Op(op) if op == expected => Ok(()),
+        other => {
+            anyhow::bail!("unexpected instruction: expected {expected:?}, got {other:?}")
+        }
+    }
+}
+
+pub(crate) fn parse_script_num(actual: script::Instruction<'_>) -> Result<i64> {
+    match actual {
+        script::Instruction::PushBytes(bytes) => decode_script_num(bytes),
+        script::Instruction::Op(op) => {
+            let code = op.into_u8();
+            if code == opcodes::all::OP_PUSHNUM_NEG1.into_u8() {
+                return Ok(-1);
+            }
+
+            let one = opcodes::all::OP_PUSHNUM_1.into_u8();
+            let sixteen = opcodes::all::OP_PUSHNUM_16.into_u8();
+            if (one..=sixteen).contains(&code) {
+                return Ok((code - one + 1) as i64);
+            }
+
+            anyhow::bail!("unexpected opcode where script number expected: {op:?}");
+        }
+    }
+}
+
+fn decode_script_num(bytes: &[u8]) -> Result<i64> {
+    if bytes.is_empty() {
+        return Ok(0);
+    }
+    if bytes.len() > 8 {
+        anyhow::bail!("script number too large: {} bytes", bytes.len());
+    }
+
+    let mut magnitude = bytes.to_vec();
+    let negative = magnitude.last().is_some_and(|b| (b & 0x80) != 0);
+    if let Some(last) = magnitude.last_mut() {
+        *last &= 0x7f;
+    }
+
+    let mut value: i64 = 0;
+    for (i, b) in magnitude.iter().enumerate() {
+        value |= (*b as i64) << (8 * i);
+    }
+
+    Ok(if negative { -value } else { value })
+}
+
+pub(crate) fn expect_push<const N: usize>(actual: script::Instruction<'_>) -> Result<[u8; N]> {
+    match actual {
+        script::Instruction::PushBytes(bytes) if bytes.len() == N => {
+            let mut out = [0u8; N];
+            out.copy_from_slice(bytes);
+            Ok(out)
+        }
+        other => {
+            anyhow::bail!("unexpected instruction: expected push {N} bytes, got {other:?}")
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HtlcFunding {
     pub funding_txid: Txid,
@@ -190,6 +187,166 @@ pub struct HtlcFunding {
     pub asset_amount: u64,
     pub policy_asset: AssetId,
     pub fee_subsidy_sats: u64,
+    pub blinding: Option<HtlcBlinding>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HtlcBlinding {
+    pub blinding_key: ZkpSecretKey,
+    pub asset_prevout: TxOut,
+    pub lbtc_prevout: TxOut,
+}
+
+impl HtlcBlinding {
+    pub fn from_funding_tx(
+        funding_tx: &Transaction,
+        asset_vout: u32,
+        lbtc_vout: u32,
+        blinding_key: ZkpSecretKey,
+    ) -> Result<Self> {
+        let prevout = |vout: u32| {
+            funding_tx
+                .output
+                .get(vout as usize)
+                .cloned()
+                .with_context(|| format!("funding tx has no output {vout}"))
+        };
+        Ok(Self {
+            blinding_key,
+            asset_prevout: prevout(asset_vout)?,
+            lbtc_prevout: prevout(lbtc_vout)?,
+        })
+    }
+}
+
+impl HtlcFunding {
+    fn prevout_values(&self) -> (Value, Value) {
+        match &self.blinding {
+            Some(b) => (b.asset_prevout.value, b.lbtc_prevout.value),
+            None => (
+                Value::Explicit(self.asset_amount),
+                Value::Explicit(self.fee_subsidy_sats),
+            ),
+        }
+    }
+
+    pub(crate) fn spend_inputs(&self, sequence: Sequence) -> Vec<TxIn> {
+        [self.asset_vout, self.lbtc_vout]
+            .into_iter()
+            .map(|vout| TxIn {
+                previous_output: OutPoint::new(self.funding_txid, vout),
+                is_pegin: false,
+                script_sig: Script::new(),
+                sequence,
+                asset_issuance: Default::default(),
+                witness: TxInWitness::default(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn spend_outputs(&self, receive: &Address, fee_sats: u64) -> Result<Vec<TxOut>> {
+        anyhow::ensure!(
+            fee_sats < self.fee_subsidy_sats,
+            "fee_sats must be less than fee_subsidy_sats"
+        );
+        let nonce = match (&self.blinding, receive.blinding_pubkey) {
+            (None, _) => Nonce::Null,
+            (Some(_), Some(blinding_pubkey)) => Nonce::Confidential(blinding_pubkey),
+            (Some(_), None) => {
+                anyhow::bail!(
+                    "spending a confidential HTLC requires a confidential receive address"
+                )
+            }
+        };
+
+        let receive_spk = receive.script_pubkey();
+        Ok(vec![
+            TxOut {
+                asset: Asset::Explicit(self.asset_id),
+                value: Value::Explicit(self.asset_amount),
+                nonce,
+                script_pubkey: receive_spk.clone(),
+                witness: TxOutWitness::default(),
+            },
+            TxOut {
+                asset: Asset::Explicit(self.policy_asset),
+                value: Value::Explicit(self.fee_subsidy_sats - fee_sats),
+                nonce,
+                script_pubkey: receive_spk,
+                witness: TxOutWitness::default(),
+            },
+            TxOut::new_fee(fee_sats, self.policy_asset),
+        ])
+    }
+
+    pub(crate) fn blind_spend(&self, tx: &mut Transaction) -> Result<()> {
+        let Some(blinding) = &self.blinding else {
+            return Ok(());
+        };
+        let secp = ZkpSecp256k1::new();
+        let asset_secrets = blinding
+            .asset_prevout
+            .unblind(&secp, blinding.blinding_key)
+            .map_err(|e| anyhow::anyhow!("unblind asset prevout: {e:?}"))?;
+        let lbtc_secrets = blinding
+            .lbtc_prevout
+            .unblind(&secp, blinding.blinding_key)
+            .map_err(|e| anyhow::anyhow!("unblind lbtc prevout: {e:?}"))?;
+        anyhow::ensure!(
+            asset_secrets.asset == self.asset_id && asset_secrets.value == self.asset_amount,
+            "asset prevout does not unblind to the expected asset and amount"
+        );
+        anyhow::ensure!(
+            lbtc_secrets.asset == self.policy_asset && lbtc_secrets.value == self.fee_subsidy_sats,
+            "lbtc prevout does not unblind to the expected fee subsidy"
+        );
+        tx.blind(
+            &mut rand::thread_rng(),
+            &secp,
+            &[asset_secrets, lbtc_secrets],
+        )
+        .map_err(|e| anyhow::anyhow!("blind HTLC spend: {e:?}"))?;
+        Ok(())
+    }
+}
+
+pub fn parse_blinding_key(blinding_key_hex: &str) -> Result<ZkpSecretKey> {
+    ZkpSecretKey::from_slice(&hex::decode(blinding_key_hex).context("decode blinding key")?)
+        .context("parse blinding key")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtlcSpend {
+    Claim { preimage: [u8; 32] },
+    Refund,
+}
+
+pub fn classify_htlc_spend(
+    tx: &Transaction,
+    outpoint: &OutPoint,
+    payment_hash: &[u8; 32],
+) -> Result<Option<HtlcSpend>> {
+    let Some(input) = tx.input.iter().find(|i| &i.previous_output == outpoint) else {
+        return Ok(None);
+    };
+
+    match input.witness.script_witness.as_slice() {
+        [_sig, _pubkey, preimage, selector, _witness_script] if selector.as_slice() == [1u8] => {
+            let preimage: [u8; 32] = preimage
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("claim preimage must be 32 bytes"))?;
+            anyhow::ensure!(
+                &sha256_preimage(&preimage) == payment_hash,
+                "claim preimage does not match payment hash"
+            );
+            Ok(Some(HtlcSpend::Claim { preimage }))
+        }
+        [_sig, _pubkey, selector, _witness_script] if selector.is_empty() => {
+            Ok(Some(HtlcSpend::Refund))
+        }
+        _ => anyhow::bail!("unrecognized HTLC witness spending {outpoint}"),
+    }
 }
 
 pub fn pubkey_hash160_from_p2wpkh_address(address: &Address) -> Result<[u8; 20]> {
@@ -251,73 +408,25 @@ pub fn claim_tx_from_witness_script(
     preimage: [u8; 32],
     fee_sats: u64,
 ) -> Result<Transaction> {
-    anyhow::ensure!(
-        fee_sats < funding.fee_subsidy_sats,
-        "fee_sats must be less than fee_subsidy_sats"
-    );
-
-    let inputs = vec![
-        TxIn {
-            previous_output: OutPoint {
-                txid: funding.funding_txid,
-                vout: funding.asset_vout,
-            },
-            is_pegin: false,
-            script_sig: Script::new(),
-            sequence: Sequence::MAX,
-            asset_issuance: Default::default(),
-            witness: TxInWitness::default(),
-        },
-        TxIn {
-            previous_output: OutPoint {
-                txid: funding.funding_txid,
-                vout: funding.lbtc_vout,
-            },
-            is_pegin: false,
-            script_sig: Script::new(),
-            sequence: Sequence::MAX,
-            asset_issuance: Default::default(),
-            witness: TxInWitness::default(),
-        },
-    ];
-
-    let claimer_spk = claimer_receive.script_pubkey();
-
-    let outputs = vec![
-        TxOut {
-            asset: Asset::Explicit(funding.asset_id),
-            value: Value::Explicit(funding.asset_amount),
-            nonce: Nonce::Null,
-            script_pubkey: claimer_spk.clone(),
-            witness: TxOutWitness::default(),
-        },
-        TxOut {
-            asset: Asset::Explicit(funding.policy_asset),
-            value: Value::Explicit(funding.fee_subsidy_sats - fee_sats),
-            nonce: Nonce::Null,
-            script_pubkey: claimer_spk,
-            witness: TxOutWitness::default(),
-        },
-        TxOut::new_fee(fee_sats, funding.policy_asset),
-    ];
-
     let mut tx = Transaction {
         version: 2,
         lock_time: LockTime::ZERO,
-        input: inputs,
-        output: outputs,
+        input: funding.spend_inputs(Sequence::ENABLE_RBF_NO_LOCKTIME),
+        output: funding.spend_outputs(claimer_receive, fee_sats)?,
     };
+    funding.blind_spend(&mut tx)?;
 
     let secp = BitcoinSecp256k1::new();
     let sighash_type = EcdsaSighashType::All;
 
+    let (asset_value, lbtc_value) = funding.prevout_values();
     let mut cache = SighashCache::new(&tx);
     let asset_sig = segwit_v0_sign(
         &secp,
         &mut cache,
         0,
         witness_script,
-        funding.asset_amount,
+        asset_value,
         claimer_secret_key,
         sighash_type,
     )
@@ -327,7 +436,7 @@ pub fn claim_tx_from_witness_script(
         &mut cache,
         1,
         witness_script,
-        funding.fee_subsidy_sats,
+        lbtc_value,
         claimer_secret_key,
         sighash_type,
     )
@@ -361,74 +470,26 @@ pub fn refund_tx_from_witness_script(
     refunder_secret_key: &BitcoinSecretKey,
     fee_sats: u64,
 ) -> Result<Transaction> {
-    anyhow::ensure!(
-        fee_sats < funding.fee_subsidy_sats,
-        "fee_sats must be less than fee_subsidy_sats"
-    );
-
-    let inputs = vec![
-        TxIn {
-            previous_output: OutPoint {
-                txid: funding.funding_txid,
-                vout: funding.asset_vout,
-            },
-            is_pegin: false,
-            script_sig: Script::new(),
-            sequence: Sequence::ENABLE_LOCKTIME_NO_RBF,
-            asset_issuance: Default::default(),
-            witness: TxInWitness::default(),
-        },
-        TxIn {
-            previous_output: OutPoint {
-                txid: funding.funding_txid,
-                vout: funding.lbtc_vout,
-            },
-            is_pegin: false,
-            script_sig: Script::new(),
-            sequence: Sequence::ENABLE_LOCKTIME_NO_RBF,
-            asset_issuance: Default::default(),
-            witness: TxInWitness::default(),
-        },
-    ];
-
-    let refunder_spk = refunder_receive.script_pubkey();
-
-    let outputs = vec![
-        TxOut {
-            asset: Asset::Explicit(funding.asset_id),
-            value: Value::Explicit(funding.asset_amount),
-            nonce: Nonce::Null,
-            script_pubkey: refunder_spk.clone(),
-            witness: TxOutWitness::default(),
-        },
-        TxOut {
-            asset: Asset::Explicit(funding.policy_asset),
-            value: Value::Explicit(funding.fee_subsidy_sats - fee_sats),
-            nonce: Nonce::Null,
-            script_pubkey: refunder_spk,
-            witness: TxOutWitness::default(),
-        },
-        TxOut::new_fee(fee_sats, funding.policy_asset),
-    ];
-
     let mut tx = Transaction {
         version: 2,
         lock_time: LockTime::from_height(refund_lock_height)
             .context("refund_lock_height is invalid locktime")?,
-        input: inputs,
-        output: outputs,
+        input: funding.spend_inputs(Sequence::ENABLE_RBF_NO_LOCKTIME),
+        output: funding.spend_outputs(refunder_receive, fee_sats)?,
     };
+    funding.blind_spend(&mut tx)?;
 
     let secp = BitcoinSecp256k1::new();
     let sighash_type = EcdsaSighashType::All;
 
+    let (asset_value, lbtc_value) = funding.prevout_values();
     let mut cache = SighashCache::new(&tx);
     let asset_sig = segwit_v0_sign(
         &secp,
         &mut cache,
         0,
         witness_script,
-        funding.asset_amount,
+        asset_value,
         refunder_secret_key,
         sighash_type,
     )
@@ -438,7 +499,7 @@ pub fn refund_tx_from_witness_script(
         &mut cache,
         1,
         witness_script,
-        funding.fee_subsidy_sats,
+        lbtc_value,
         refunder_secret_key,
         sighash_type,
     )
@@ -467,16 +528,11 @@ fn segwit_v0_sign(
     cache: &mut SighashCache<&Transaction>,
     input_index: usize,
     script_code: &Script,
-    value: u64,
+    value: Value,
     secret_key: &BitcoinSecretKey,
     sighash_type: EcdsaSighashType,
 ) -> Result<Vec<u8>> {
-    let sighash = cache.segwitv0_sighash(
-        input_index,
-        script_code,
-        Value::Explicit(value),
-        sighash_type,
-    );
+    let sighash = cache.segwitv0_sighash(input_index, script_code, value, sighash_type);
 
     let msg = BitcoinMessage::from_digest_slice(&sighash.to_byte_array())
         .context("create sighash message")?;
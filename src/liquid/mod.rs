@@ -1,3 +1,7 @@
+pub mod chain;
+pub mod fee;
 pub mod htlc;
 pub mod keys;
+pub mod sim;
+pub mod taproot;
 pub mod wallet;
@@ -0,0 +1,279 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::Wollet;
+use lwk_wollet::elements::bitcoin::hashes::Hash as _;
+use lwk_wollet::elements::{BlockHash, OutPoint, Script, Sequence, Transaction, Txid};
+
+use crate::liquid::chain::{LiquidChain, ScriptHistoryEntry};
+use crate::liquid::fee::{MIN_FEE_RATE_SAT_VB, fee_for_vsize, tx_fee, tx_vsize};
+
+const LOCK_TIME_THRESHOLD: u32 = 500_000_000;
+
+#[derive(Debug)]
+struct SimulatedChainState {
+    blocks: Vec<Vec<Transaction>>,
+    mempool: Vec<Transaction>,
+    reject_reason: Option<String>,
+    fee_rate: Option<f64>,
+}
+
+impl Default for SimulatedChainState {
+    fn default() -> Self {
+        Self {
+            blocks: vec![Vec::new()],
+            mempool: Vec::new(),
+            reject_reason: None,
+            fee_rate: None,
+        }
+    }
+}
+
+impl SimulatedChainState {
+    fn tip_height(&self) -> u32 {
+        u32::try_from(self.blocks.len() - 1).expect("simulated chain height fits in u32")
+    }
+
+    fn confirmed(&self) -> impl Iterator<Item = (u32, &Transaction)> {
+        self.blocks.iter().enumerate().flat_map(|(height, txs)| {
+            let height = u32::try_from(height).expect("simulated chain height fits in u32");
+            txs.iter().map(move |tx| (height, tx))
+        })
+    }
+
+    fn all(&self) -> impl Iterator<Item = (Option<u32>, &Transaction)> {
+        self.confirmed()
+            .map(|(height, tx)| (Some(height), tx))
+            .chain(self.mempool.iter().map(|tx| (None, tx)))
+    }
+
+    fn find(&self, txid: &Txid) -> Option<(Option<u32>, &Transaction)> {
+        self.all().find(|(_, tx)| &tx.txid() == txid)
+    }
+
+    fn spender_of(&self, outpoint: &OutPoint) -> Option<(Option<u32>, Txid)> {
+        self.all().find_map(|(height, tx)| {
+            tx.input
+                .iter()
+                .any(|input| &input.previous_output == outpoint)
+                .then(|| (height, tx.txid()))
+        })
+    }
+
+    /// Returns the mempool transactions `tx` replaces.
+    fn check_accept(&self, tx: &Transaction) -> Result<Vec<Txid>> {
+        if let Some(reason) = &self.reject_reason {
+            anyhow::bail!("transaction rejected: {reason}");
+        }
+
+        let lock_time = tx.lock_time.to_consensus_u32();
+        let lock_time_enabled = tx.input.iter().any(|input| input.sequence != Sequence::MAX);
+        if lock_time_enabled && lock_time < LOCK_TIME_THRESHOLD && lock_time > self.tip_height() {
+            anyhow::bail!(
+                "non-final: lock_time={lock_time} tip_height={}",
+                self.tip_height()
+            );
+        }
+
+        let mut conflicts = Vec::new();
+        for input in &tx.input {
+            let prevout = input.previous_output;
+            if let Some((_, prev_tx)) = self.find(&prevout.txid) {
+                anyhow::ensure!(
+                    (prevout.vout as usize) < prev_tx.output.len(),
+                    "bad-txns-inputs-missingorspent: prevout={prevout}"
+                );
+            }
+            match self.spender_of(&prevout) {
+                Some((Some(_), spender)) => {
+                    anyhow::bail!(
+                        "bad-txns-inputs-missingorspent: prevout={prevout} spent_by={spender}"
+                    )
+                }
+                Some((None, spender)) => {
+                    if !conflicts.contains(&spender) {
+                        conflicts.push(spender);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        for txid in &conflicts {
+            let (_, conflict) = self.find(txid).context("conflicting tx not found")?;
+            anyhow::ensure!(
+                conflict.input.iter().any(|input| input.sequence.is_rbf()),
+                "txn-mempool-conflict: spent_by={txid}"
+            );
+            let min_fee = tx_fee(conflict) + fee_for_vsize(tx_vsize(tx), MIN_FEE_RATE_SAT_VB);
+            anyhow::ensure!(
+                tx_fee(tx) >= min_fee,
+                "txn-mempool-conflict: replacing {txid} needs a fee of at least {min_fee} sats"
+            );
+        }
+        Ok(conflicts)
+    }
+}
+
+/// An in-memory Liquid chain for deterministic tests.
+///
+/// It tracks raw transactions only: `sync_wollet` leaves the wallet empty, so anything that
+/// spends wallet UTXOs (funding, CPFP) has to be exercised against elementsd instead.
+#[derive(Clone, Default)]
+pub struct SimulatedLiquidChain {
+    state: Arc<Mutex<SimulatedChainState>>,
+}
+
+impl SimulatedLiquidChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.state
+            .lock()
+            .expect("simulated chain mutex poisoned")
+            .tip_height()
+    }
+
+    pub fn mine_blocks(&self, count: u32) -> u32 {
+        let mut state = self.state.lock().expect("simulated chain mutex poisoned");
+        for _ in 0..count {
+            let txs = std::mem::take(&mut state.mempool);
+            state.blocks.push(txs);
+        }
+        state.tip_height()
+    }
+
+    pub fn reorg(&self, depth: u32) -> Result<u32> {
+        let mut state = self.state.lock().expect("simulated chain mutex poisoned");
+        anyhow::ensure!(
+            depth <= state.tip_height(),
+            "reorg depth {depth} exceeds tip height {}",
+            state.tip_height()
+        );
+        let mut returned = Vec::new();
+        for _ in 0..depth {
+            let txs = state.blocks.pop().context("pop block")?;
+            returned.splice(0..0, txs);
+        }
+        returned.append(&mut state.mempool);
+        state.mempool = returned;
+        Ok(state.tip_height())
+    }
+
+    pub fn evict(&self, txid: &Txid) -> bool {
+        let mut state = self.state.lock().expect("simulated chain mutex poisoned");
+        let before = state.mempool.len();
+        state.mempool.retain(|tx| &tx.txid() != txid);
+        state.mempool.len() != before
+    }
+
+    pub fn reject_broadcasts(&self, reason: Option<String>) {
+        self.state
+            .lock()
+            .expect("simulated chain mutex poisoned")
+            .reject_reason = reason;
+    }
+
+    /// Sets the fee rate returned by `estimate_fee_rate`; `None` means no estimate.
+    pub fn set_fee_rate(&self, sat_per_vb: Option<f64>) {
+        self.state
+            .lock()
+            .expect("simulated chain mutex poisoned")
+            .fee_rate = sat_per_vb;
+    }
+
+    pub fn mempool_txids(&self) -> Vec<Txid> {
+        self.state
+            .lock()
+            .expect("simulated chain mutex poisoned")
+            .mempool
+            .iter()
+            .map(Transaction::txid)
+            .collect()
+    }
+
+    pub fn tx_height(&self, txid: &Txid) -> Option<Option<u32>> {
+        self.state
+            .lock()
+            .expect("simulated chain mutex poisoned")
+            .find(txid)
+            .map(|(height, _)| height)
+    }
+}
+
+impl LiquidChain for SimulatedLiquidChain {
+    fn sync_wollet(&mut self, _wollet: &mut Wollet) -> Result<()> {
+        // Simulated transactions are never fed to the wollet; see the type docs.
+        Ok(())
+    }
+
+    fn tip_height(&mut self) -> Result<u32> {
+        Ok(self.height())
+    }
+
+    fn genesis_hash(&mut self) -> Result<BlockHash> {
+        Ok(BlockHash::all_zeros())
+    }
+
+    fn script_history(&self, script_pubkey: &Script) -> Result<Vec<ScriptHistoryEntry>> {
+        let state = self.state.lock().expect("simulated chain mutex poisoned");
+        let pays_script = |outpoint: &OutPoint| {
+            state.find(&outpoint.txid).is_some_and(|(_, prev_tx)| {
+                prev_tx
+                    .output
+                    .get(outpoint.vout as usize)
+                    .is_some_and(|output| &output.script_pubkey == script_pubkey)
+            })
+        };
+
+        Ok(state
+            .all()
+            .filter(|(_, tx)| {
+                tx.output
+                    .iter()
+                    .any(|output| &output.script_pubkey == script_pubkey)
+                    || tx
+                        .input
+                        .iter()
+                        .any(|input| pays_script(&input.previous_output))
+            })
+            .map(|(height, tx)| ScriptHistoryEntry {
+                txid: tx.txid(),
+                height,
+            })
+            .collect())
+    }
+
+    fn get_transaction(&self, txid: &Txid) -> Result<Transaction> {
+        self.state
+            .lock()
+            .expect("simulated chain mutex poisoned")
+            .find(txid)
+            .map(|(_, tx)| tx.clone())
+            .context("transaction not found")
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> Result<Txid> {
+        let mut state = self.state.lock().expect("simulated chain mutex poisoned");
+        let txid = tx.txid();
+        if state.find(&txid).is_some() {
+            return Ok(txid);
+        }
+        let replaced = state.check_accept(tx)?;
+        state
+            .mempool
+            .retain(|mempool_tx| !replaced.contains(&mempool_tx.txid()));
+        state.mempool.push(tx.clone());
+        Ok(txid)
+    }
+
+    fn estimate_fee_rate(&self, _target_blocks: usize) -> Result<Option<f64>> {
+        Ok(self
+            .state
+            .lock()
+            .expect("simulated chain mutex poisoned")
+            .fee_rate)
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::{Context as _, Result};
+use lwk_wollet::elements::bitcoin::hashes::Hash as _;
+use lwk_wollet::elements::bitcoin::secp256k1::{
+    All, Keypair, Message, Secp256k1, SecretKey, XOnlyPublicKey,
+};
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::opcodes;
+use lwk_wollet::elements::script::{Builder, Script};
+use lwk_wollet::elements::sighash::{Prevouts, SighashCache};
+use lwk_wollet::elements::taproot::{LeafVersion, TapLeafHash, TaprootBuilder, TaprootSpendInfo};
+use lwk_wollet::elements::{
+    Address, AddressParams, BlockHash, LockTime, OutPoint, SchnorrSighashType, Sequence,
+    Transaction, TxOut, TxOutWitness,
+};
+
+use crate::liquid::htlc::{
+    HtlcFunding, HtlcSpend, expect_op, expect_push, next_instruction, parse_script_num,
+    sha256_preimage,
+};
+
+/// BIP341's NUMS point `H`. Nobody knows its discrete log, so an HTLC using it as the internal key
+/// can only be spent through its leaves.
+const UNSPENDABLE_INTERNAL_KEY: [u8; 32] = [
+    0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a, 0x5e,
+    0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80, 0x3a, 0xc0,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaprootHtlcSpec {
+    pub payment_hash: [u8; 32],
+    pub claimer_pubkey: XOnlyPublicKey,
+    pub refunder_pubkey: XOnlyPublicKey,
+    pub refund_lock_height: u32,
+}
+
+impl TaprootHtlcSpec {
+    pub fn claim_leaf(&self) -> Script {
+        Builder::new()
+            .push_opcode(opcodes::all::OP_SIZE)
+            .push_int(32)
+            .push_opcode(opcodes::all::OP_EQUALVERIFY)
+            .push_opcode(opcodes::all::OP_SHA256)
+            .push_slice(&self.payment_hash)
+            .push_opcode(opcodes::all::OP_EQUALVERIFY)
+            .push_slice(&self.claimer_pubkey.serialize())
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .into_script()
+    }
+
+    pub fn refund_leaf(&self) -> Script {
+        Builder::new()
+            .push_int(self.refund_lock_height as i64)
+            .push_opcode(opcodes::all::OP_CLTV)
+            .push_opcode(opcodes::all::OP_DROP)
+            .push_slice(&self.refunder_pubkey.serialize())
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .into_script()
+    }
+
+    pub fn internal_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_slice(&UNSPENDABLE_INTERNAL_KEY).expect("NUMS point is a valid key")
+    }
+
+    pub fn spend_info(&self) -> Result<TaprootSpendInfo> {
+        let secp = Secp256k1::verification_only();
+        TaprootBuilder::new()
+            .add_leaf(1, self.claim_leaf())
+            .map_err(|e| anyhow::anyhow!("add claim leaf: {e:?}"))?
+            .add_leaf(1, self.refund_leaf())
+            .map_err(|e| anyhow::anyhow!("add refund leaf: {e:?}"))?
+            .finalize(&secp, self.internal_key())
+            .map_err(|_| anyhow::anyhow!("finalize taproot tree"))
+    }
+
+    pub fn p2tr_address(&self, params: &'static AddressParams) -> Result<Address> {
+        let secp = Secp256k1::verification_only();
+        let spend_info = self.spend_info()?;
+        Ok(Address::p2tr(
+            &secp,
+            spend_info.internal_key(),
+            spend_info.merkle_root(),
+            None,
+            params,
+        ))
+    }
+
+    pub fn encoded_leaves(&self) -> Vec<u8> {
+        let mut out = self.claim_leaf().to_bytes();
+        out.extend(self.refund_leaf().to_bytes());
+        out
+    }
+
+    pub fn parse_encoded_leaves(encoded: &[u8]) -> Result<Self> {
+        let leaves = Script::from(encoded.to_vec());
+        let mut iter = leaves.instructions_minimal();
+
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_SIZE)?;
+        let size = parse_script_num(next_instruction(&mut iter)?)?;
+        anyhow::ensure!(size == 32, "unexpected preimage size check: {size}");
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_EQUALVERIFY)?;
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_SHA256)?;
+        let payment_hash = expect_push::<32>(next_instruction(&mut iter)?)?;
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_EQUALVERIFY)?;
+        let claimer_pubkey =
+            XOnlyPublicKey::from_slice(&expect_push::<32>(next_instruction(&mut iter)?)?)
+                .context("parse claimer pubkey")?;
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_CHECKSIG)?;
+
+        let refund_lock_height = parse_script_num(next_instruction(&mut iter)?)?;
+        let refund_lock_height = u32::try_from(refund_lock_height).map_err(|_| {
+            anyhow::anyhow!("refund_lock_height out of range: {refund_lock_height}")
+        })?;
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_CLTV)?;
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_DROP)?;
+        let refunder_pubkey =
+            XOnlyPublicKey::from_slice(&expect_push::<32>(next_instruction(&mut iter)?)?)
+                .context("parse refunder pubkey")?;
+        expect_op(next_instruction(&mut iter)?, opcodes::all::OP_CHECKSIG)?;
+
+        anyhow::ensure!(
+            iter.next().is_none(),
+            "unexpected trailing instructions in taproot leaves"
+        );
+
+        let spec = Self {
+            payment_hash,
+            claimer_pubkey,
+            refunder_pubkey,
+            refund_lock_height,
+        };
+        anyhow::ensure!(
+            spec.encoded_leaves() == encoded,
+            "taproot leaves are not canonically encoded"
+        );
+        Ok(spec)
+    }
+}
+
+pub fn taproot_claim_tx_script_path(
+    spec: &TaprootHtlcSpec,
+    funding: &HtlcFunding,
+    claimer_receive: &Address,
+    claimer_secret_key: &SecretKey,
+    preimage: [u8; 32],
+    fee_sats: u64,
+    genesis_hash: BlockHash,
+) -> Result<Transaction> {
+    anyhow::ensure!(
+        sha256_preimage(&preimage) == spec.payment_hash,
+        "preimage does not match payment hash"
+    );
+    let tx = unsigned_spend_tx(
+        funding,
+        claimer_receive,
+        fee_sats,
+        LockTime::ZERO,
+        Sequence::ENABLE_RBF_NO_LOCKTIME,
+    )?;
+    sign_script_path(
+        spec,
+        funding,
+        tx,
+        spec.claim_leaf(),
+        claimer_secret_key,
+        Some(preimage),
+        genesis_hash,
+    )
+}
+
+pub fn taproot_refund_tx_script_path(
+    spec: &TaprootHtlcSpec,
+    funding: &HtlcFunding,
+    refunder_receive: &Address,
+    refunder_secret_key: &SecretKey,
+    fee_sats: u64,
+    genesis_hash: BlockHash,
+) -> Result<Transaction> {
+    let lock_time =
+        LockTime::from_height(spec.refund_lock_height).context("invalid refund lock height")?;
+    let tx = unsigned_spend_tx(
+        funding,
+        refunder_receive,
+        fee_sats,
+        lock_time,
+        Sequence::ENABLE_RBF_NO_LOCKTIME,
+    )?;
+    sign_script_path(
+        spec,
+        funding,
+        tx,
+        spec.refund_leaf(),
+        refunder_secret_key,
+        None,
+        genesis_hash,
+    )
+}
+
+pub fn classify_taproot_htlc_spend(
+    tx: &Transaction,
+    outpoint: &OutPoint,
+    spec: &TaprootHtlcSpec,
+) -> Result<Option<HtlcSpend>> {
+    let Some(input) = tx.input.iter().find(|i| &i.previous_output == outpoint) else {
+        return Ok(None);
+    };
+
+    match input.witness.script_witness.as_slice() {
+        [_sig, preimage, leaf, _control_block] if leaf == &spec.claim_leaf().to_bytes() => {
+            let preimage: [u8; 32] = preimage
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("claim preimage must be 32 bytes"))?;
+            anyhow::ensure!(
+                sha256_preimage(&preimage) == spec.payment_hash,
+                "claim preimage does not match payment hash"
+            );
+            Ok(Some(HtlcSpend::Claim { preimage }))
+        }
+        [_sig, leaf, _control_block] if leaf == &spec.refund_leaf().to_bytes() => {
+            Ok(Some(HtlcSpend::Refund))
+        }
+        _ => anyhow::bail!("unrecognized taproot HTLC witness spending {outpoint}"),
+    }
+}
+
+fn sign_script_path(
+    spec: &TaprootHtlcSpec,
+    funding: &HtlcFunding,
+    mut tx: Transaction,
+    leaf: Script,
+    secret_key: &SecretKey,
+    preimage: Option<[u8; 32]>,
+    genesis_hash: BlockHash,
+) -> Result<Transaction> {
+    let secp = Secp256k1::new();
+    let keypair = Keypair::from_secret_key(&secp, secret_key);
+    let leaf_version = LeafVersion::default();
+    let control_block = spec
+        .spend_info()?
+        .control_block(&(leaf.clone(), leaf_version))
+        .context("leaf is not part of the HTLC tree")?;
+    let leaf_hash = TapLeafHash::from_script(&leaf, leaf_version);
+
+    let prevouts = htlc_prevouts(spec, funding)?;
+    for input_index in 0..tx.input.len() {
+        let sighash = SighashCache::new(&tx)
+            .taproot_script_spend_signature_hash(
+                input_index,
+                &Prevouts::All(&prevouts),
+                leaf_hash,
+                SchnorrSighashType::Default,
+                genesis_hash,
+            )
+            .with_context(|| format!("compute script-path sighash for input {input_index}"))?;
+        let sig = schnorr_sign(&secp, sighash.to_byte_array(), &keypair)?;
+
+        let mut witness = vec![sig];
+        witness.extend(preimage.map(|preimage| preimage.to_vec()));
+        witness.push(leaf.to_bytes());
+        witness.push(control_block.serialize());
+        tx.input[input_index].witness.script_witness = witness;
+    }
+
+    Ok(tx)
+}
+
+fn schnorr_sign(secp: &Secp256k1<All>, sighash: [u8; 32], keypair: &Keypair) -> Result<Vec<u8>> {
+    let msg = Message::from_digest_slice(&sighash).context("create sighash message")?;
+    Ok(secp
+        .sign_schnorr_no_aux_rand(&msg, keypair)
+        .serialize()
+        .to_vec())
+}
+
+fn htlc_prevouts(spec: &TaprootHtlcSpec, funding: &HtlcFunding) -> Result<Vec<TxOut>> {
+    if let Some(blinding) = &funding.blinding {
+        return Ok(vec![
+            blinding.asset_prevout.clone(),
+            blinding.lbtc_prevout.clone(),
+        ]);
+    }
+    let script_pubkey = spec.p2tr_address(&AddressParams::ELEMENTS)?.script_pubkey();
+    Ok(vec![
+        TxOut {
+            asset: Asset::Explicit(funding.asset_id),
+            value: Value::Explicit(funding.asset_amount),
+            nonce: Nonce::Null,
+            script_pubkey: script_pubkey.clone(),
+            witness: TxOutWitness::default(),
+        },
+        TxOut {
+            asset: Asset::Explicit(funding.policy_asset),
+            value: Value::Explicit(funding.fee_subsidy_sats),
+            nonce: Nonce::Null,
+            script_pubkey,
+            witness: TxOutWitness::default(),
+        },
+    ])
+}
+
+fn unsigned_spend_tx(
+    funding: &HtlcFunding,
+    receive: &Address,
+    fee_sats: u64,
+    lock_time: LockTime,
+    sequence: Sequence,
+) -> Result<Transaction> {
+    let mut tx = Transaction {
+        version: 2,
+        lock_time,
+        input: funding.spend_inputs(sequence),
+        output: funding.spend_outputs(receive, fee_sats)?,
+    };
+    funding.blind_spend(&mut tx)?;
+    Ok(tx)
+}
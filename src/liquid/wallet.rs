@@ -1,21 +1,30 @@
+use std::collections::HashSet;
 use std::path::Path;
 use std::time::{Duration, Instant};
 
 use anyhow::{Context as _, Result};
 use lwk_common::Signer as _;
 use lwk_signer::SwSigner;
-use lwk_wollet::blocking::BlockchainBackend as _;
 use lwk_wollet::{
-    ElectrumClient, ElectrumUrl, ElementsNetwork, History, Wollet, WolletDescriptor,
-    elements::{Address, AssetId, Script, Transaction, Txid, confidential},
-    full_scan_with_electrum_client,
+    ElementsNetwork, Wollet, WolletDescriptor,
+    elements::{
+        Address, AssetId, BlockHash, OutPoint, Script, Transaction, Txid, confidential,
+        secp256k1_zkp::{PublicKey, Secp256k1, SecretKey},
+    },
 };
 
+use crate::liquid::chain::{ElectrumChain, LiquidChain, confirmations};
+use crate::liquid::fee::{MIN_FEE_RATE_SAT_VB, cpfp_child_fee_rate, tx_vsize};
+
 pub struct LiquidWallet {
     signer: SwSigner,
     wollet: Wollet,
-    client: ElectrumClient,
+    chain: Box<dyn LiquidChain>,
     network: ElementsNetwork,
+    tip_height: u32,
+    /// HTLC history entries already fetched that do not spend the paired outpoint. A txid commits
+    /// to its inputs, so these never need fetching again.
+    non_spends: HashSet<(OutPoint, Txid)>,
 }
 
 impl LiquidWallet {
@@ -25,6 +34,17 @@ impl LiquidWallet {
         electrum_url: &str,
         persist_dir: &Path,
         network: ElementsNetwork,
+    ) -> Result<Self> {
+        let chain = ElectrumChain::new(electrum_url).context("create electrum chain")?;
+        Self::with_chain(mnemonic, slip77_key, Box::new(chain), persist_dir, network)
+    }
+
+    pub fn with_chain(
+        mnemonic: &str,
+        slip77_key: &str,
+        chain: Box<dyn LiquidChain>,
+        persist_dir: &Path,
+        network: ElementsNetwork,
     ) -> Result<Self> {
         let signer = SwSigner::new(mnemonic, false).context("create SwSigner")?;
         let xpub = signer.xpub();
@@ -35,13 +55,13 @@ impl LiquidWallet {
         let wollet =
             Wollet::with_fs_persist(network, descriptor, persist_dir).context("create wollet")?;
 
-        let client = electrum_client(electrum_url).context("create electrum client")?;
-
         let mut wallet = Self {
             signer,
             wollet,
-            client,
+            chain,
             network,
+            tip_height: 0,
+            non_spends: HashSet::new(),
         };
         wallet.sync().context("initial sync")?;
         Ok(wallet)
@@ -61,7 +81,11 @@ impl LiquidWallet {
     }
 
     pub fn tip_height(&self) -> u32 {
-        self.wollet.tip().height()
+        self.tip_height
+    }
+
+    pub fn genesis_hash(&mut self) -> Result<BlockHash> {
+        self.chain.genesis_hash()
     }
 
     pub fn address_at(&self, index: u32) -> Result<Address> {
@@ -74,8 +98,11 @@ impl LiquidWallet {
     }
 
     pub fn sync(&mut self) -> Result<()> {
-        full_scan_with_electrum_client(&mut self.wollet, &mut self.client)
-            .context("sync wollet via electrum")
+        self.chain
+            .sync_wollet(&mut self.wollet)
+            .context("sync wollet")?;
+        self.tip_height = self.chain.tip_height().context("get chain tip")?;
+        Ok(())
     }
 
     pub fn build_and_broadcast_funding(
@@ -84,21 +111,33 @@ impl LiquidWallet {
         asset_id: AssetId,
         asset_amount: u64,
         fee_subsidy_sats: u64,
+        blinding_key: Option<SecretKey>,
     ) -> Result<(Transaction, Txid, u32, u32)> {
         self.sync()
             .context("sync wallet before building funding tx")?;
 
         let policy_asset = self.policy_asset();
-
-        let mut pset = self
-            .wollet
-            .tx_builder()
-            .add_explicit_recipient(htlc_address, asset_amount, asset_id)
-            .context("add htlc asset output")?
-            .add_explicit_recipient(htlc_address, fee_subsidy_sats, policy_asset)
-            .context("add htlc lbtc subsidy output")?
-            .finish()
-            .context("finalize funding pset")?;
+        let secp = Secp256k1::new();
+
+        let builder = self.wollet.tx_builder();
+        let builder = match blinding_key {
+            Some(blinding_key) => {
+                let confidential_address = htlc_address
+                    .to_unconfidential()
+                    .to_confidential(PublicKey::from_secret_key(&secp, &blinding_key));
+                builder
+                    .add_recipient(&confidential_address, asset_amount, asset_id)
+                    .context("add htlc asset output")?
+                    .add_recipient(&confidential_address, fee_subsidy_sats, policy_asset)
+                    .context("add htlc lbtc subsidy output")?
+            }
+            None => builder
+                .add_explicit_recipient(htlc_address, asset_amount, asset_id)
+                .context("add htlc asset output")?
+                .add_explicit_recipient(htlc_address, fee_subsidy_sats, policy_asset)
+                .context("add htlc lbtc subsidy output")?,
+        };
+        let mut pset = builder.finish().context("finalize funding pset")?;
 
         let sigs = self.signer.sign(&mut pset).context("sign funding pset")?;
         anyhow::ensure!(sigs > 0, "no signatures added for funding");
@@ -107,7 +146,7 @@ impl LiquidWallet {
             .wollet
             .finalize(&mut pset)
             .context("finalize funding tx")?;
-        let txid = self.client.broadcast(&tx).context("broadcast funding tx")?;
+        let txid = self.chain.broadcast(&tx).context("broadcast funding tx")?;
 
         let mut asset_vout: Option<u32> = None;
         let mut lbtc_vout: Option<u32> = None;
@@ -118,11 +157,19 @@ impl LiquidWallet {
                 continue;
             }
 
-            match output.asset {
-                confidential::Asset::Explicit(a) if a == asset_id => {
+            let output_asset = match (output.asset, blinding_key) {
+                (confidential::Asset::Explicit(a), _) => Some(a),
+                (_, Some(blinding_key)) => output
+                    .unblind(&secp, blinding_key)
+                    .ok()
+                    .map(|secrets| secrets.asset),
+                _ => None,
+            };
+            match output_asset {
+                Some(a) if a == asset_id => {
                     asset_vout = Some(vout as u32);
                 }
-                confidential::Asset::Explicit(a) if a == policy_asset => {
+                Some(a) if a == policy_asset => {
                     lbtc_vout = Some(vout as u32);
                 }
                 _ => {}
@@ -135,30 +182,67 @@ impl LiquidWallet {
         Ok((tx, txid, asset_vout, lbtc_vout))
     }
 
+    /// Spends the wallet's L-BTC change from `parent` back to the wallet so that parent and child
+    /// together pay `sat_per_vb`.
+    ///
+    /// Returns `None` when `parent` has no unspent L-BTC change owned by the wallet.
+    pub fn build_and_broadcast_cpfp(
+        &mut self,
+        parent: &Transaction,
+        sat_per_vb: f64,
+    ) -> Result<Option<Transaction>> {
+        self.sync().context("sync wallet before building cpfp tx")?;
+
+        let parent_txid = parent.txid();
+        let policy_asset = self.policy_asset();
+        let Some(change) = self
+            .wollet
+            .utxos()
+            .context("list wallet utxos")?
+            .into_iter()
+            .find(|utxo| utxo.outpoint.txid == parent_txid && utxo.unblinded.asset == policy_asset)
+        else {
+            return Ok(None);
+        };
+
+        // Measure the child at the minimum rate, then charge it whatever the parent is missing.
+        let probe = self.build_cpfp_child(change.outpoint, MIN_FEE_RATE_SAT_VB)?;
+        let child_rate = cpfp_child_fee_rate(parent, tx_vsize(&probe), sat_per_vb);
+
+        let child = self.build_cpfp_child(change.outpoint, child_rate)?;
+        self.chain.broadcast(&child).context("broadcast cpfp tx")?;
+        Ok(Some(child))
+    }
+
+    fn build_cpfp_child(&mut self, change: OutPoint, sat_per_vb: f64) -> Result<Transaction> {
+        let mut pset = self
+            .wollet
+            .tx_builder()
+            .set_wallet_utxos(vec![change])
+            .drain_lbtc_wallet()
+            .fee_rate(Some((sat_per_vb * 1000.0) as f32))
+            .finish()
+            .context("finalize cpfp pset")?;
+
+        let sigs = self.signer.sign(&mut pset).context("sign cpfp pset")?;
+        anyhow::ensure!(sigs > 0, "no signatures added for cpfp");
+
+        self.wollet.finalize(&mut pset).context("finalize cpfp tx")
+    }
+
     pub fn tx_confirmations_for_script(
         &self,
         script_pubkey: &Script,
         txid: &Txid,
     ) -> Result<Option<u32>> {
-        let mut histories = self
-            .client
-            .get_scripts_history(&[script_pubkey])
+        let history = self
+            .chain
+            .script_history(script_pubkey)
             .context("get script history")?;
-        let history: Vec<History> = histories.pop().unwrap_or_default();
-        let Some(entry) = history.into_iter().find(|h| &h.txid == txid) else {
-            return Ok(None);
-        };
-
-        if entry.height <= 0 {
-            return Ok(Some(0));
-        }
-
-        let height = u32::try_from(entry.height).context("history height must be positive")?;
-        let tip = self.tip_height();
-        if tip < height {
-            return Ok(Some(0));
-        }
-        Ok(Some(tip - height + 1))
+        Ok(history
+            .into_iter()
+            .find(|h| &h.txid == txid)
+            .map(|h| confirmations(h.height, self.tip_height())))
     }
 
     pub fn wait_for_tx_confirmations_for_script(
@@ -189,27 +273,44 @@ impl LiquidWallet {
         }
     }
 
+    pub fn find_outpoint_spend(
+        &mut self,
+        script_pubkey: &Script,
+        outpoint: &OutPoint,
+    ) -> Result<Option<Transaction>> {
+        let history = self
+            .chain
+            .script_history(script_pubkey)
+            .context("get script history")?;
+        for entry in history {
+            if entry.txid == outpoint.txid || self.non_spends.contains(&(*outpoint, entry.txid)) {
+                continue;
+            }
+            let tx = self
+                .chain
+                .get_transaction(&entry.txid)
+                .with_context(|| format!("get transaction {}", entry.txid))?;
+            if tx.input.iter().any(|i| &i.previous_output == outpoint) {
+                return Ok(Some(tx));
+            }
+            self.non_spends.insert((*outpoint, entry.txid));
+        }
+        Ok(None)
+    }
+
     pub fn broadcast_transaction(&self, tx: &Transaction) -> Result<Txid> {
-        self.client.broadcast(tx).context("broadcast tx")
+        self.chain.broadcast(tx)
+    }
+
+    pub fn estimate_fee_rate(&self, target_blocks: usize) -> Result<Option<f64>> {
+        self.chain.estimate_fee_rate(target_blocks)
     }
 
     pub fn get_transaction(&self, txid: &Txid) -> Result<Transaction> {
-        let mut txs = self
-            .client
-            .get_transactions(&[*txid])
-            .context("get transaction")?;
-        let tx = txs.pop().context("transaction not found")?;
-        Ok(tx)
+        self.chain.get_transaction(txid)
     }
 
     pub fn signer(&self) -> &SwSigner {
         &self.signer
     }
 }
-
-fn electrum_client(url: &str) -> Result<ElectrumClient> {
-    let endpoint = url.trim_start_matches("tcp://");
-    let electrum_url = ElectrumUrl::new(endpoint, false, false)
-        .with_context(|| format!("parse electrum url {endpoint}"))?;
-    ElectrumClient::new(&electrum_url).context("create electrum client")
-}
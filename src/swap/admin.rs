@@ -0,0 +1,286 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use lwk_wollet::elements::AssetId;
+use tonic::{Request, Response, Status};
+
+use crate::proto::v1 as pb;
+use crate::swap::offer::AssetOffer;
+use crate::swap::service::{
+    LEGACY_BUYER_OWNER, LEGACY_SELLER_OWNER, SwapServiceImpl, api_key_secret_hash, unix_now,
+};
+use crate::swap::store::SqliteStore;
+use crate::swap::{ApiKeyRecord, ApiKeyRole, OfferVersionRecord, SwapDirection};
+
+#[derive(Clone)]
+pub struct AdminServiceImpl {
+    swaps: SwapServiceImpl,
+    store: Arc<Mutex<SqliteStore>>,
+    admin_token: String,
+}
+
+impl AdminServiceImpl {
+    pub fn new(
+        swaps: SwapServiceImpl,
+        store: Arc<Mutex<SqliteStore>>,
+        admin_token: String,
+    ) -> Self {
+        Self {
+            swaps,
+            store,
+            admin_token,
+        }
+    }
+
+    fn require_admin<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let token = SwapServiceImpl::authorization_bearer_token(request.metadata())
+            .ok_or_else(|| Status::unauthenticated("missing bearer token"))?;
+        if token != self.admin_token {
+            return Err(Status::permission_denied("admin role required"));
+        }
+        Ok(())
+    }
+
+    fn parse_asset_id(asset_id: &str) -> Result<AssetId, Status> {
+        AssetId::from_str(asset_id)
+            .map_err(|e| Status::invalid_argument(format!("invalid asset_id: {e}")))
+    }
+
+    fn offer_from_proto(offer: &pb::Offer) -> Result<AssetOffer, Status> {
+        let supported_directions = offer
+            .supported_directions
+            .iter()
+            .map(|d| match pb::SwapDirection::try_from(*d) {
+                Ok(pb::SwapDirection::LnToLiquid) => Ok(SwapDirection::LnToLiquid),
+                Ok(pb::SwapDirection::LiquidToLn) => Ok(SwapDirection::LiquidToLn),
+                _ => Err(Status::invalid_argument(
+                    "supported_directions must contain valid SwapDirections",
+                )),
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+        let offer = AssetOffer {
+            asset_id: Self::parse_asset_id(&offer.asset_id)?,
+            supported_directions,
+            price_msat_per_asset_unit: offer.price_msat_per_asset_unit,
+            fee_subsidy_sats: offer.fee_subsidy_sats,
+            refund_delta_blocks: offer.refund_delta_blocks,
+            min_asset_amount: offer.min_asset_amount,
+            max_asset_amount: offer.max_asset_amount,
+        };
+        offer
+            .validate()
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        Ok(offer)
+    }
+
+    fn version_to_proto(&self, record: &OfferVersionRecord) -> Result<pb::OfferVersion, Status> {
+        let offer = AssetOffer::from_version(record)
+            .map_err(|e| Status::internal(format!("decode offer version: {e:#}")))?;
+        let offer = self.swaps.offer_to_proto(&offer);
+        Ok(pb::OfferVersion {
+            asset_id: record.asset_id.clone(),
+            version: record.version,
+            offer_id: SwapServiceImpl::offer_id(&offer),
+            offer: Some(offer),
+            paused: record.paused,
+            created_at: record.created_at,
+        })
+    }
+
+    fn append_version(&self, offer: &AssetOffer, paused: bool) -> Result<pb::OfferVersion, Status> {
+        let record = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .append_offer_version(&offer.to_version(paused, unix_now()))
+            .map_err(|e| Status::internal(format!("store offer version: {e:#}")))?;
+        tracing::info!(
+            asset_id = %record.asset_id,
+            version = record.version,
+            paused,
+            "offer updated"
+        );
+        self.version_to_proto(&record)
+    }
+
+    fn api_key_to_proto(record: &ApiKeyRecord) -> pb::ApiKey {
+        let role = match record.role {
+            ApiKeyRole::Buyer => pb::SwapRole::Buyer,
+            ApiKeyRole::Seller => pb::SwapRole::Seller,
+        };
+        pb::ApiKey {
+            key_id: record.key_id.clone(),
+            role: role as i32,
+            owner: record.owner.clone(),
+            created_at: record.created_at,
+            revoked_at: record.revoked_at.unwrap_or(0),
+        }
+    }
+
+    fn set_paused(&self, asset_id: &str, paused: bool) -> Result<pb::OfferVersion, Status> {
+        let asset_id = Self::parse_asset_id(asset_id)?;
+        let (offer, was_paused) = self
+            .swaps
+            .current_offer(&asset_id)
+            .map_err(|e| Status::internal(format!("get offer: {e:#}")))?
+            .ok_or_else(|| Status::not_found("offer not found"))?;
+        if was_paused == paused {
+            let latest = self
+                .store
+                .lock()
+                .expect("store mutex poisoned")
+                .latest_offer_version(&asset_id.to_string())
+                .map_err(|e| Status::internal(format!("get offer version: {e:#}")))?;
+            if let Some(latest) = latest {
+                return self.version_to_proto(&latest);
+            }
+        }
+        self.append_version(&offer, paused)
+    }
+}
+
+#[tonic::async_trait]
+impl pb::admin_service_server::AdminService for AdminServiceImpl {
+    async fn update_offer(
+        &self,
+        request: Request<pb::UpdateOfferRequest>,
+    ) -> Result<Response<pb::OfferVersion>, Status> {
+        self.require_admin(&request)?;
+        let req = request.into_inner();
+        let offer = req
+            .offer
+            .ok_or_else(|| Status::invalid_argument("offer is required"))?;
+        let offer = Self::offer_from_proto(&offer)?;
+        self.swaps
+            .check_offer_timelock(&offer)
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+
+        let paused = self
+            .swaps
+            .current_offer(&offer.asset_id)
+            .map_err(|e| Status::internal(format!("get offer: {e:#}")))?
+            .is_some_and(|(_, paused)| paused);
+        Ok(Response::new(self.append_version(&offer, paused)?))
+    }
+
+    async fn pause_offer(
+        &self,
+        request: Request<pb::PauseOfferRequest>,
+    ) -> Result<Response<pb::OfferVersion>, Status> {
+        self.require_admin(&request)?;
+        let req = request.into_inner();
+        Ok(Response::new(self.set_paused(&req.asset_id, true)?))
+    }
+
+    async fn resume_offer(
+        &self,
+        request: Request<pb::ResumeOfferRequest>,
+    ) -> Result<Response<pb::OfferVersion>, Status> {
+        self.require_admin(&request)?;
+        let req = request.into_inner();
+        Ok(Response::new(self.set_paused(&req.asset_id, false)?))
+    }
+
+    async fn list_offer_versions(
+        &self,
+        request: Request<pb::ListOfferVersionsRequest>,
+    ) -> Result<Response<pb::ListOfferVersionsResponse>, Status> {
+        self.require_admin(&request)?;
+        let req = request.into_inner();
+        let asset_id = Self::parse_asset_id(&req.asset_id)?;
+
+        let records = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .list_offer_versions(&asset_id.to_string())
+            .map_err(|e| Status::internal(format!("list offer versions: {e:#}")))?;
+        let versions = records
+            .iter()
+            .map(|record| self.version_to_proto(record))
+            .collect::<Result<Vec<_>, Status>>()?;
+        Ok(Response::new(pb::ListOfferVersionsResponse { versions }))
+    }
+
+    async fn create_api_key(
+        &self,
+        request: Request<pb::CreateApiKeyRequest>,
+    ) -> Result<Response<pb::CreateApiKeyResponse>, Status> {
+        self.require_admin(&request)?;
+        let req = request.into_inner();
+        let role = match pb::SwapRole::try_from(req.role) {
+            Ok(pb::SwapRole::Buyer) => ApiKeyRole::Buyer,
+            Ok(pb::SwapRole::Seller) => ApiKeyRole::Seller,
+            _ => return Err(Status::invalid_argument("role must be buyer or seller")),
+        };
+        let owner = req.owner.trim();
+        if owner.is_empty() {
+            return Err(Status::invalid_argument("owner is required"));
+        }
+        // A key with a static token's owner would share that token's swaps.
+        if owner == LEGACY_BUYER_OWNER || owner == LEGACY_SELLER_OWNER {
+            return Err(Status::invalid_argument(format!(
+                "owner {owner} is reserved for the static tokens"
+            )));
+        }
+
+        let key_id = format!("ak_{}", hex::encode(rand::random::<[u8; 8]>()));
+        let secret = hex::encode(rand::random::<[u8; 32]>());
+        let record = ApiKeyRecord {
+            key_id: key_id.clone(),
+            secret_hash: api_key_secret_hash(&secret),
+            role,
+            owner: owner.to_string(),
+            created_at: unix_now(),
+            revoked_at: None,
+        };
+        self.store
+            .lock()
+            .expect("store mutex poisoned")
+            .insert_api_key(&record)
+            .map_err(|e| Status::internal(format!("store api key: {e:#}")))?;
+        tracing::info!(%key_id, ?role, owner, "api key created");
+
+        Ok(Response::new(pb::CreateApiKeyResponse {
+            key: Some(Self::api_key_to_proto(&record)),
+            token: format!("{key_id}.{secret}"),
+        }))
+    }
+
+    async fn revoke_api_key(
+        &self,
+        request: Request<pb::RevokeApiKeyRequest>,
+    ) -> Result<Response<pb::ApiKey>, Status> {
+        self.require_admin(&request)?;
+        let req = request.into_inner();
+        if req.key_id.trim().is_empty() {
+            return Err(Status::invalid_argument("key_id is required"));
+        }
+
+        let record = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .revoke_api_key(&req.key_id, unix_now())
+            .map_err(|e| Status::internal(format!("revoke api key: {e:#}")))?
+            .ok_or_else(|| Status::not_found("api key not found"))?;
+        tracing::info!(key_id = %record.key_id, "api key revoked");
+        Ok(Response::new(Self::api_key_to_proto(&record)))
+    }
+
+    async fn list_api_keys(
+        &self,
+        request: Request<pb::ListApiKeysRequest>,
+    ) -> Result<Response<pb::ListApiKeysResponse>, Status> {
+        self.require_admin(&request)?;
+        let records = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .list_api_keys()
+            .map_err(|e| Status::internal(format!("list api keys: {e:#}")))?;
+        Ok(Response::new(pb::ListApiKeysResponse {
+            keys: records.iter().map(Self::api_key_to_proto).collect(),
+        }))
+    }
+}
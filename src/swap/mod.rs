@@ -1,5 +1,11 @@
+pub mod admin;
+pub mod offer;
+pub mod payment;
+pub mod pricing;
 pub mod service;
 pub mod store;
+pub mod timelock;
+pub mod verify;
 
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +16,8 @@ pub enum SwapStatus {
     Funded,
     Paid,
     Claimed,
+    /// A refund was broadcast and has not confirmed yet.
+    RefundPending,
     Refunded,
     Failed,
 }
@@ -21,6 +29,38 @@ pub enum SwapDirection {
     LiquidToLn,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HtlcVersion {
+    P2wsh,
+    Taproot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapTxKind {
+    Funding,
+    /// Child spending the funding tx's wallet change to raise its package fee rate.
+    Cpfp,
+    Claim,
+    Refund,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapTransition {
+    pub swap_id: String,
+    pub from_status: Option<SwapStatus>,
+    pub to_status: SwapStatus,
+    pub created_at: u64,
+}
+
+/// A committed status change, with the swap as it stood right after it.
+#[derive(Debug, Clone)]
+pub struct SwapUpdate {
+    pub transition: SwapTransition,
+    pub swap: SwapRecord,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapRecord {
     pub swap_id: String,
@@ -47,8 +87,18 @@ pub struct SwapRecord {
     pub ln_payment_id: Option<String>,
     pub ln_preimage_hex: Option<String>,
     pub claim_txid: Option<String>,
+    pub refund_txid: Option<String>,
 
     pub status: SwapStatus,
+
+    pub buyer_claim_pubkey_hex: Option<String>,
+    pub htlc_version: HtlcVersion,
+    pub htlc_blinding_key_hex: Option<String>,
+
+    /// The owner of the buyer API key that created the swap.
+    pub owner: String,
+
+    pub created_at: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +117,56 @@ pub struct QuoteRecord {
     pub refund_delta_blocks: u32,
     pub invoice_expiry_secs: u32,
     pub max_min_funding_confs: u32,
+    pub supported_directions: Vec<SwapDirection>,
+    pub min_asset_amount: u64,
+    pub max_asset_amount: u64,
+    pub max_open_notional_msat: u64,
+    pub max_buyer_open_notional_msat: u64,
+
+    pub price_source: String,
+    pub source_price_msat_per_asset_unit: u64,
+    pub spread_bps: u32,
+    pub priced_at: u64,
 
     pub swap_id: Option<String>,
+    /// The owner of the buyer API key whose swap took the quote.
+    pub buyer_id: Option<String>,
+
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfferVersionRecord {
+    pub asset_id: String,
+    pub version: u32,
+
+    pub supported_directions: Vec<SwapDirection>,
+    pub price_msat_per_asset_unit: u64,
+    pub fee_subsidy_sats: u64,
+    pub refund_delta_blocks: u32,
+    pub min_asset_amount: u64,
+    pub max_asset_amount: u64,
+
+    pub paused: bool,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyRole {
+    Buyer,
+    Seller,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub key_id: String,
+    /// Hex-encoded SHA-256 of the key's secret. The secret itself is never stored.
+    pub secret_hash: String,
+    pub role: ApiKeyRole,
+    pub owner: String,
+
+    pub created_at: u64,
+    pub revoked_at: Option<u64>,
 }
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr as _;
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::elements::AssetId;
+use serde::Deserialize;
+
+use super::{OfferVersionRecord, SwapDirection};
+
+/// Sell-side terms for one asset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetOffer {
+    pub asset_id: AssetId,
+    pub supported_directions: Vec<SwapDirection>,
+    pub price_msat_per_asset_unit: u64,
+    pub fee_subsidy_sats: u64,
+    pub refund_delta_blocks: u32,
+    pub min_asset_amount: u64,
+    /// `0` means no upper limit.
+    pub max_asset_amount: u64,
+}
+
+impl AssetOffer {
+    pub fn supports(&self, direction: SwapDirection) -> bool {
+        self.supported_directions.contains(&direction)
+    }
+
+    pub fn from_version(record: &OfferVersionRecord) -> Result<Self> {
+        Ok(Self {
+            asset_id: AssetId::from_str(&record.asset_id)
+                .with_context(|| format!("parse asset_id {}", record.asset_id))?,
+            supported_directions: record.supported_directions.clone(),
+            price_msat_per_asset_unit: record.price_msat_per_asset_unit,
+            fee_subsidy_sats: record.fee_subsidy_sats,
+            refund_delta_blocks: record.refund_delta_blocks,
+            min_asset_amount: record.min_asset_amount,
+            max_asset_amount: record.max_asset_amount,
+        })
+    }
+
+    pub fn to_version(&self, paused: bool, created_at: u64) -> OfferVersionRecord {
+        OfferVersionRecord {
+            asset_id: self.asset_id.to_string(),
+            version: 0,
+            supported_directions: self.supported_directions.clone(),
+            price_msat_per_asset_unit: self.price_msat_per_asset_unit,
+            fee_subsidy_sats: self.fee_subsidy_sats,
+            refund_delta_blocks: self.refund_delta_blocks,
+            min_asset_amount: self.min_asset_amount,
+            max_asset_amount: self.max_asset_amount,
+            paused,
+            created_at,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(
+            !self.supported_directions.is_empty(),
+            "offer for {} must support at least one direction",
+            self.asset_id
+        );
+        anyhow::ensure!(
+            self.max_asset_amount == 0 || self.min_asset_amount <= self.max_asset_amount,
+            "offer for {} has min_asset_amount > max_asset_amount",
+            self.asset_id
+        );
+        Ok(())
+    }
+}
+
+/// The assets the seller is willing to sell, keyed by asset id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OfferBook {
+    offers: BTreeMap<AssetId, AssetOffer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OfferFileEntry {
+    asset_id: String,
+    #[serde(default = "default_directions")]
+    supported_directions: Vec<SwapDirection>,
+    price_msat_per_asset_unit: u64,
+    fee_subsidy_sats: u64,
+    refund_delta_blocks: u32,
+    #[serde(default)]
+    min_asset_amount: u64,
+    #[serde(default)]
+    max_asset_amount: u64,
+}
+
+fn default_directions() -> Vec<SwapDirection> {
+    vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn]
+}
+
+impl OfferBook {
+    pub fn new(offers: impl IntoIterator<Item = AssetOffer>) -> Result<Self> {
+        let mut book = BTreeMap::new();
+        for offer in offers {
+            offer.validate()?;
+            let asset_id = offer.asset_id;
+            anyhow::ensure!(
+                book.insert(asset_id, offer).is_none(),
+                "duplicate offer for asset {asset_id}"
+            );
+        }
+        anyhow::ensure!(!book.is_empty(), "offer book must not be empty");
+        Ok(Self { offers: book })
+    }
+
+    /// Loads a JSON array of offers. `supported_directions` defaults to both directions.
+    pub fn from_json_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("read offers file {}", path.display()))?;
+        let entries: Vec<OfferFileEntry> =
+            serde_json::from_str(&json).context("parse offers file")?;
+        let offers = entries
+            .into_iter()
+            .map(|e| {
+                Ok(AssetOffer {
+                    asset_id: AssetId::from_str(&e.asset_id)
+                        .with_context(|| format!("parse asset_id {}", e.asset_id))?,
+                    supported_directions: e.supported_directions,
+                    price_msat_per_asset_unit: e.price_msat_per_asset_unit,
+                    fee_subsidy_sats: e.fee_subsidy_sats,
+                    refund_delta_blocks: e.refund_delta_blocks,
+                    min_asset_amount: e.min_asset_amount,
+                    max_asset_amount: e.max_asset_amount,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(offers)
+    }
+
+    pub fn get(&self, asset_id: &AssetId) -> Option<&AssetOffer> {
+        self.offers.get(asset_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AssetOffer> {
+        self.offers.values()
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use tonic::Status;
+
+use crate::lightning::backend::{LightningBackend, PaymentUpdate, wait_preimage};
+use crate::liquid::htlc::sha256_preimage;
+use crate::swap::SwapRecord;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightningPaymentResult {
+    pub payment_id: String,
+    pub preimage: [u8; 32],
+}
+
+pub async fn execute_lightning_payment(
+    ln: &dyn LightningBackend,
+    record: &SwapRecord,
+    timeout: Duration,
+) -> Result<LightningPaymentResult, Status> {
+    execute_lightning_payment_with(ln, record, timeout, |_| Ok(())).await
+}
+
+pub async fn execute_lightning_payment_with(
+    ln: &dyn LightningBackend,
+    record: &SwapRecord,
+    timeout: Duration,
+    on_started: impl FnOnce(&str) -> anyhow::Result<()>,
+) -> Result<LightningPaymentResult, Status> {
+    let expected_payment_hash = expected_payment_hash(record)?;
+
+    let mut updates = ln.subscribe_payments();
+    let payment_id = ln
+        .pay_invoice(record.bolt11_invoice.clone())
+        .await
+        .map_err(|e| Status::internal(format!("pay invoice: {e:#}")))?;
+    on_started(&payment_id).map_err(|e| Status::internal(format!("record payment: {e:#}")))?;
+
+    finish_lightning_payment(ln, &mut updates, payment_id, expected_payment_hash, timeout).await
+}
+
+pub async fn resume_lightning_payment(
+    ln: &dyn LightningBackend,
+    record: &SwapRecord,
+    payment_id: &str,
+    timeout: Duration,
+) -> Result<LightningPaymentResult, Status> {
+    let expected_payment_hash = expected_payment_hash(record)?;
+    let mut updates = ln.subscribe_payments();
+    finish_lightning_payment(
+        ln,
+        &mut updates,
+        payment_id.to_string(),
+        expected_payment_hash,
+        timeout,
+    )
+    .await
+}
+
+fn expected_payment_hash(record: &SwapRecord) -> Result<[u8; 32], Status> {
+    let expected_payment_hash =
+        hex::decode(&record.payment_hash).map_err(|e| Status::internal(format!("{e:#}")))?;
+    expected_payment_hash
+        .try_into()
+        .map_err(|_| Status::internal("payment_hash must be 32 bytes"))
+}
+
+async fn finish_lightning_payment(
+    ln: &dyn LightningBackend,
+    updates: &mut broadcast::Receiver<PaymentUpdate>,
+    payment_id: String,
+    expected_payment_hash: [u8; 32],
+    timeout: Duration,
+) -> Result<LightningPaymentResult, Status> {
+    let preimage = wait_preimage(ln, updates, &payment_id, timeout)
+        .await
+        .map_err(|e| Status::internal(format!("wait preimage: {e:#}")))?;
+
+    let got_payment_hash = sha256_preimage(&preimage);
+    if got_payment_hash != expected_payment_hash {
+        return Err(Status::internal("preimage hash mismatch"));
+    }
+
+    Ok(LightningPaymentResult {
+        payment_id,
+        preimage,
+    })
+}
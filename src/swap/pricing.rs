@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::io::{Read as _, Write as _};
+use std::net::{TcpStream, ToSocketAddrs as _};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::elements::AssetId;
+use serde::Deserialize;
+
+use crate::swap::service::unix_now;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// A price observed by a [`PriceSource`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcePrice {
+    pub price_msat_per_asset_unit: u64,
+    pub source: String,
+    /// When the source observed the price (unix seconds).
+    pub observed_at: u64,
+}
+
+/// Supplies the reference price of an asset before the pricing policy is applied.
+///
+/// Implementations may block; the service calls them from a blocking task.
+pub trait PriceSource: Send + Sync {
+    /// Returns `None` when the source has no price for `asset_id`.
+    fn price(&self, asset_id: &AssetId) -> Result<Option<SourcePrice>>;
+}
+
+/// The JSON document read by [`FilePriceSource`] and [`HttpPriceSource`].
+///
+/// `{"updated_at": 1700000000, "prices": {"<asset_id>": 1000}}`; `updated_at` is optional.
+#[derive(Debug, Deserialize)]
+struct PriceFeed {
+    #[serde(default)]
+    updated_at: Option<u64>,
+    prices: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone)]
+struct Prices {
+    prices: BTreeMap<AssetId, u64>,
+    updated_at: Option<u64>,
+}
+
+impl Prices {
+    fn parse(json: &str) -> Result<Self> {
+        let feed: PriceFeed = serde_json::from_str(json).context("parse price feed")?;
+        let prices = feed
+            .prices
+            .into_iter()
+            .map(|(asset_id, price)| {
+                let asset_id = AssetId::from_str(&asset_id)
+                    .with_context(|| format!("parse asset_id {asset_id}"))?;
+                anyhow::ensure!(price > 0, "price for {asset_id} must be > 0");
+                Ok((asset_id, price))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            prices,
+            updated_at: feed.updated_at,
+        })
+    }
+
+    fn get(&self, asset_id: &AssetId, source: &str, fetched_at: u64) -> Option<SourcePrice> {
+        self.prices.get(asset_id).map(|price| SourcePrice {
+            price_msat_per_asset_unit: *price,
+            source: source.to_string(),
+            observed_at: self.updated_at.unwrap_or(fetched_at),
+        })
+    }
+}
+
+/// Fixed prices, e.g. for tests or assets pegged to BTC.
+#[derive(Debug, Clone)]
+pub struct StaticPriceSource {
+    prices: BTreeMap<AssetId, u64>,
+}
+
+impl StaticPriceSource {
+    pub fn new(prices: impl IntoIterator<Item = (AssetId, u64)>) -> Self {
+        Self {
+            prices: prices.into_iter().collect(),
+        }
+    }
+}
+
+impl PriceSource for StaticPriceSource {
+    fn price(&self, asset_id: &AssetId) -> Result<Option<SourcePrice>> {
+        Ok(self.prices.get(asset_id).map(|price| SourcePrice {
+            price_msat_per_asset_unit: *price,
+            source: "static".to_string(),
+            observed_at: unix_now(),
+        }))
+    }
+}
+
+/// A JSON price feed on disk, reloaded whenever its modification time changes.
+///
+/// Without `updated_at` in the feed, prices are timestamped with the file's modification time.
+#[derive(Debug)]
+pub struct FilePriceSource {
+    path: PathBuf,
+    cache: Mutex<Option<(SystemTime, Prices)>>,
+}
+
+impl FilePriceSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cache: Mutex::new(None),
+        }
+    }
+}
+
+impl PriceSource for FilePriceSource {
+    fn price(&self, asset_id: &AssetId) -> Result<Option<SourcePrice>> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .with_context(|| format!("stat price file {}", self.path.display()))?;
+
+        let mut cache = self.cache.lock().expect("price cache mutex poisoned");
+        let stale = cache.as_ref().is_none_or(|(at, _)| *at != modified);
+        if stale {
+            let json = std::fs::read_to_string(&self.path)
+                .with_context(|| format!("read price file {}", self.path.display()))?;
+            let prices = Prices::parse(&json)?;
+            tracing::info!(
+                path = %self.path.display(),
+                assets = prices.prices.len(),
+                "price file reloaded"
+            );
+            *cache = Some((modified, prices));
+        }
+
+        let (_, prices) = cache.as_ref().expect("price cache must be loaded");
+        let modified_at = modified
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Ok(prices.get(asset_id, "file", modified_at))
+    }
+}
+
+/// A JSON price feed served over plain HTTP, fetched on every quote.
+///
+/// This is a stand-in for a price oracle running next to the server, so only `http://` URLs and
+/// non-chunked responses are supported.
+#[derive(Debug, Clone)]
+pub struct HttpPriceSource {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl HttpPriceSource {
+    pub fn new(url: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("http://")
+            .context("price oracle url must start with http://")?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().context("parse price oracle port")?),
+            None => (authority, 80),
+        };
+        anyhow::ensure!(!host.is_empty(), "price oracle url must have a host");
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    fn fetch(&self) -> Result<String> {
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .context("resolve price oracle")?
+            .next()
+            .context("price oracle has no address")?;
+        let mut stream =
+            TcpStream::connect_timeout(&addr, HTTP_TIMEOUT).context("connect price oracle")?;
+        stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+        write!(
+            stream,
+            "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\n\r\n",
+            self.path, self.host
+        )
+        .context("send price oracle request")?;
+
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .context("read price oracle response")?;
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .context("malformed price oracle response")?;
+        let status = head.lines().next().unwrap_or_default();
+        anyhow::ensure!(
+            status.split_whitespace().nth(1) == Some("200"),
+            "price oracle returned {status}"
+        );
+        Ok(body.to_string())
+    }
+}
+
+impl PriceSource for HttpPriceSource {
+    fn price(&self, asset_id: &AssetId) -> Result<Option<SourcePrice>> {
+        let body = self.fetch()?;
+        let prices = Prices::parse(&body)?;
+        Ok(prices.get(asset_id, "http", unix_now()))
+    }
+}
+
+/// A spread override for quotes of at least `min_asset_amount`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeTier {
+    pub min_asset_amount: u64,
+    pub spread_bps: u32,
+}
+
+impl FromStr for SizeTier {
+    type Err = anyhow::Error;
+
+    /// Parses `<min_asset_amount>:<spread_bps>`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (min_asset_amount, spread_bps) = s
+            .split_once(':')
+            .context("size tier must be <min_asset_amount>:<spread_bps>")?;
+        Ok(Self {
+            min_asset_amount: min_asset_amount
+                .parse()
+                .context("parse size tier min_asset_amount")?,
+            spread_bps: spread_bps.parse().context("parse size tier spread_bps")?,
+        })
+    }
+}
+
+/// Turns a source price into the quoted total.
+///
+/// The default policy quotes the source price as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PricingPolicy {
+    /// Markup over the source price, in basis points.
+    pub spread_bps: u32,
+    /// The tier with the largest `min_asset_amount <= asset_amount` replaces `spread_bps`.
+    pub size_tiers: Vec<SizeTier>,
+    pub min_notional_msat: u64,
+    /// `0` means no upper limit.
+    pub max_notional_msat: u64,
+    /// Source prices older than this are rejected. `0` disables the check.
+    pub max_price_age_secs: u64,
+}
+
+impl PricingPolicy {
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(
+            self.max_notional_msat == 0 || self.min_notional_msat <= self.max_notional_msat,
+            "min_notional_msat must be <= max_notional_msat"
+        );
+        let mut seen = std::collections::BTreeSet::new();
+        for tier in &self.size_tiers {
+            anyhow::ensure!(
+                seen.insert(tier.min_asset_amount),
+                "duplicate size tier for min_asset_amount {}",
+                tier.min_asset_amount
+            );
+        }
+        Ok(())
+    }
+
+    pub fn spread_bps_for(&self, asset_amount: u64) -> u32 {
+        self.size_tiers
+            .iter()
+            .filter(|t| t.min_asset_amount <= asset_amount)
+            .max_by_key(|t| t.min_asset_amount)
+            .map_or(self.spread_bps, |t| t.spread_bps)
+    }
+
+    /// Returns the total price rounded up to the next msat, or `None` on overflow.
+    pub fn total_price_msat(
+        &self,
+        price_msat_per_asset_unit: u64,
+        asset_amount: u64,
+    ) -> Option<u64> {
+        let spread_bps = u128::from(self.spread_bps_for(asset_amount));
+        let base = u128::from(price_msat_per_asset_unit).checked_mul(u128::from(asset_amount))?;
+        let total = base
+            .checked_mul(BPS_DENOMINATOR + spread_bps)?
+            .div_ceil(BPS_DENOMINATOR);
+        u64::try_from(total).ok()
+    }
+}
@@ -1,31 +1,64 @@
+use std::collections::BTreeMap;
 use std::str::FromStr as _;
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context as _, Result};
 use lwk_wollet::elements::bitcoin::hashes::{Hash as _, sha256};
-use lwk_wollet::elements::{Address, AssetId, Script, Txid};
+use lwk_wollet::elements::bitcoin::secp256k1::{PublicKey, Secp256k1};
+use lwk_wollet::elements::encode::{deserialize, serialize_hex};
+use lwk_wollet::elements::secp256k1_zkp::{
+    PublicKey as ZkpPublicKey, Secp256k1 as ZkpSecp256k1, SecretKey as ZkpSecretKey,
+};
+use lwk_wollet::elements::{Address, AssetId, OutPoint, Script, Transaction, Txid};
 use prost::Message as _;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 
+use crate::lightning::backend::{LightningBackend, PaymentState};
 use crate::lightning::invoice::{
-    amount_msat_from_bolt11, is_expired_bolt11, payment_hash_from_bolt11,
+    amount_msat_from_bolt11, is_expired_bolt11, min_final_cltv_expiry_delta_from_bolt11,
+    payment_hash_from_bolt11, secs_until_expiry_bolt11,
 };
-use crate::lightning::ldk::LdkLightningClient;
+use crate::liquid::fee::{FeeEstimator, MIN_FEE_RATE_SAT_VB, build_at_fee_rate, tx_fee_rate};
 use crate::liquid::htlc::{
-    HtlcFunding, HtlcSpec, claim_tx_from_witness_script, pubkey_hash160_from_p2wpkh_address,
-    sha256_preimage,
+    HtlcBlinding, HtlcFunding, HtlcSpec, HtlcSpend, claim_tx_from_witness_script,
+    classify_htlc_spend, parse_blinding_key, pubkey_hash160, pubkey_hash160_from_p2wpkh_address,
+    refund_tx_from_witness_script, sha256_preimage,
 };
 use crate::liquid::keys::derive_secret_key;
+use crate::liquid::taproot::{
+    TaprootHtlcSpec, classify_taproot_htlc_spend, taproot_claim_tx_script_path,
+    taproot_refund_tx_script_path,
+};
 use crate::liquid::wallet::LiquidWallet;
 use crate::proto::v1 as pb;
-use crate::swap::store::SqliteStore;
-use crate::swap::{QuoteRecord, SwapDirection, SwapRecord, SwapStatus};
+use crate::swap::offer::{AssetOffer, OfferBook};
+use crate::swap::payment::{
+    LightningPaymentResult, execute_lightning_payment_with, resume_lightning_payment,
+};
+use crate::swap::pricing::{PriceSource, PricingPolicy, SourcePrice};
+use crate::swap::store::{PageCursor, QuoteFilter, SqliteStore, SwapFilter};
+use crate::swap::timelock::{check_buyer_invoice, check_invoice_expiry};
+use crate::swap::{
+    ApiKeyRole, HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus, SwapTxKind,
+};
 
 const MAX_MIN_FUNDING_CONFS: u32 = 6;
 const DEFAULT_PAYMENT_TIMEOUT_SECS: u64 = 60;
-const DEFAULT_CLAIM_FEE_SATS: u64 = 500;
+/// How often `WatchSwap` re-reads the executor's tip for funding confirmations. Status changes
+/// arrive on the store's update channel instead.
+const WATCH_SWAP_TIP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+/// The owner of swaps created with the legacy `buyer_token`.
+pub const LEGACY_BUYER_OWNER: &str = "buyer";
+/// The owner the legacy `seller_token` acts as.
+pub const LEGACY_SELLER_OWNER: &str = "seller";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CallerRole {
@@ -33,6 +66,12 @@ enum CallerRole {
     Seller,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Caller {
+    role: CallerRole,
+    owner: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum AuthError {
     MissingBearerToken,
@@ -40,6 +79,7 @@ enum AuthError {
     SellerRoleRequired,
     BuyerRoleRequired,
     SwapRoleRequired(&'static str),
+    ApiKeyLookupFailed,
 }
 
 impl From<AuthError> for Status {
@@ -52,35 +92,74 @@ impl From<AuthError> for Status {
             AuthError::SwapRoleRequired(what) => {
                 Status::permission_denied(format!("{what} role required"))
             }
+            AuthError::ApiKeyLookupFailed => Status::internal("look up api key"),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct SwapServiceConfig {
-    pub sell_asset_id: AssetId,
-    pub price_msat_per_asset_unit: u64,
-    pub fee_subsidy_sats: u64,
-    pub refund_delta_blocks: u32,
+    pub offers: OfferBook,
     pub invoice_expiry_secs: u32,
+    /// Liquid blocks an HTLC must stay locked after the swap's invoice can no longer be paid or
+    /// settled.
+    pub min_timelock_margin_blocks: u32,
+    /// Refuse to start a Lightning payment once the tip is within this many blocks of the swap's
+    /// `refund_lock_height`.
+    pub payment_safety_blocks: u32,
+    pub quote_ttl_secs: u32,
+    pub pricing: PricingPolicy,
+    /// Reject quotes that exceed the wallet balance not yet reserved by other quotes and swaps.
+    pub check_inventory: bool,
+    /// Cap on `total_price_msat` summed over all open swaps. `0` means no limit.
+    pub max_open_notional_msat: u64,
+    /// Cap on `total_price_msat` summed over one buyer's open swaps. `0` means no limit.
+    pub max_buyer_open_notional_msat: u64,
     pub seller_key_index: u32,
     pub buyer_key_index: u32,
     pub seller_token: String,
+    /// Legacy shared buyer token; its swaps are owned by [`LEGACY_BUYER_OWNER`]. Empty disables
+    /// it, leaving API keys as the only way to act as a buyer.
     pub buyer_token: String,
 }
 
+impl SwapServiceConfig {
+    /// Rejects an offer whose HTLC could become refundable while the seller's invoice is still
+    /// payable.
+    pub fn check_offer_timelock(&self, offer: &AssetOffer) -> Result<()> {
+        if !offer.supports(SwapDirection::LnToLiquid) {
+            return Ok(());
+        }
+        check_invoice_expiry(
+            offer.refund_delta_blocks,
+            u64::from(self.invoice_expiry_secs),
+            self.min_timelock_margin_blocks,
+        )
+        .with_context(|| format!("offer for {}", offer.asset_id))
+    }
+}
+
+/// Funding confirmations a `WatchSwap` stream last read, and the tip they were counted at.
+#[derive(Debug, Clone, Copy)]
+struct FundingConfirmations {
+    tip_height: u32,
+    confirmations: u32,
+}
+
 #[derive(Clone)]
 pub struct SwapServiceImpl {
     cfg: SwapServiceConfig,
-    ln: LdkLightningClient,
+    ln: Arc<dyn LightningBackend>,
     wallet: Arc<Mutex<LiquidWallet>>,
     store: Arc<Mutex<SqliteStore>>,
+    price_source: Option<Arc<dyn PriceSource>>,
+    fee_estimator: FeeEstimator,
 }
 
 impl SwapServiceImpl {
     pub fn new(
         cfg: SwapServiceConfig,
-        ln: LdkLightningClient,
+        ln: Arc<dyn LightningBackend>,
         wallet: Arc<Mutex<LiquidWallet>>,
         store: Arc<Mutex<SqliteStore>>,
     ) -> Self {
@@ -89,26 +168,108 @@ impl SwapServiceImpl {
             ln,
             wallet,
             store,
+            price_source: None,
+            fee_estimator: FeeEstimator::default(),
+        }
+    }
+
+    /// Prices quotes from `source` instead of the offer's `price_msat_per_asset_unit`.
+    pub fn with_price_source(mut self, source: Arc<dyn PriceSource>) -> Self {
+        self.price_source = Some(source);
+        self
+    }
+
+    /// Sets how claim and refund fee rates are chosen.
+    pub fn with_fee_estimator(mut self, fee_estimator: FeeEstimator) -> Self {
+        self.fee_estimator = fee_estimator;
+        self
+    }
+
+    async fn source_price(&self, offer: &AssetOffer) -> Result<SourcePrice, Status> {
+        let Some(source) = self.price_source.clone() else {
+            return Ok(SourcePrice {
+                price_msat_per_asset_unit: offer.price_msat_per_asset_unit,
+                source: "offer".to_string(),
+                observed_at: unix_now(),
+            });
+        };
+
+        let asset_id = offer.asset_id;
+        let price = tokio::task::spawn_blocking(move || source.price(&asset_id))
+            .await
+            .map_err(|e| Status::internal(format!("join price source: {e}")))?
+            .map_err(|e| Status::unavailable(format!("price source: {e:#}")))?
+            .ok_or_else(|| Status::failed_precondition("no price for asset_id"))?;
+
+        let max_age = self.cfg.pricing.max_price_age_secs;
+        if max_age != 0 && unix_now().saturating_sub(price.observed_at) > max_age {
+            return Err(Status::failed_precondition(format!(
+                "price from {} is older than {max_age}s",
+                price.source
+            )));
+        }
+        Ok(price)
+    }
+
+    /// Returns the current offer for `asset_id` and whether it is paused.
+    ///
+    /// Offers updated through `AdminService` take precedence over the configured offer book.
+    pub(crate) fn current_offer(&self, asset_id: &AssetId) -> Result<Option<(AssetOffer, bool)>> {
+        let stored = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .latest_offer_version(&asset_id.to_string())?;
+        match stored {
+            Some(record) => Ok(Some((AssetOffer::from_version(&record)?, record.paused))),
+            None => Ok(self.cfg.offers.get(asset_id).map(|o| (o.clone(), false))),
+        }
+    }
+
+    fn current_offers(&self) -> Result<Vec<(AssetOffer, bool)>> {
+        let mut offers: BTreeMap<AssetId, (AssetOffer, bool)> = self
+            .cfg
+            .offers
+            .iter()
+            .map(|o| (o.asset_id, (o.clone(), false)))
+            .collect();
+        let stored = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .list_latest_offer_versions()?;
+        for record in stored {
+            let offer = AssetOffer::from_version(&record)?;
+            offers.insert(offer.asset_id, (offer, record.paused));
         }
+        Ok(offers.into_values().collect())
     }
 
-    fn current_offer(&self) -> pb::Offer {
-        let supported_directions = vec![
-            pb::SwapDirection::LnToLiquid as i32,
-            pb::SwapDirection::LiquidToLn as i32,
-        ];
+    pub(crate) fn check_offer_timelock(&self, offer: &AssetOffer) -> Result<()> {
+        self.cfg.check_offer_timelock(offer)
+    }
+
+    pub(crate) fn offer_to_proto(&self, offer: &AssetOffer) -> pb::Offer {
         pb::Offer {
-            asset_id: self.cfg.sell_asset_id.to_string(),
-            supported_directions,
-            price_msat_per_asset_unit: self.cfg.price_msat_per_asset_unit,
-            fee_subsidy_sats: self.cfg.fee_subsidy_sats,
-            refund_delta_blocks: self.cfg.refund_delta_blocks,
+            asset_id: offer.asset_id.to_string(),
+            supported_directions: offer
+                .supported_directions
+                .iter()
+                .map(|d| Self::direction_to_proto(*d) as i32)
+                .collect(),
+            price_msat_per_asset_unit: offer.price_msat_per_asset_unit,
+            fee_subsidy_sats: offer.fee_subsidy_sats,
+            refund_delta_blocks: offer.refund_delta_blocks,
             invoice_expiry_secs: self.cfg.invoice_expiry_secs,
             max_min_funding_confs: MAX_MIN_FUNDING_CONFS,
+            min_asset_amount: offer.min_asset_amount,
+            max_asset_amount: offer.max_asset_amount,
+            max_open_notional_msat: self.cfg.max_open_notional_msat,
+            max_buyer_open_notional_msat: self.cfg.max_buyer_open_notional_msat,
         }
     }
 
-    fn offer_id(offer: &pb::Offer) -> String {
+    pub(crate) fn offer_id(offer: &pb::Offer) -> String {
         let mut buf = Vec::new();
         offer
             .encode(&mut buf)
@@ -123,12 +284,79 @@ impl SwapServiceImpl {
         }
     }
 
+    fn direction_filter(direction: i32) -> Result<Option<SwapDirection>, Status> {
+        match pb::SwapDirection::try_from(direction) {
+            Ok(pb::SwapDirection::Unspecified) => Ok(None),
+            Ok(pb::SwapDirection::LnToLiquid) => Ok(Some(SwapDirection::LnToLiquid)),
+            Ok(pb::SwapDirection::LiquidToLn) => Ok(Some(SwapDirection::LiquidToLn)),
+            Err(_) => Err(Status::invalid_argument(
+                "direction must be a valid SwapDirection",
+            )),
+        }
+    }
+
+    fn status_filter(status: i32) -> Result<Option<SwapStatus>, Status> {
+        match pb::SwapStatus::try_from(status) {
+            Ok(pb::SwapStatus::Unspecified) => Ok(None),
+            Ok(pb::SwapStatus::Created) => Ok(Some(SwapStatus::Created)),
+            Ok(pb::SwapStatus::Funded) => Ok(Some(SwapStatus::Funded)),
+            Ok(pb::SwapStatus::Paid) => Ok(Some(SwapStatus::Paid)),
+            Ok(pb::SwapStatus::Claimed) => Ok(Some(SwapStatus::Claimed)),
+            Ok(pb::SwapStatus::RefundPending) => Ok(Some(SwapStatus::RefundPending)),
+            Ok(pb::SwapStatus::Refunded) => Ok(Some(SwapStatus::Refunded)),
+            Ok(pb::SwapStatus::Failed) => Ok(Some(SwapStatus::Failed)),
+            Err(_) => Err(Status::invalid_argument(
+                "status must be a valid SwapStatus",
+            )),
+        }
+    }
+
+    fn asset_id_filter(asset_id: &str) -> Result<Option<String>, Status> {
+        if asset_id.is_empty() {
+            return Ok(None);
+        }
+        let asset_id = AssetId::from_str(asset_id)
+            .map_err(|e| Status::invalid_argument(format!("invalid asset_id: {e}")))?;
+        Ok(Some(asset_id.to_string()))
+    }
+
+    fn page_size(page_size: u32) -> Result<usize, Status> {
+        match page_size {
+            0 => Ok(DEFAULT_PAGE_SIZE),
+            n if n as usize <= MAX_PAGE_SIZE => Ok(n as usize),
+            _ => Err(Status::invalid_argument(format!(
+                "page_size must be <= {MAX_PAGE_SIZE}"
+            ))),
+        }
+    }
+
+    fn decode_page_token(page_token: &str) -> Result<Option<PageCursor>, Status> {
+        if page_token.is_empty() {
+            return Ok(None);
+        }
+        let (created_at, id) = page_token
+            .split_once(':')
+            .ok_or_else(|| Status::invalid_argument("invalid page_token"))?;
+        let created_at = created_at
+            .parse()
+            .map_err(|_| Status::invalid_argument("invalid page_token"))?;
+        Ok(Some(PageCursor {
+            created_at,
+            id: id.to_string(),
+        }))
+    }
+
+    fn encode_page_token(created_at: u64, id: &str) -> String {
+        format!("{created_at}:{id}")
+    }
+
     fn quote_record_to_proto(record: &QuoteRecord) -> pb::Quote {
         let direction = Self::direction_to_proto(record.direction);
-        let supported_directions = vec![
-            pb::SwapDirection::LnToLiquid as i32,
-            pb::SwapDirection::LiquidToLn as i32,
-        ];
+        let supported_directions = record
+            .supported_directions
+            .iter()
+            .map(|d| Self::direction_to_proto(*d) as i32)
+            .collect();
         pb::Quote {
             quote_id: record.quote_id.clone(),
             offer_id: record.offer_id.clone(),
@@ -140,6 +368,10 @@ impl SwapServiceImpl {
                 refund_delta_blocks: record.refund_delta_blocks,
                 invoice_expiry_secs: record.invoice_expiry_secs,
                 max_min_funding_confs: record.max_min_funding_confs,
+                min_asset_amount: record.min_asset_amount,
+                max_asset_amount: record.max_asset_amount,
+                max_open_notional_msat: record.max_open_notional_msat,
+                max_buyer_open_notional_msat: record.max_buyer_open_notional_msat,
             }),
             direction: direction as i32,
             parties: Some(Self::parties_for_direction(direction)),
@@ -147,41 +379,84 @@ impl SwapServiceImpl {
             asset_amount: record.asset_amount,
             min_funding_confs: record.min_funding_confs,
             total_price_msat: record.total_price_msat,
+            created_at: record.created_at,
+            expires_at: record.expires_at,
+            price_source: record.price_source.clone(),
+            source_price_msat_per_asset_unit: record.source_price_msat_per_asset_unit,
+            spread_bps: record.spread_bps,
+            priced_at: record.priced_at,
         }
     }
 
-    fn authorization_bearer_token(metadata: &tonic::metadata::MetadataMap) -> Option<&str> {
+    pub(crate) fn authorization_bearer_token(
+        metadata: &tonic::metadata::MetadataMap,
+    ) -> Option<&str> {
         let header = metadata.get("authorization")?.to_str().ok()?;
         header.strip_prefix("Bearer ")
     }
 
-    fn require_authenticated<T>(&self, request: &Request<T>) -> Result<CallerRole, AuthError> {
+    fn require_authenticated<T>(&self, request: &Request<T>) -> Result<Caller, AuthError> {
         let token = Self::authorization_bearer_token(request.metadata())
             .ok_or(AuthError::MissingBearerToken)?;
 
         if token == self.cfg.seller_token {
-            return Ok(CallerRole::Seller);
+            return Ok(Caller {
+                role: CallerRole::Seller,
+                owner: LEGACY_SELLER_OWNER.to_string(),
+            });
+        }
+        if !self.cfg.buyer_token.is_empty() && token == self.cfg.buyer_token {
+            return Ok(Caller {
+                role: CallerRole::Buyer,
+                owner: LEGACY_BUYER_OWNER.to_string(),
+            });
         }
-        if token == self.cfg.buyer_token {
-            return Ok(CallerRole::Buyer);
+
+        let (key_id, secret) = token.split_once('.').ok_or(AuthError::InvalidBearerToken)?;
+        let key = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .get_api_key(key_id)
+            .map_err(|e| {
+                tracing::warn!(key_id, error = %format!("{e:#}"), "api key lookup failed");
+                AuthError::ApiKeyLookupFailed
+            })?
+            .ok_or(AuthError::InvalidBearerToken)?;
+        if key.revoked_at.is_some() || key.secret_hash != api_key_secret_hash(secret) {
+            return Err(AuthError::InvalidBearerToken);
         }
 
-        Err(AuthError::InvalidBearerToken)
+        Ok(Caller {
+            role: match key.role {
+                ApiKeyRole::Buyer => CallerRole::Buyer,
+                ApiKeyRole::Seller => CallerRole::Seller,
+            },
+            owner: key.owner,
+        })
     }
 
     fn require_seller<T>(&self, request: &Request<T>) -> Result<(), AuthError> {
-        let role = self.require_authenticated(request)?;
-        if role != CallerRole::Seller {
+        let caller = self.require_authenticated(request)?;
+        if caller.role != CallerRole::Seller {
             return Err(AuthError::SellerRoleRequired);
         }
         Ok(())
     }
 
-    fn require_buyer<T>(&self, request: &Request<T>) -> Result<(), AuthError> {
-        let role = self.require_authenticated(request)?;
-        if role != CallerRole::Buyer {
+    fn require_buyer<T>(&self, request: &Request<T>) -> Result<Caller, AuthError> {
+        let caller = self.require_authenticated(request)?;
+        if caller.role != CallerRole::Buyer {
             return Err(AuthError::BuyerRoleRequired);
         }
+        Ok(caller)
+    }
+
+    /// Buyers only see their own swaps; another buyer's swap is reported as missing.
+    fn require_swap_owner(caller: &Caller, record: &SwapRecord) -> Result<(), Status> {
+        if caller.role == CallerRole::Buyer && caller.owner != record.owner {
+            return Err(Status::not_found("swap not found"));
+        }
         Ok(())
     }
 
@@ -212,12 +487,12 @@ impl SwapServiceImpl {
     }
 
     fn require_swap_role(
-        caller: CallerRole,
+        caller: &Caller,
         required: pb::SwapRole,
         what: &'static str,
     ) -> Result<(), AuthError> {
         if matches!(
-            (caller, required),
+            (caller.role, required),
             (CallerRole::Buyer, pb::SwapRole::Buyer) | (CallerRole::Seller, pb::SwapRole::Seller)
         ) {
             Ok(())
@@ -232,12 +507,20 @@ impl SwapServiceImpl {
             SwapStatus::Funded => pb::SwapStatus::Funded as i32,
             SwapStatus::Paid => pb::SwapStatus::Paid as i32,
             SwapStatus::Claimed => pb::SwapStatus::Claimed as i32,
+            SwapStatus::RefundPending => pb::SwapStatus::RefundPending as i32,
             SwapStatus::Refunded => pb::SwapStatus::Refunded as i32,
             SwapStatus::Failed => pb::SwapStatus::Failed as i32,
         };
 
         let witness_script =
             hex::decode(&record.witness_script_hex).context("decode witness_script_hex")?;
+        let buyer_claim_pubkey = record
+            .buyer_claim_pubkey_hex
+            .as_deref()
+            .map(hex::decode)
+            .transpose()
+            .context("decode buyer_claim_pubkey_hex")?
+            .unwrap_or_default();
 
         let direction = Self::direction_to_proto(record.direction);
         Ok(pb::Swap {
@@ -258,44 +541,1028 @@ impl SwapServiceImpl {
                 asset_vout: record.asset_vout,
                 lbtc_vout: record.lbtc_vout,
                 min_funding_confs: record.min_funding_confs,
+                buyer_claim_pubkey,
+                htlc_version: match record.htlc_version {
+                    HtlcVersion::P2wsh => pb::HtlcVersion::P2wsh as i32,
+                    HtlcVersion::Taproot => pb::HtlcVersion::Taproot as i32,
+                },
+                blinding_key: record
+                    .htlc_blinding_key_hex
+                    .as_deref()
+                    .and_then(|key| hex::decode(key).ok())
+                    .unwrap_or_default(),
             }),
             quote_id: record.quote_id.clone(),
+            created_at: record.created_at,
+            refund_txid: record.refund_txid.clone().unwrap_or_default(),
         })
     }
 
-    fn wait_for_funding_confirmations(
-        wallet: &Arc<Mutex<LiquidWallet>>,
-        script_pubkey: &Script,
-        txid: &Txid,
-        min_confs: u32,
-        timeout: Duration,
-    ) -> Result<u32> {
-        let deadline = Instant::now() + timeout;
-
-        loop {
-            let confs = {
+    async fn load_swap(&self, swap_id: &str) -> Result<SwapRecord> {
+        let store = self.store.clone();
+        let swap_id = swap_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<SwapRecord> {
+            store
+                .lock()
+                .expect("store mutex poisoned")
+                .get_swap(&swap_id)
+                .context("get swap")?
+                .with_context(|| format!("swap not found: {swap_id}"))
+        })
+        .await
+        .context("join")?
+    }
+
+    /// Funding confirmations of `record` for `WatchSwap`.
+    ///
+    /// Watchers never sync the wallet: confirmations are counted against the tip the swap executor
+    /// last synced, and the HTLC history is only re-read once that tip has moved past `seen`.
+    async fn funding_confirmations(
+        &self,
+        record: &SwapRecord,
+        seen: Option<FundingConfirmations>,
+    ) -> Result<Option<FundingConfirmations>> {
+        if !matches!(record.status, SwapStatus::Created | SwapStatus::Funded) {
+            return Ok(seen);
+        }
+        let wallet = self.wallet.clone();
+        let funding_txid = Txid::from_str(&record.funding_txid).context("parse funding_txid")?;
+        let htlc_script_pubkey = Address::from_str(&record.p2wsh_address)
+            .context("parse p2wsh_address")?
+            .script_pubkey();
+        tokio::task::spawn_blocking(move || -> Result<Option<FundingConfirmations>> {
+            let wallet = wallet.lock().expect("wallet mutex poisoned");
+            let tip_height = wallet.tip_height();
+            if let Some(seen) = seen.filter(|seen| seen.tip_height == tip_height) {
+                return Ok(Some(seen));
+            }
+            let confirmations = wallet
+                .tx_confirmations_for_script(&htlc_script_pubkey, &funding_txid)
+                .context("get funding tx confirmations")?
+                .unwrap_or(0);
+            Ok(Some(FundingConfirmations {
+                tip_height,
+                confirmations,
+            }))
+        })
+        .await
+        .context("join")?
+    }
+
+    async fn stream_swap_events(
+        &self,
+        swap_id: String,
+        tx: mpsc::Sender<Result<pb::SwapEvent, Status>>,
+    ) {
+        // Subscribe before the first read so no transition falls between the two.
+        let mut updates = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .subscribe_swaps();
+        let mut record = match self.load_swap(&swap_id).await {
+            Ok(record) => record,
+            Err(err) => {
+                let _ = tx
+                    .send(Err(Status::internal(format!("load swap: {err:#}"))))
+                    .await;
+                return;
+            }
+        };
+        let mut last_sent = None;
+        let mut funding = None;
+        'watch: loop {
+            match self.funding_confirmations(&record, funding).await {
+                Ok(seen) => funding = seen,
+                Err(err) => {
+                    tracing::warn!(%swap_id, error = %format!("{err:#}"), "watch swap confirmations failed");
+                }
+            }
+            let confs = funding.map_or(0, |f| f.confirmations);
+            if last_sent != Some((record.status, confs)) {
+                let event = Self::swap_record_to_proto(&record)
+                    .map(|swap| pb::SwapEvent {
+                        swap: Some(swap),
+                        funding_confirmations: confs,
+                    })
+                    .map_err(|e| Status::internal(format!("encode swap: {e:#}")));
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+                last_sent = Some((record.status, confs));
+            }
+            if matches!(
+                record.status,
+                SwapStatus::Claimed | SwapStatus::Refunded | SwapStatus::Failed
+            ) {
+                return;
+            }
+
+            let awaiting_confirmations =
+                matches!(record.status, SwapStatus::Created | SwapStatus::Funded);
+            let tip_poll = tokio::time::sleep(WATCH_SWAP_TIP_POLL_INTERVAL);
+            tokio::pin!(tip_poll);
+            let next = loop {
+                tokio::select! {
+                    _ = tx.closed() => return,
+                    _ = &mut tip_poll, if awaiting_confirmations => {
+                        continue 'watch;
+                    }
+                    update = updates.recv() => match update {
+                        Ok(update) if update.swap.swap_id == swap_id => break Ok(update.swap),
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(_)) => break self.load_swap(&swap_id).await,
+                        Err(RecvError::Closed) => return,
+                    },
+                }
+            };
+            match next {
+                Ok(latest) => record = latest,
+                Err(err) => {
+                    tracing::warn!(%swap_id, error = %format!("{err:#}"), "watch swap reload failed");
+                }
+            }
+        }
+    }
+
+    pub async fn drive_swaps(&self) -> Result<usize> {
+        let mut advanced = self.resume_lightning_payments().await?;
+
+        let wallet = self.wallet.clone();
+        let store = self.store.clone();
+        let cfg = self.cfg.clone();
+        let fee_estimator = self.fee_estimator;
+        advanced += tokio::task::spawn_blocking(move || -> Result<usize> {
+            let fee_rate = {
                 let mut wallet = wallet.lock().expect("wallet mutex poisoned");
                 wallet.sync().context("sync wallet")?;
-                wallet
-                    .tx_confirmations_for_script(script_pubkey, txid)
-                    .context("get funding tx confirmations")?
+                fee_estimator.fee_rate(&wallet)
+            };
+            let swaps = store
+                .lock()
+                .expect("store mutex poisoned")
+                .list_swaps_by_status(&[
+                    SwapStatus::Created,
+                    SwapStatus::Claimed,
+                    SwapStatus::RefundPending,
+                ])
+                .context("list swaps")?;
+
+            let mut advanced = 0;
+            for s in swaps {
+                let step = match s.status {
+                    SwapStatus::Created => Self::advance_funding(&wallet, &store, &s, fee_rate),
+                    SwapStatus::Claimed => {
+                        Self::rebroadcast_claim(&wallet, &store, &cfg, &s, fee_rate)
+                            .map(|()| false)
+                    }
+                    SwapStatus::RefundPending => {
+                        Self::track_refund(&wallet, &store, &cfg, &s, fee_rate)
+                    }
+                    _ => Ok(false),
+                };
+                match step {
+                    Ok(true) => advanced += 1,
+                    Ok(false) => {}
+                    Err(err) => {
+                        tracing::warn!(swap_id = %s.swap_id, error = %format!("{err:#}"), "swap executor step failed");
+                    }
+                }
+            }
+            Ok(advanced)
+        })
+        .await
+        .context("join")??;
+
+        Ok(advanced)
+    }
+
+    async fn resume_lightning_payments(&self) -> Result<usize> {
+        let swaps = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .list_swaps_by_status(&[SwapStatus::Funded])
+            .context("list swaps")?;
+
+        let mut paid = 0;
+        for s in swaps {
+            if s.ln_preimage_hex.is_some() {
+                continue;
+            }
+            let Some(payment_id) = s.ln_payment_id.as_deref() else {
+                continue;
             };
 
-            if let Some(confs) = confs
-                && confs >= min_confs
+            match self
+                .ln
+                .payment_state(payment_id)
+                .await
+                .with_context(|| format!("get payment state for swap {}", s.swap_id))?
             {
-                return Ok(confs);
+                Some(PaymentState::Succeeded(preimage)) => {
+                    anyhow::ensure!(
+                        hex::encode(sha256_preimage(&preimage)) == s.payment_hash,
+                        "preimage hash mismatch for swap {}",
+                        s.swap_id
+                    );
+                    self.store
+                        .lock()
+                        .expect("store mutex poisoned")
+                        .upsert_swap_payment(
+                            &s.swap_id,
+                            payment_id,
+                            &hex::encode(preimage),
+                            SwapStatus::Paid,
+                        )
+                        .context("persist resumed payment")?;
+                    tracing::info!(swap_id = %s.swap_id, %payment_id, "resumed lightning payment succeeded");
+                    paid += 1;
+                }
+                Some(PaymentState::Failed(reason)) => {
+                    self.store
+                        .lock()
+                        .expect("store mutex poisoned")
+                        .set_swap_payment_id(&s.swap_id, None)
+                        .context("clear failed payment")?;
+                    tracing::warn!(swap_id = %s.swap_id, %payment_id, %reason, "resumed lightning payment failed");
+                }
+                Some(PaymentState::Pending) | None => {}
+            }
+        }
+
+        Ok(paid)
+    }
+
+    fn advance_funding(
+        wallet: &Arc<Mutex<LiquidWallet>>,
+        store: &Arc<Mutex<SqliteStore>>,
+        s: &SwapRecord,
+        fee_rate: f64,
+    ) -> Result<bool> {
+        let funding_txid = Txid::from_str(&s.funding_txid).context("parse funding_txid")?;
+        let htlc_script_pubkey = Address::from_str(&s.p2wsh_address)
+            .context("parse p2wsh_address")?
+            .script_pubkey();
+        let confs = wallet
+            .lock()
+            .expect("wallet mutex poisoned")
+            .tx_confirmations_for_script(&htlc_script_pubkey, &funding_txid)
+            .context("get funding tx confirmations")?;
+
+        match confs {
+            Some(confs) if confs >= s.min_funding_confs => {
+                let mut store = store.lock().expect("store mutex poisoned");
+                store
+                    .update_swap_status(&s.swap_id, SwapStatus::Funded)
+                    .context("update swap status (funded)")?;
+                store
+                    .delete_swap_tx(&s.swap_id, SwapTxKind::Funding)
+                    .context("drop funding tx")?;
+                store
+                    .delete_swap_tx(&s.swap_id, SwapTxKind::Cpfp)
+                    .context("drop cpfp tx")?;
+                tracing::info!(swap_id = %s.swap_id, %funding_txid, confs, "swap funded");
+                Ok(true)
+            }
+            Some(_) => {
+                Self::bump_funding(wallet, store, s, fee_rate)?;
+                Ok(false)
+            }
+            None => {
+                Self::rebroadcast_swap_tx(wallet, store, &s.swap_id, SwapTxKind::Funding)?;
+                Self::rebroadcast_swap_tx(wallet, store, &s.swap_id, SwapTxKind::Cpfp)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Raises an underpaying funding tx to `fee_rate` with a child spending its wallet change.
+    ///
+    /// The funding txid never changes, so the HTLC outpoints stored on the swap stay valid.
+    fn bump_funding(
+        wallet: &Arc<Mutex<LiquidWallet>>,
+        store: &Arc<Mutex<SqliteStore>>,
+        s: &SwapRecord,
+        fee_rate: f64,
+    ) -> Result<()> {
+        let (funding_hex, cpfp_hex) = {
+            let store = store.lock().expect("store mutex poisoned");
+            (
+                store.get_swap_tx(&s.swap_id, SwapTxKind::Funding)?,
+                store.get_swap_tx(&s.swap_id, SwapTxKind::Cpfp)?,
+            )
+        };
+        // The change has a single spend; once a child exists it is only rebroadcast.
+        let (Some(funding_hex), None) = (funding_hex, cpfp_hex) else {
+            return Ok(());
+        };
+        let funding: Transaction =
+            deserialize(&hex::decode(funding_hex).context("decode stored funding tx")?)
+                .context("parse stored funding tx")?;
+        if tx_fee_rate(&funding) >= fee_rate {
+            return Ok(());
+        }
+
+        let Some(child) = wallet
+            .lock()
+            .expect("wallet mutex poisoned")
+            .build_and_broadcast_cpfp(&funding, fee_rate)
+            .context("bump funding tx")?
+        else {
+            tracing::debug!(swap_id = %s.swap_id, "underpaying funding tx has no wallet change to bump");
+            return Ok(());
+        };
+        let txid = child.txid();
+        store
+            .lock()
+            .expect("store mutex poisoned")
+            .put_swap_tx(
+                &s.swap_id,
+                SwapTxKind::Cpfp,
+                &txid.to_string(),
+                &serialize_hex(&child),
+            )
+            .context("persist cpfp tx")?;
+        tracing::info!(swap_id = %s.swap_id, funding_txid = %s.funding_txid, %txid, fee_rate, "bumped funding tx with cpfp");
+        Ok(())
+    }
+
+    fn rebroadcast_claim(
+        wallet: &Arc<Mutex<LiquidWallet>>,
+        store: &Arc<Mutex<SqliteStore>>,
+        cfg: &SwapServiceConfig,
+        s: &SwapRecord,
+        fee_rate: f64,
+    ) -> Result<()> {
+        let Some(claim_txid) = s.claim_txid.as_deref() else {
+            return Ok(());
+        };
+        if store
+            .lock()
+            .expect("store mutex poisoned")
+            .get_swap_tx(&s.swap_id, SwapTxKind::Claim)?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        let claim_txid = Txid::from_str(claim_txid).context("parse claim_txid")?;
+        let htlc_script_pubkey = Address::from_str(&s.p2wsh_address)
+            .context("parse p2wsh_address")?
+            .script_pubkey();
+        let confs = wallet
+            .lock()
+            .expect("wallet mutex poisoned")
+            .tx_confirmations_for_script(&htlc_script_pubkey, &claim_txid)
+            .context("get claim tx confirmations")?;
+
+        match confs {
+            Some(confs) if confs > 0 => {
+                store
+                    .lock()
+                    .expect("store mutex poisoned")
+                    .delete_swap_tx(&s.swap_id, SwapTxKind::Claim)
+                    .context("drop claim tx")?;
             }
+            Some(_) => {
+                Self::bump_swap_tx(wallet, store, cfg, s, SwapTxKind::Claim, fee_rate)?;
+            }
+            None => {
+                // A claim we replaced may have confirmed instead of its replacement.
+                let outpoint = OutPoint::new(
+                    Txid::from_str(&s.funding_txid).context("parse funding_txid")?,
+                    s.asset_vout,
+                );
+                let spend_tx = wallet
+                    .lock()
+                    .expect("wallet mutex poisoned")
+                    .find_outpoint_spend(&htlc_script_pubkey, &outpoint)
+                    .context("find htlc spend")?;
+                if let Some(spend_tx) = spend_tx {
+                    let txid = spend_tx.txid().to_string();
+                    store
+                        .lock()
+                        .expect("store mutex poisoned")
+                        .record_claim_broadcast(&s.swap_id, &txid, &serialize_hex(&spend_tx))
+                        .context("persist claim tx")?;
+                    return Ok(());
+                }
+                if !Self::bump_swap_tx(wallet, store, cfg, s, SwapTxKind::Claim, fee_rate)? {
+                    Self::rebroadcast_swap_tx(wallet, store, &s.swap_id, SwapTxKind::Claim)?;
+                }
+            }
+        }
+        Ok(())
+    }
 
-            if Instant::now() >= deadline {
-                anyhow::bail!(
-                    "timeout waiting funding confirmations: txid={txid} min_confs={min_confs}"
+    fn track_refund(
+        wallet: &Arc<Mutex<LiquidWallet>>,
+        store: &Arc<Mutex<SqliteStore>>,
+        cfg: &SwapServiceConfig,
+        s: &SwapRecord,
+        fee_rate: f64,
+    ) -> Result<bool> {
+        let Some(refund_txid) = s.refund_txid.as_deref() else {
+            return Ok(false);
+        };
+        let refund_txid = Txid::from_str(refund_txid).context("parse refund_txid")?;
+        let htlc_script_pubkey = Address::from_str(&s.p2wsh_address)
+            .context("parse p2wsh_address")?
+            .script_pubkey();
+        let confs = wallet
+            .lock()
+            .expect("wallet mutex poisoned")
+            .tx_confirmations_for_script(&htlc_script_pubkey, &refund_txid)
+            .context("get refund tx confirmations")?;
+
+        match confs {
+            Some(confs) if confs > 0 => {
+                let mut store = store.lock().expect("store mutex poisoned");
+                store
+                    .update_swap_status(&s.swap_id, SwapStatus::Refunded)
+                    .context("update swap status (refunded)")?;
+                store
+                    .delete_swap_tx(&s.swap_id, SwapTxKind::Refund)
+                    .context("drop refund tx")?;
+                tracing::info!(swap_id = %s.swap_id, %refund_txid, confs, "refund confirmed");
+                Ok(true)
+            }
+            Some(_) => {
+                Self::bump_swap_tx(wallet, store, cfg, s, SwapTxKind::Refund, fee_rate)?;
+                Ok(false)
+            }
+            None => {
+                // Another spend of the HTLC is left to the spend watcher, which records the claim.
+                let outpoint = OutPoint::new(
+                    Txid::from_str(&s.funding_txid).context("parse funding_txid")?,
+                    s.asset_vout,
                 );
+                if wallet
+                    .lock()
+                    .expect("wallet mutex poisoned")
+                    .find_outpoint_spend(&htlc_script_pubkey, &outpoint)
+                    .context("find htlc spend")?
+                    .is_some()
+                {
+                    return Ok(false);
+                }
+                if !Self::bump_swap_tx(wallet, store, cfg, s, SwapTxKind::Refund, fee_rate)? {
+                    Self::rebroadcast_swap_tx(wallet, store, &s.swap_id, SwapTxKind::Refund)?;
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Replaces a pending claim or refund that pays less than `fee_rate` with one re-signed at the
+    /// higher rate. Returns whether a replacement was broadcast.
+    fn bump_swap_tx(
+        wallet: &Arc<Mutex<LiquidWallet>>,
+        store: &Arc<Mutex<SqliteStore>>,
+        cfg: &SwapServiceConfig,
+        s: &SwapRecord,
+        kind: SwapTxKind,
+        fee_rate: f64,
+    ) -> Result<bool> {
+        let Some(tx_hex) = store
+            .lock()
+            .expect("store mutex poisoned")
+            .get_swap_tx(&s.swap_id, kind)?
+        else {
+            return Ok(false);
+        };
+        let tx: Transaction = deserialize(&hex::decode(tx_hex).context("decode stored tx")?)
+            .context("parse stored tx")?;
+        let current_rate = tx_fee_rate(&tx);
+        if current_rate >= fee_rate {
+            return Ok(false);
+        }
+        let fee_rate = fee_rate.max(current_rate + MIN_FEE_RATE_SAT_VB);
+
+        let mut wallet = wallet.lock().expect("wallet mutex poisoned");
+        let replacement = match kind {
+            SwapTxKind::Claim => {
+                let preimage = Self::stored_preimage(s)?;
+                build_at_fee_rate(fee_rate, |fee_sats| {
+                    Self::build_claim_tx(&mut wallet, cfg, s, preimage, fee_sats)
+                })?
+            }
+            SwapTxKind::Refund => {
+                let refunder_key_index = Self::refunder_key_index(cfg, s.direction);
+                build_at_fee_rate(fee_rate, |fee_sats| {
+                    Self::build_refund_tx(&mut wallet, s, refunder_key_index, fee_sats)
+                })?
             }
+            SwapTxKind::Funding | SwapTxKind::Cpfp => return Ok(false),
+        };
+        // Persist only what the node accepted; the replaced tx stays valid until then.
+        let txid = wallet
+            .broadcast_transaction(&replacement)
+            .with_context(|| format!("broadcast {kind:?} replacement"))?;
+        drop(wallet);
+
+        let mut store = store.lock().expect("store mutex poisoned");
+        if kind == SwapTxKind::Refund {
+            store
+                .record_refund_broadcast(
+                    &s.swap_id,
+                    &txid.to_string(),
+                    &serialize_hex(&replacement),
+                )
+                .context("persist refund replacement")?;
+        } else {
+            store
+                .record_claim_broadcast(&s.swap_id, &txid.to_string(), &serialize_hex(&replacement))
+                .context("persist claim replacement")?;
+        }
+        tracing::info!(swap_id = %s.swap_id, %txid, ?kind, fee_rate, "replaced swap tx with a higher fee");
+        Ok(true)
+    }
+
+    fn rebroadcast_swap_tx(
+        wallet: &Arc<Mutex<LiquidWallet>>,
+        store: &Arc<Mutex<SqliteStore>>,
+        swap_id: &str,
+        kind: SwapTxKind,
+    ) -> Result<()> {
+        let Some(tx_hex) = store
+            .lock()
+            .expect("store mutex poisoned")
+            .get_swap_tx(swap_id, kind)?
+        else {
+            return Ok(());
+        };
+        let tx: Transaction = deserialize(&hex::decode(tx_hex).context("decode stored tx")?)
+            .context("parse stored tx")?;
+        let txid = wallet
+            .lock()
+            .expect("wallet mutex poisoned")
+            .broadcast_transaction(&tx)
+            .with_context(|| format!("rebroadcast {kind:?} tx"))?;
+        tracing::info!(%swap_id, %txid, ?kind, "rebroadcast swap tx");
+        Ok(())
+    }
+
+    pub async fn sync_inbound_settlements(&self) -> Result<usize> {
+        let swaps = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .list_swaps_by_status(&[SwapStatus::Funded])
+            .context("list swaps")?;
 
-            std::thread::sleep(Duration::from_millis(500));
+        let mut settled = 0;
+        for s in swaps {
+            if s.direction != SwapDirection::LnToLiquid || s.ln_preimage_hex.is_some() {
+                continue;
+            }
+            match self.settle_inbound(&s).await {
+                Ok(true) => settled += 1,
+                Ok(false) => {}
+                Err(err) => {
+                    tracing::warn!(swap_id = %s.swap_id, error = %format!("{err:#}"), "inbound settlement check failed");
+                }
+            }
         }
+
+        Ok(settled)
+    }
+
+    async fn settle_inbound(&self, s: &SwapRecord) -> Result<bool> {
+        let payment_hash: [u8; 32] = hex::decode(&s.payment_hash)
+            .context("decode payment_hash")?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("payment_hash must be 32 bytes"))?;
+        let Some(settlement) = self
+            .ln
+            .inbound_settlement(&payment_hash)
+            .await
+            .context("lookup inbound settlement")?
+        else {
+            return Ok(false);
+        };
+        anyhow::ensure!(
+            sha256_preimage(&settlement.preimage) == payment_hash,
+            "preimage hash mismatch"
+        );
+
+        self.store
+            .lock()
+            .expect("store mutex poisoned")
+            .upsert_swap_payment(
+                &s.swap_id,
+                &settlement.payment_id,
+                &hex::encode(settlement.preimage),
+                SwapStatus::Paid,
+            )
+            .context("persist inbound payment")?;
+        tracing::info!(
+            swap_id = %s.swap_id,
+            payment_id = %settlement.payment_id,
+            "inbound invoice settled"
+        );
+        Ok(true)
+    }
+
+    pub async fn sync_htlc_spends(&self) -> Result<usize> {
+        let wallet = self.wallet.clone();
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize> {
+            let swaps = store
+                .lock()
+                .expect("store mutex poisoned")
+                .list_swaps_by_status(&[
+                    SwapStatus::Created,
+                    SwapStatus::Funded,
+                    SwapStatus::Paid,
+                    SwapStatus::RefundPending,
+                ])
+                .context("list swaps")?;
+
+            let mut resolved = 0;
+            for s in swaps {
+                match Self::sync_htlc_spend(&wallet, &store, &s) {
+                    Ok(true) => resolved += 1,
+                    Ok(false) => {}
+                    Err(err) => {
+                        tracing::warn!(swap_id = %s.swap_id, error = %format!("{err:#}"), "htlc spend check failed");
+                    }
+                }
+            }
+
+            Ok(resolved)
+        })
+        .await
+        .context("join")?
     }
+
+    /// Records how the HTLC of `s` was spent, if it has been. Returns whether the swap changed.
+    fn sync_htlc_spend(
+        wallet: &Mutex<LiquidWallet>,
+        store: &Mutex<SqliteStore>,
+        s: &SwapRecord,
+    ) -> Result<bool> {
+        let payment_hash: [u8; 32] = hex::decode(&s.payment_hash)
+            .context("decode payment_hash")?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("payment_hash must be 32 bytes"))?;
+        let htlc_script_pubkey = Address::from_str(&s.p2wsh_address)
+            .context("parse p2wsh_address")?
+            .script_pubkey();
+        let outpoint = OutPoint::new(
+            Txid::from_str(&s.funding_txid).context("parse funding_txid")?,
+            s.asset_vout,
+        );
+
+        let Some(spend_tx) = wallet
+            .lock()
+            .expect("wallet mutex poisoned")
+            .find_outpoint_spend(&htlc_script_pubkey, &outpoint)
+            .context("find htlc spend")?
+        else {
+            return Ok(false);
+        };
+        let spend = match s.htlc_version {
+            HtlcVersion::P2wsh => classify_htlc_spend(&spend_tx, &outpoint, &payment_hash),
+            HtlcVersion::Taproot => hex::decode(&s.witness_script_hex)
+                .context("decode witness_script_hex")
+                .and_then(|leaves| TaprootHtlcSpec::parse_encoded_leaves(&leaves))
+                .and_then(|spec| classify_taproot_htlc_spend(&spend_tx, &outpoint, &spec)),
+        }
+        .context("classify htlc spend")?
+        .context("spending tx does not spend the htlc outpoint")?;
+
+        let txid = spend_tx.txid();
+        let mut store = store.lock().expect("store mutex poisoned");
+        match spend {
+            HtlcSpend::Claim { preimage } => {
+                store
+                    .record_htlc_claim(&s.swap_id, &txid.to_string(), Some(&hex::encode(preimage)))
+                    .context("persist htlc claim")?;
+                tracing::info!(swap_id = %s.swap_id, claim_txid = %txid, "htlc claimed on-chain");
+            }
+            // The swap executor confirms our pending refund. A refund we replaced may still win, in
+            // which case it is tracked instead.
+            HtlcSpend::Refund if s.status == SwapStatus::RefundPending => {
+                if s.refund_txid.as_deref() == Some(txid.to_string().as_str()) {
+                    return Ok(false);
+                }
+                store
+                    .record_refund_broadcast(
+                        &s.swap_id,
+                        &txid.to_string(),
+                        &serialize_hex(&spend_tx),
+                    )
+                    .context("persist refund tx")?;
+                tracing::info!(swap_id = %s.swap_id, refund_txid = %txid, "htlc spent by a replaced refund");
+            }
+            HtlcSpend::Refund => {
+                store
+                    .update_swap_status(&s.swap_id, SwapStatus::Refunded)
+                    .context("update swap status (refunded)")?;
+                tracing::info!(swap_id = %s.swap_id, refund_txid = %txid, "htlc refunded on-chain");
+            }
+        }
+        Ok(true)
+    }
+
+    /// Broadcasts refunds for unpaid swaps whose HTLC timelock has expired.
+    ///
+    /// Swaps move to `RefundPending` once their refund is broadcast; [`Self::drive_swaps`]
+    /// rebroadcasts the refund until it confirms.
+    pub async fn refund_expired_swaps(&self) -> Result<usize> {
+        let wallet = self.wallet.clone();
+        let store = self.store.clone();
+        let cfg = self.cfg.clone();
+        let fee_estimator = self.fee_estimator;
+        tokio::task::spawn_blocking(move || -> Result<usize> {
+            let mut wallet = wallet.lock().expect("wallet mutex poisoned");
+            wallet.sync().context("sync wallet")?;
+            let tip_height = wallet.tip_height();
+            let fee_rate = fee_estimator.fee_rate(&wallet);
+
+            let swaps = store
+                .lock()
+                .expect("store mutex poisoned")
+                .list_swaps_by_status(&[SwapStatus::Created, SwapStatus::Funded])
+                .context("list swaps")?;
+            let mut refunded = 0;
+            for s in swaps {
+                if tip_height < s.refund_lock_height {
+                    continue;
+                }
+
+                let refunder_key_index = Self::refunder_key_index(&cfg, s.direction);
+                let tx = match build_at_fee_rate(fee_rate, |fee_sats| {
+                    Self::build_refund_tx(&mut wallet, &s, refunder_key_index, fee_sats)
+                }) {
+                    Ok(tx) => tx,
+                    Err(err) => {
+                        tracing::warn!(swap_id = %s.swap_id, error = %format!("{err:#}"), "refund build failed");
+                        continue;
+                    }
+                };
+                let refund_txid = match wallet.broadcast_transaction(&tx) {
+                    Ok(txid) => txid,
+                    Err(err) => {
+                        tracing::warn!(swap_id = %s.swap_id, error = %format!("{err:#}"), "refund broadcast failed");
+                        continue;
+                    }
+                };
+                if let Err(err) = store
+                    .lock()
+                    .expect("store mutex poisoned")
+                    .record_refund_broadcast(
+                        &s.swap_id,
+                        &refund_txid.to_string(),
+                        &serialize_hex(&tx),
+                    )
+                {
+                    tracing::warn!(swap_id = %s.swap_id, error = %format!("{err:#}"), "persist refund tx failed");
+                    continue;
+                }
+                tracing::info!(swap_id = %s.swap_id, %refund_txid, "broadcast refund tx");
+                refunded += 1;
+            }
+
+            Ok(refunded)
+        })
+        .await
+        .context("join")?
+    }
+
+    fn build_refund_tx(
+        wallet: &mut LiquidWallet,
+        s: &SwapRecord,
+        refunder_key_index: u32,
+        fee_sats: u64,
+    ) -> Result<Transaction> {
+        let funding = Self::htlc_funding(wallet, s)?;
+        let refunder_receive = wallet
+            .address_at(refunder_key_index)
+            .context("get refunder receive address")?;
+        if s.direction == SwapDirection::LiquidToLn {
+            anyhow::ensure!(
+                refunder_receive.to_string() == s.buyer_liquid_address,
+                "buyer_liquid_address mismatch"
+            );
+        }
+        let refunder_secret_key = derive_secret_key(wallet.signer(), refunder_key_index)
+            .context("derive refunder secret key")?;
+
+        match s.htlc_version {
+            HtlcVersion::P2wsh => {
+                let witness_script: Script = s
+                    .witness_script_hex
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("parse witness_script: {e:?}"))?;
+                refund_tx_from_witness_script(
+                    &witness_script,
+                    s.refund_lock_height,
+                    &funding,
+                    &refunder_receive,
+                    &refunder_secret_key,
+                    fee_sats,
+                )
+                .context("build refund tx")
+            }
+            HtlcVersion::Taproot => {
+                let spec = TaprootHtlcSpec::parse_encoded_leaves(
+                    &hex::decode(&s.witness_script_hex).context("decode witness_script_hex")?,
+                )
+                .context("parse taproot leaves")?;
+                let genesis_hash = wallet.genesis_hash().context("get genesis hash")?;
+                taproot_refund_tx_script_path(
+                    &spec,
+                    &funding,
+                    &refunder_receive,
+                    &refunder_secret_key,
+                    fee_sats,
+                    genesis_hash,
+                )
+                .context("build taproot refund tx")
+            }
+        }
+    }
+
+    /// Re-checks the HTLC right before its invoice is paid, so the payer can still claim with the
+    /// preimage it buys.
+    fn ensure_htlc_payable(
+        wallet: &mut LiquidWallet,
+        cfg: &SwapServiceConfig,
+        s: &SwapRecord,
+    ) -> Result<(), Status> {
+        wallet
+            .sync()
+            .map_err(|e| Status::internal(format!("sync liquid wallet: {e:#}")))?;
+        let tip = wallet.tip_height();
+        if tip.saturating_add(cfg.payment_safety_blocks) >= s.refund_lock_height {
+            return Err(Status::failed_precondition(format!(
+                "tip {tip} is within {} blocks of refund_lock_height {}; the HTLC may be refunded \
+                 before the claim confirms",
+                cfg.payment_safety_blocks, s.refund_lock_height
+            )));
+        }
+
+        let funding_txid = Txid::from_str(&s.funding_txid)
+            .map_err(|e| Status::internal(format!("parse funding_txid: {e}")))?;
+        let htlc_script_pubkey = Address::from_str(&s.p2wsh_address)
+            .map_err(|e| Status::internal(format!("parse p2wsh_address: {e}")))?
+            .script_pubkey();
+        let confs = wallet
+            .tx_confirmations_for_script(&htlc_script_pubkey, &funding_txid)
+            .map_err(|e| Status::internal(format!("get funding tx confirmations: {e:#}")))?;
+        match confs {
+            Some(confs) if confs >= s.min_funding_confs => {}
+            Some(confs) => {
+                return Err(Status::failed_precondition(format!(
+                    "funding tx has {confs} confirmations, need {}",
+                    s.min_funding_confs
+                )));
+            }
+            None => {
+                return Err(Status::failed_precondition(
+                    "funding tx is no longer on chain",
+                ));
+            }
+        }
+
+        for vout in [s.asset_vout, s.lbtc_vout] {
+            let outpoint = OutPoint::new(funding_txid, vout);
+            let spend = wallet
+                .find_outpoint_spend(&htlc_script_pubkey, &outpoint)
+                .map_err(|e| Status::internal(format!("check htlc spend: {e:#}")))?;
+            if let Some(spend) = spend {
+                return Err(Status::failed_precondition(format!(
+                    "htlc output {outpoint} is already spent by {}",
+                    spend.txid()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn htlc_funding(wallet: &LiquidWallet, s: &SwapRecord) -> Result<HtlcFunding> {
+        let funding_txid = Txid::from_str(&s.funding_txid).context("parse funding_txid")?;
+        Ok(HtlcFunding {
+            funding_txid,
+            asset_vout: s.asset_vout,
+            lbtc_vout: s.lbtc_vout,
+            asset_id: AssetId::from_str(&s.asset_id).context("parse asset_id")?,
+            asset_amount: s.asset_amount,
+            policy_asset: wallet.policy_asset(),
+            fee_subsidy_sats: s.fee_subsidy_sats,
+            blinding: match &s.htlc_blinding_key_hex {
+                Some(blinding_key_hex) => Some(
+                    HtlcBlinding::from_funding_tx(
+                        &wallet
+                            .get_transaction(&funding_txid)
+                            .context("fetch funding tx")?,
+                        s.asset_vout,
+                        s.lbtc_vout,
+                        parse_blinding_key(blinding_key_hex)?,
+                    )
+                    .context("load htlc blinding")?,
+                ),
+                None => None,
+            },
+        })
+    }
+
+    fn refunder_key_index(cfg: &SwapServiceConfig, direction: SwapDirection) -> u32 {
+        match direction {
+            SwapDirection::LnToLiquid => cfg.seller_key_index,
+            SwapDirection::LiquidToLn => cfg.buyer_key_index,
+        }
+    }
+
+    fn stored_preimage(s: &SwapRecord) -> Result<[u8; 32]> {
+        hex::decode(
+            s.ln_preimage_hex
+                .as_deref()
+                .context("swap has not been paid yet")?,
+        )
+        .context("decode preimage_hex")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("preimage must be 32 bytes"))
+    }
+
+    fn build_claim_tx(
+        wallet: &mut LiquidWallet,
+        cfg: &SwapServiceConfig,
+        s: &SwapRecord,
+        preimage: [u8; 32],
+        fee_sats: u64,
+    ) -> Result<Transaction> {
+        let (claimer_key_index, expected_address) = match s.direction {
+            SwapDirection::LnToLiquid => {
+                (cfg.buyer_key_index, Some(s.buyer_liquid_address.as_str()))
+            }
+            SwapDirection::LiquidToLn => (cfg.seller_key_index, None),
+        };
+
+        let claimer_receive = wallet
+            .address_at(claimer_key_index)
+            .context("get claimer receive address")?;
+        if let Some(expected_address) = expected_address {
+            anyhow::ensure!(
+                claimer_receive.to_string() == expected_address,
+                "buyer_liquid_address mismatch"
+            );
+        }
+
+        let claimer_secret_key = derive_secret_key(wallet.signer(), claimer_key_index)
+            .context("derive claimer secret key")?;
+        let funding = Self::htlc_funding(wallet, s)?;
+
+        match s.htlc_version {
+            HtlcVersion::P2wsh => {
+                let witness_script: Script = s
+                    .witness_script_hex
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("parse witness_script_hex: {e:?}"))?;
+                claim_tx_from_witness_script(
+                    &witness_script,
+                    &funding,
+                    &claimer_receive,
+                    &claimer_secret_key,
+                    preimage,
+                    fee_sats,
+                )
+                .context("build claim tx")
+            }
+            HtlcVersion::Taproot => {
+                let spec = TaprootHtlcSpec::parse_encoded_leaves(
+                    &hex::decode(&s.witness_script_hex).context("decode witness_script_hex")?,
+                )
+                .context("parse taproot leaves")?;
+                let genesis_hash = wallet.genesis_hash().context("get genesis hash")?;
+                taproot_claim_tx_script_path(
+                    &spec,
+                    &funding,
+                    &claimer_receive,
+                    &claimer_secret_key,
+                    preimage,
+                    fee_sats,
+                    genesis_hash,
+                )
+                .context("build taproot claim tx")
+            }
+        }
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Hex-encoded SHA-256 of an API key secret, as stored in `ApiKeyRecord::secret_hash`.
+pub(crate) fn api_key_secret_hash(secret: &str) -> String {
+    hex::encode(sha256::Hash::hash(secret.as_bytes()).to_byte_array())
 }
 
 #[tonic::async_trait]
@@ -327,8 +1594,12 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
 
         let asset_id = AssetId::from_str(&req.asset_id)
             .map_err(|e| Status::invalid_argument(format!("invalid asset_id: {e}")))?;
-        if asset_id != self.cfg.sell_asset_id {
-            return Err(Status::failed_precondition("unsupported asset_id"));
+        let (asset_offer, paused) = self
+            .current_offer(&asset_id)
+            .map_err(|e| Status::internal(format!("get offer: {e:#}")))?
+            .ok_or_else(|| Status::failed_precondition("unsupported asset_id"))?;
+        if paused {
+            return Err(Status::failed_precondition("offer paused"));
         }
 
         let min_funding_confs = req.min_funding_confs;
@@ -338,20 +1609,58 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             )));
         }
 
-        let offer = self.current_offer();
-        if !offer
-            .supported_directions
-            .contains(&(Self::direction_to_proto(direction) as i32))
-        {
+        if !asset_offer.supports(direction) {
             return Err(Status::failed_precondition("unsupported direction"));
         }
+        self.cfg
+            .check_offer_timelock(&asset_offer)
+            .map_err(|e| Status::failed_precondition(format!("{e:#}")))?;
+        if asset_amount < asset_offer.min_asset_amount {
+            return Err(Status::failed_precondition(format!(
+                "asset_amount must be >= {}",
+                asset_offer.min_asset_amount
+            )));
+        }
+        if asset_offer.max_asset_amount != 0 && asset_amount > asset_offer.max_asset_amount {
+            return Err(Status::failed_precondition(format!(
+                "asset_amount must be <= {}",
+                asset_offer.max_asset_amount
+            )));
+        }
+
+        let offer = self.offer_to_proto(&asset_offer);
 
         let offer_id = Self::offer_id(&offer);
-        let total_price_msat = asset_amount
-            .checked_mul(offer.price_msat_per_asset_unit)
+        let price = self.source_price(&asset_offer).await?;
+        let pricing = &self.cfg.pricing;
+        let total_price_msat = pricing
+            .total_price_msat(price.price_msat_per_asset_unit, asset_amount)
             .ok_or_else(|| Status::invalid_argument("total_price_msat overflow"))?;
+        if total_price_msat < pricing.min_notional_msat {
+            return Err(Status::failed_precondition(format!(
+                "total_price_msat must be >= {}",
+                pricing.min_notional_msat
+            )));
+        }
+        if pricing.max_notional_msat != 0 && total_price_msat > pricing.max_notional_msat {
+            return Err(Status::failed_precondition(format!(
+                "total_price_msat must be <= {}",
+                pricing.max_notional_msat
+            )));
+        }
+        for cap in [
+            self.cfg.max_open_notional_msat,
+            self.cfg.max_buyer_open_notional_msat,
+        ] {
+            if cap != 0 && total_price_msat > cap {
+                return Err(Status::failed_precondition(format!(
+                    "total_price_msat exceeds the open notional cap of {cap} msat"
+                )));
+            }
+        }
 
         let quote_id = Uuid::new_v4().to_string();
+        let created_at = unix_now();
         let record = QuoteRecord {
             quote_id: quote_id.clone(),
             offer_id: offer_id.clone(),
@@ -365,13 +1674,66 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             refund_delta_blocks: offer.refund_delta_blocks,
             invoice_expiry_secs: offer.invoice_expiry_secs,
             max_min_funding_confs: offer.max_min_funding_confs,
+            supported_directions: asset_offer.supported_directions.clone(),
+            min_asset_amount: offer.min_asset_amount,
+            max_asset_amount: offer.max_asset_amount,
+            max_open_notional_msat: offer.max_open_notional_msat,
+            max_buyer_open_notional_msat: offer.max_buyer_open_notional_msat,
+            price_source: price.source,
+            source_price_msat_per_asset_unit: price.price_msat_per_asset_unit,
+            spread_bps: pricing.spread_bps_for(asset_amount),
+            priced_at: price.observed_at,
             swap_id: None,
+            buyer_id: None,
+            created_at,
+            expires_at: created_at.saturating_add(u64::from(self.cfg.quote_ttl_secs)),
         };
 
-        self.store
-            .lock()
-            .expect("store mutex poisoned")
-            .insert_quote(&record)
+        let needed = {
+            let mut wallet = self.wallet.lock().expect("wallet mutex poisoned");
+            let policy_asset = wallet.policy_asset();
+            // Asset id -> (amount to reserve, wallet balance).
+            let mut needed: BTreeMap<AssetId, (u64, u64)> =
+                BTreeMap::from([(asset_id, (asset_amount, 0))]);
+            if offer.fee_subsidy_sats != 0 {
+                needed.entry(policy_asset).or_insert((0, 0)).0 += offer.fee_subsidy_sats;
+            }
+            if self.cfg.check_inventory {
+                // Funded swaps no longer hold a reservation, so the balance must already exclude
+                // their funding outputs.
+                wallet
+                    .sync()
+                    .map_err(|e| Status::internal(format!("sync liquid wallet: {e:#}")))?;
+                for (asset, (_, balance)) in needed.iter_mut() {
+                    *balance = wallet
+                        .balance(asset)
+                        .map_err(|e| Status::internal(format!("get wallet balance: {e:#}")))?;
+                }
+            }
+            needed
+        };
+
+        let mut store = self.store.lock().expect("store mutex poisoned");
+        if self.cfg.check_inventory {
+            let reserved = store
+                .reserved_inventory(created_at)
+                .map_err(|e| Status::internal(format!("get reserved inventory: {e:#}")))?;
+            for (asset, (amount, balance)) in &needed {
+                let available =
+                    balance.saturating_sub(reserved.get(&asset.to_string()).copied().unwrap_or(0));
+                if *amount > available {
+                    return Err(Status::failed_precondition(format!(
+                        "insufficient inventory of {asset}: {available} available, {amount} needed"
+                    )));
+                }
+            }
+        }
+        let amounts = needed
+            .iter()
+            .map(|(asset, (amount, _))| (asset.to_string(), *amount))
+            .collect::<Vec<_>>();
+        store
+            .insert_quote_with_reservations(&record, &amounts)
             .map_err(|e| Status::internal(format!("persist quote: {e:#}")))?;
 
         Ok(Response::new(Self::quote_record_to_proto(&record)))
@@ -381,7 +1743,7 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
         &self,
         request: Request<pb::GetQuoteRequest>,
     ) -> Result<Response<pb::Quote>, Status> {
-        let _caller = self.require_authenticated(&request).map_err(Status::from)?;
+        let caller = self.require_authenticated(&request).map_err(Status::from)?;
         let req = request.into_inner();
         if req.quote_id.trim().is_empty() {
             return Err(Status::invalid_argument("quote_id is required"));
@@ -393,16 +1755,79 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             .expect("store mutex poisoned")
             .get_quote(&req.quote_id)
             .map_err(|e| Status::internal(format!("get quote: {e:#}")))?
+            .filter(|quote| {
+                caller.role == CallerRole::Seller
+                    || quote
+                        .buyer_id
+                        .as_ref()
+                        .is_none_or(|owner| *owner == caller.owner)
+            })
             .ok_or_else(|| Status::not_found("quote not found"))?;
 
         Ok(Response::new(Self::quote_record_to_proto(&record)))
     }
 
+    async fn list_offers(
+        &self,
+        request: Request<pb::ListOffersRequest>,
+    ) -> Result<Response<pb::ListOffersResponse>, Status> {
+        let _caller = self.require_authenticated(&request).map_err(Status::from)?;
+
+        let offers = self
+            .current_offers()
+            .map_err(|e| Status::internal(format!("list offers: {e:#}")))?;
+        Ok(Response::new(pb::ListOffersResponse {
+            offers: offers
+                .iter()
+                .filter(|(_, paused)| !paused)
+                .map(|(offer, _)| self.offer_to_proto(offer))
+                .collect(),
+        }))
+    }
+
+    async fn list_quotes(
+        &self,
+        request: Request<pb::ListQuotesRequest>,
+    ) -> Result<Response<pb::ListQuotesResponse>, Status> {
+        self.require_seller(&request).map_err(Status::from)?;
+        let req = request.into_inner();
+
+        let filter = QuoteFilter {
+            direction: Self::direction_filter(req.direction)?,
+            asset_id: Self::asset_id_filter(&req.asset_id)?,
+            created_after: (req.created_after > 0).then_some(req.created_after),
+            created_before: (req.created_before > 0).then_some(req.created_before),
+        };
+        let page_size = Self::page_size(req.page_size)?;
+        let after = Self::decode_page_token(&req.page_token)?;
+
+        let mut records = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .list_quotes_page(&filter, after.as_ref(), page_size + 1)
+            .map_err(|e| Status::internal(format!("list quotes: {e:#}")))?;
+        let next_page_token = if records.len() > page_size {
+            records.truncate(page_size);
+            records
+                .last()
+                .map(|r| Self::encode_page_token(r.created_at, &r.quote_id))
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        Ok(Response::new(pb::ListQuotesResponse {
+            quotes: records.iter().map(Self::quote_record_to_proto).collect(),
+            next_page_token,
+        }))
+    }
+
     async fn create_swap(
         &self,
         request: Request<pb::CreateSwapRequest>,
     ) -> Result<Response<pb::Swap>, Status> {
-        self.require_buyer(&request).map_err(Status::from)?;
+        let caller = self.require_buyer(&request).map_err(Status::from)?;
         let req = request.into_inner();
         if req.quote_id.trim().is_empty() {
             return Err(Status::invalid_argument("quote_id is required"));
@@ -419,30 +1844,42 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             .ok_or_else(|| Status::not_found("quote not found"))?;
 
         if let Some(existing_swap_id) = quote.swap_id.clone() {
+            // Retrying CreateSwap returns the caller's swap; anyone else only learns the quote is taken.
+            if quote.buyer_id.as_deref() != Some(caller.owner.as_str()) {
+                return Err(Status::failed_precondition("quote already used"));
+            }
             let record = self
                 .store
                 .lock()
                 .expect("store mutex poisoned")
                 .get_swap(&existing_swap_id)
                 .map_err(|e| Status::internal(format!("get swap: {e:#}")))?
-                .ok_or_else(|| Status::internal("quote refers to missing swap"))?;
+                .ok_or_else(|| Status::failed_precondition("quote already used"))?;
+            Self::require_swap_owner(&caller, &record)?;
             let swap = Self::swap_record_to_proto(&record)
                 .map_err(|e| Status::internal(format!("encode swap: {e:#}")))?;
             return Ok(Response::new(swap));
         }
 
-        let current_offer = self.current_offer();
-        let current_offer_id = Self::offer_id(&current_offer);
-        if current_offer_id != quote.offer_id {
+        if unix_now() >= quote.expires_at {
             return Err(Status::failed_precondition(
-                "offer changed since quoting; retry CreateQuote",
+                "quote expired; retry CreateQuote",
             ));
         }
 
         let asset_id = AssetId::from_str(&quote.asset_id)
             .map_err(|e| Status::invalid_argument(format!("invalid asset_id: {e}")))?;
-        if asset_id != self.cfg.sell_asset_id {
-            return Err(Status::failed_precondition("unsupported asset_id"));
+        let (current_offer, paused) = self
+            .current_offer(&asset_id)
+            .map_err(|e| Status::internal(format!("get offer: {e:#}")))?
+            .ok_or_else(|| Status::failed_precondition("unsupported asset_id"))?;
+        if paused {
+            return Err(Status::failed_precondition("offer paused"));
+        }
+        if Self::offer_id(&self.offer_to_proto(&current_offer)) != quote.offer_id {
+            return Err(Status::failed_precondition(
+                "offer changed since quoting; retry CreateQuote",
+            ));
         }
 
         let buyer_liquid_address = Address::from_str(&req.buyer_liquid_address)
@@ -455,12 +1892,44 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             )));
         }
 
-        let buyer_pubkey_hash160 = pubkey_hash160_from_p2wpkh_address(&buyer_liquid_address)
-            .map_err(|e| {
+        let buyer_claim_pubkey = if req.buyer_claim_pubkey.is_empty() {
+            None
+        } else {
+            if quote.direction != SwapDirection::LnToLiquid {
+                return Err(Status::invalid_argument(
+                    "buyer_claim_pubkey is only supported for LN_TO_LIQUID swaps",
+                ));
+            }
+            if req.buyer_claim_pubkey.len() != 33 {
+                return Err(Status::invalid_argument(
+                    "buyer_claim_pubkey must be a 33-byte compressed public key",
+                ));
+            }
+            let pubkey = PublicKey::from_slice(&req.buyer_claim_pubkey).map_err(|e| {
+                Status::invalid_argument(format!("invalid buyer_claim_pubkey: {e}"))
+            })?;
+            Some(pubkey)
+        };
+
+        let htlc_version = match pb::HtlcVersion::try_from(req.htlc_version) {
+            Ok(pb::HtlcVersion::Unspecified | pb::HtlcVersion::P2wsh) => HtlcVersion::P2wsh,
+            Ok(pb::HtlcVersion::Taproot) => HtlcVersion::Taproot,
+            Err(_) => {
+                return Err(Status::invalid_argument(
+                    "htlc_version must be a valid HtlcVersion",
+                ));
+            }
+        };
+        let buyer_pubkey_hash160 = match &buyer_claim_pubkey {
+            Some(pubkey) => pubkey_hash160(&pubkey.serialize()),
+            None => pubkey_hash160_from_p2wpkh_address(&buyer_liquid_address).map_err(|e| {
                 Status::invalid_argument(format!(
                     "buyer_liquid_address must be a P2WPKH address: {e}"
                 ))
-            })?;
+            })?,
+        };
+        let buyer_claim_pubkey_hex =
+            buyer_claim_pubkey.map(|pubkey| hex::encode(pubkey.serialize()));
 
         let params = self
             .wallet
@@ -474,6 +1943,46 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             ));
         }
 
+        let htlc_blinding_key = if req.confidential {
+            Some(
+                ZkpSecretKey::from_slice(&rand::random::<[u8; 32]>())
+                    .map_err(|e| Status::internal(format!("generate blinding key: {e}")))?,
+            )
+        } else {
+            None
+        };
+
+        let taproot_keys = match htlc_version {
+            HtlcVersion::P2wsh => None,
+            HtlcVersion::Taproot => {
+                let secp = Secp256k1::new();
+                let wallet = self.wallet.lock().expect("wallet mutex poisoned");
+                let seller_secret_key =
+                    derive_secret_key(wallet.signer(), self.cfg.seller_key_index)
+                        .map_err(|e| Status::internal(format!("derive seller key: {e:#}")))?;
+                let buyer_pubkey = match buyer_claim_pubkey {
+                    Some(pubkey) => pubkey,
+                    None => {
+                        let buyer_secret_key =
+                            derive_secret_key(wallet.signer(), self.cfg.buyer_key_index).map_err(
+                                |e| Status::internal(format!("derive buyer key: {e:#}")),
+                            )?;
+                        let buyer_pubkey = buyer_secret_key.public_key(&secp);
+                        if pubkey_hash160(&buyer_pubkey.serialize()) != buyer_pubkey_hash160 {
+                            return Err(Status::invalid_argument(
+                                "taproot HTLCs require buyer_claim_pubkey or a buyer_liquid_address that belongs to the server-held buyer key",
+                            ));
+                        }
+                        buyer_pubkey
+                    }
+                };
+                Some((
+                    seller_secret_key.x_only_public_key(&secp).0,
+                    buyer_pubkey.x_only_public_key().0,
+                ))
+            }
+        };
+
         let swap_id = Uuid::new_v4().to_string();
         let (invoice, payment_hash) = match quote.direction {
             SwapDirection::LnToLiquid => {
@@ -520,6 +2029,22 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
                         "buyer_bolt11_invoice is already expired",
                     ));
                 }
+                let secs_until_expiry = secs_until_expiry_bolt11(buyer_invoice)
+                    .map_err(|e| Status::invalid_argument(format!("invalid invoice: {e:#}")))?;
+                let min_final_cltv_expiry_delta =
+                    min_final_cltv_expiry_delta_from_bolt11(buyer_invoice)
+                        .map_err(|e| Status::invalid_argument(format!("invalid invoice: {e:#}")))?;
+                // The HTLC is funded right after this, so its refund height is
+                // tip + refund_delta_blocks.
+                check_buyer_invoice(
+                    quote.refund_delta_blocks,
+                    secs_until_expiry,
+                    min_final_cltv_expiry_delta,
+                    self.cfg.min_timelock_margin_blocks,
+                )
+                .map_err(|e| {
+                    Status::invalid_argument(format!("unsafe buyer_bolt11_invoice: {e:#}"))
+                })?;
                 let payment_hash = payment_hash_from_bolt11(buyer_invoice)
                     .map_err(|e| Status::invalid_argument(format!("invalid invoice: {e:#}")))?;
                 (buyer_invoice.to_string(), payment_hash)
@@ -528,19 +2053,56 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
 
         let payment_hash_hex = hex::encode(payment_hash);
 
+        let reserved = {
+            // Checked and reserved under one store lock so concurrent swaps cannot both fit.
+            let mut store = self.store.lock().expect("store mutex poisoned");
+            let open = store
+                .open_notional(&caller.owner)
+                .map_err(|e| Status::internal(format!("get open notional: {e:#}")))?;
+            let caps = [
+                (
+                    "open notional",
+                    self.cfg.max_open_notional_msat,
+                    open.total_msat,
+                ),
+                (
+                    "buyer open notional",
+                    self.cfg.max_buyer_open_notional_msat,
+                    open.buyer_msat,
+                ),
+            ];
+            for (what, cap, open) in caps {
+                if cap != 0 && open.saturating_add(quote.total_price_msat) > cap {
+                    return Err(Status::failed_precondition(format!(
+                        "{what} cap exceeded: {open} msat open, {} msat quoted, cap {cap} msat",
+                        quote.total_price_msat
+                    )));
+                }
+            }
+            store
+                .reserve_quote(&quote.quote_id, &swap_id, &caller.owner)
+                .map_err(|e| Status::internal(format!("reserve quote: {e:#}")))?
+        };
+        if !reserved {
+            return Err(Status::failed_precondition("quote already used"));
+        }
+
         let wallet = self.wallet.clone();
         let store = self.store.clone();
         let cfg = self.cfg.clone();
         let quote_id = quote.quote_id.clone();
+        let owner = caller.owner;
 
         let direction = quote.direction;
         let record = tokio::task::spawn_blocking(move || -> Result<SwapRecord> {
-            let (mut record, htlc_script_pubkey, funding_txid) = {
-                let mut wallet = wallet.lock().expect("wallet mutex poisoned");
+            let mut wallet = wallet.lock().expect("wallet mutex poisoned");
+            // The quote is released if nothing was broadcast, so the buyer can retry it.
+            let funded = (|| -> Result<_> {
                 wallet.sync().context("sync wallet")?;
 
-                let refund_lock_height =
-                    wallet.tip_height().saturating_add(cfg.refund_delta_blocks);
+                let refund_lock_height = wallet
+                    .tip_height()
+                    .saturating_add(quote.refund_delta_blocks);
                 let seller_refund_address = wallet
                     .address_at(cfg.seller_key_index)
                     .context("get seller refund address")?;
@@ -553,74 +2115,119 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
                     SwapDirection::LiquidToLn => (seller_pubkey_hash160, buyer_pubkey_hash160),
                 };
 
-                let spec = HtlcSpec {
-                    payment_hash,
-                    claimer_pubkey_hash160,
-                    refunder_pubkey_hash160,
-                    refund_lock_height,
+                let (htlc_address, witness_script) = match taproot_keys {
+                    None => {
+                        let spec = HtlcSpec {
+                            payment_hash,
+                            claimer_pubkey_hash160,
+                            refunder_pubkey_hash160,
+                            refund_lock_height,
+                        };
+                        (spec.p2wsh_address(params), spec.witness_script().to_bytes())
+                    }
+                    Some((seller_pubkey, buyer_pubkey)) => {
+                        let (claimer_pubkey, refunder_pubkey) = match direction {
+                            SwapDirection::LnToLiquid => (buyer_pubkey, seller_pubkey),
+                            SwapDirection::LiquidToLn => (seller_pubkey, buyer_pubkey),
+                        };
+                        let spec = TaprootHtlcSpec {
+                            payment_hash,
+                            claimer_pubkey,
+                            refunder_pubkey,
+                            refund_lock_height,
+                        };
+                        (
+                            spec.p2tr_address(params)
+                                .context("derive taproot address")?,
+                            spec.encoded_leaves(),
+                        )
+                    }
                 };
 
-                let witness_script = spec.witness_script();
-                let htlc_address = spec.p2wsh_address(params);
-                let htlc_script_pubkey = htlc_address.script_pubkey();
-
-                let (_tx, funding_txid, asset_vout, lbtc_vout) = wallet
+                let funding = wallet
                     .build_and_broadcast_funding(
                         &htlc_address,
-                        cfg.sell_asset_id,
+                        asset_id,
                         quote.asset_amount,
-                        cfg.fee_subsidy_sats,
+                        quote.fee_subsidy_sats,
+                        htlc_blinding_key,
                     )
                     .context("fund htlc")?;
+                Ok((refund_lock_height, htlc_address, witness_script, funding))
+            })();
+            let (
+                refund_lock_height,
+                htlc_address,
+                witness_script,
+                (funding_tx, funding_txid, asset_vout, lbtc_vout),
+            ) = match funded {
+                Ok(funded) => funded,
+                Err(e) => {
+                    if let Err(release_err) = store
+                        .lock()
+                        .expect("store mutex poisoned")
+                        .release_quote(&quote_id, &swap_id)
+                    {
+                        tracing::warn!(
+                            %swap_id,
+                            error = %format!("{release_err:#}"),
+                            "release quote failed"
+                        );
+                    }
+                    return Err(e);
+                }
+            };
+            let htlc_address = match htlc_blinding_key {
+                Some(blinding_key) => htlc_address.to_confidential(ZkpPublicKey::from_secret_key(
+                    &ZkpSecp256k1::new(),
+                    &blinding_key,
+                )),
+                None => htlc_address,
+            };
 
-                let record = SwapRecord {
-                    swap_id: swap_id.clone(),
-                    quote_id: quote_id.clone(),
-                    direction,
-                    bolt11_invoice: invoice.clone(),
-                    payment_hash: payment_hash_hex.clone(),
-                    asset_id: cfg.sell_asset_id.to_string(),
-                    asset_amount: quote.asset_amount,
-                    total_price_msat: quote.total_price_msat,
-                    buyer_liquid_address: buyer_liquid_address.to_string(),
-                    fee_subsidy_sats: cfg.fee_subsidy_sats,
-                    refund_lock_height,
-                    p2wsh_address: htlc_address.to_string(),
-                    witness_script_hex: hex::encode(witness_script.to_bytes()),
-                    funding_txid: funding_txid.to_string(),
-                    asset_vout,
-                    lbtc_vout,
-                    min_funding_confs,
-                    ln_payment_id: None,
-                    ln_preimage_hex: None,
-                    claim_txid: None,
-                    status: SwapStatus::Created,
-                };
-
-                let mut store = store.lock().expect("store mutex poisoned");
-                store.insert_swap(&record).context("persist swap")?;
-                store
-                    .set_quote_swap_id(&quote_id, &swap_id)
-                    .context("link quote to swap")?;
-
-                Ok::<_, anyhow::Error>((record, htlc_script_pubkey, funding_txid))
-            }?;
-
-            let _confs = Self::wait_for_funding_confirmations(
-                &wallet,
-                &htlc_script_pubkey,
-                &funding_txid,
+            let record = SwapRecord {
+                swap_id: swap_id.clone(),
+                quote_id: quote_id.clone(),
+                direction,
+                bolt11_invoice: invoice.clone(),
+                payment_hash: payment_hash_hex.clone(),
+                asset_id: asset_id.to_string(),
+                asset_amount: quote.asset_amount,
+                total_price_msat: quote.total_price_msat,
+                buyer_liquid_address: buyer_liquid_address.to_string(),
+                fee_subsidy_sats: quote.fee_subsidy_sats,
+                refund_lock_height,
+                p2wsh_address: htlc_address.to_string(),
+                witness_script_hex: hex::encode(witness_script),
+                funding_txid: funding_txid.to_string(),
+                asset_vout,
+                lbtc_vout,
                 min_funding_confs,
-                Duration::from_secs(300),
-            )
-            .context("wait funding confirmations")?;
+                ln_payment_id: None,
+                ln_preimage_hex: None,
+                claim_txid: None,
+                refund_txid: None,
+                status: SwapStatus::Created,
+                buyer_claim_pubkey_hex,
+                htlc_version,
+                htlc_blinding_key_hex: htlc_blinding_key
+                    .map(|blinding_key| hex::encode(blinding_key.secret_bytes())),
+                owner,
+                created_at: unix_now(),
+            };
 
-            record.status = SwapStatus::Funded;
             let mut store = store.lock().expect("store mutex poisoned");
+            store.insert_swap(&record).context("persist swap")?;
             store
-                .update_swap_status(&record.swap_id, SwapStatus::Funded)
-                .context("update swap status")?;
-
+                .put_swap_tx(
+                    &swap_id,
+                    SwapTxKind::Funding,
+                    &funding_txid.to_string(),
+                    &serialize_hex(&funding_tx),
+                )
+                .context("persist funding tx")?;
+
+            tracing::info!(%swap_id, %funding_txid, "swap created");
             Ok(record)
         })
         .await
@@ -637,7 +2244,7 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
         &self,
         request: Request<pb::GetSwapRequest>,
     ) -> Result<Response<pb::Swap>, Status> {
-        let _caller = self.require_authenticated(&request).map_err(Status::from)?;
+        let caller = self.require_authenticated(&request).map_err(Status::from)?;
         let req = request.into_inner();
         if req.swap_id.trim().is_empty() {
             return Err(Status::invalid_argument("swap_id is required"));
@@ -650,12 +2257,85 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             .get_swap(&req.swap_id)
             .map_err(|e| Status::internal(format!("get swap: {e:#}")))?
             .ok_or_else(|| Status::not_found("swap not found"))?;
+        Self::require_swap_owner(&caller, &record)?;
 
         let swap = Self::swap_record_to_proto(&record)
             .map_err(|e| Status::internal(format!("encode swap: {e:#}")))?;
         Ok(Response::new(swap))
     }
 
+    async fn list_swaps(
+        &self,
+        request: Request<pb::ListSwapsRequest>,
+    ) -> Result<Response<pb::ListSwapsResponse>, Status> {
+        self.require_seller(&request).map_err(Status::from)?;
+        let req = request.into_inner();
+
+        let filter = SwapFilter {
+            status: Self::status_filter(req.status)?,
+            direction: Self::direction_filter(req.direction)?,
+            asset_id: Self::asset_id_filter(&req.asset_id)?,
+            created_after: (req.created_after > 0).then_some(req.created_after),
+            created_before: (req.created_before > 0).then_some(req.created_before),
+        };
+        let page_size = Self::page_size(req.page_size)?;
+        let after = Self::decode_page_token(&req.page_token)?;
+
+        let mut records = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .list_swaps_page(&filter, after.as_ref(), page_size + 1)
+            .map_err(|e| Status::internal(format!("list swaps: {e:#}")))?;
+        let next_page_token = if records.len() > page_size {
+            records.truncate(page_size);
+            records
+                .last()
+                .map(|r| Self::encode_page_token(r.created_at, &r.swap_id))
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        let swaps = records
+            .iter()
+            .map(Self::swap_record_to_proto)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| Status::internal(format!("encode swap: {e:#}")))?;
+        Ok(Response::new(pb::ListSwapsResponse {
+            swaps,
+            next_page_token,
+        }))
+    }
+
+    type WatchSwapStream = ReceiverStream<Result<pb::SwapEvent, Status>>;
+
+    async fn watch_swap(
+        &self,
+        request: Request<pb::WatchSwapRequest>,
+    ) -> Result<Response<Self::WatchSwapStream>, Status> {
+        let caller = self.require_authenticated(&request).map_err(Status::from)?;
+        let req = request.into_inner();
+        if req.swap_id.trim().is_empty() {
+            return Err(Status::invalid_argument("swap_id is required"));
+        }
+
+        let record = self
+            .store
+            .lock()
+            .expect("store mutex poisoned")
+            .get_swap(&req.swap_id)
+            .map_err(|e| Status::internal(format!("get swap: {e:#}")))?
+            .ok_or_else(|| Status::not_found("swap not found"))?;
+        Self::require_swap_owner(&caller, &record)?;
+
+        let (tx, rx) = mpsc::channel(16);
+        let svc = self.clone();
+        tokio::spawn(async move { svc.stream_swap_events(req.swap_id, tx).await });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
     async fn create_lightning_payment(
         &self,
         request: Request<pb::CreateLightningPaymentRequest>,
@@ -673,12 +2353,13 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             .get_swap(&req.swap_id)
             .map_err(|e| Status::internal(format!("get swap: {e:#}")))?
             .ok_or_else(|| Status::not_found("swap not found"))?;
+        Self::require_swap_owner(&caller, &record)?;
 
         let direction = Self::direction_to_proto(record.direction);
         let parties = Self::parties_for_direction(direction);
         let ln_payer =
             pb::SwapRole::try_from(parties.ln_payer).unwrap_or(pb::SwapRole::Unspecified);
-        Self::require_swap_role(caller, ln_payer, "ln_payer").map_err(Status::from)?;
+        Self::require_swap_role(&caller, ln_payer, "ln_payer").map_err(Status::from)?;
 
         if let (Some(payment_id), Some(preimage_hex)) =
             (record.ln_payment_id.clone(), record.ln_preimage_hex.clone())
@@ -695,32 +2376,41 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             return Err(Status::failed_precondition("swap is not funded"));
         }
 
-        let payment_id = self
-            .ln
-            .pay_invoice(record.bolt11_invoice.clone())
-            .await
-            .map_err(|e| Status::internal(format!("pay invoice: {e:#}")))?;
-
         let timeout_secs = if req.payment_timeout_secs == 0 {
             DEFAULT_PAYMENT_TIMEOUT_SECS
         } else {
             u64::from(req.payment_timeout_secs)
         };
-        let preimage = self
-            .ln
-            .wait_preimage(&payment_id, Duration::from_secs(timeout_secs))
-            .await
-            .map_err(|e| Status::internal(format!("wait preimage: {e:#}")))?;
-
-        let expected_payment_hash =
-            hex::decode(&record.payment_hash).map_err(|e| Status::internal(format!("{e:#}")))?;
-        let expected_payment_hash: [u8; 32] = expected_payment_hash
-            .try_into()
-            .map_err(|_| Status::internal("payment_hash must be 32 bytes"))?;
-        let got_payment_hash = sha256_preimage(&preimage);
-        if got_payment_hash != expected_payment_hash {
-            return Err(Status::internal("preimage hash mismatch"));
-        }
+        let timeout = Duration::from_secs(timeout_secs);
+        let LightningPaymentResult {
+            payment_id,
+            preimage,
+        } = match record.ln_payment_id.as_deref() {
+            Some(payment_id) => {
+                resume_lightning_payment(self.ln.as_ref(), &record, payment_id, timeout).await?
+            }
+            None => {
+                let wallet = self.wallet.clone();
+                let cfg = self.cfg.clone();
+                let checked = record.clone();
+                tokio::task::spawn_blocking(move || {
+                    let mut wallet = wallet.lock().expect("wallet mutex poisoned");
+                    Self::ensure_htlc_payable(&mut wallet, &cfg, &checked)
+                })
+                .await
+                .map_err(|e| Status::internal(format!("join: {e}")))??;
+
+                let store = self.store.clone();
+                let swap_id = record.swap_id.clone();
+                execute_lightning_payment_with(self.ln.as_ref(), &record, timeout, |payment_id| {
+                    store
+                        .lock()
+                        .expect("store mutex poisoned")
+                        .set_swap_payment_id(&swap_id, Some(payment_id))
+                })
+                .await?
+            }
+        };
 
         let preimage_hex = hex::encode(preimage);
         self.store
@@ -758,100 +2448,57 @@ impl pb::swap_service_server::SwapService for SwapServiceImpl {
             .get_swap(&req.swap_id)
             .map_err(|e| Status::internal(format!("get swap: {e:#}")))?
             .ok_or_else(|| Status::not_found("swap not found"))?;
+        Self::require_swap_owner(&caller, &record)?;
 
         let direction = Self::direction_to_proto(record.direction);
         let parties = Self::parties_for_direction(direction);
         let liquid_claimer =
             pb::SwapRole::try_from(parties.liquid_claimer).unwrap_or(pb::SwapRole::Unspecified);
-        Self::require_swap_role(caller, liquid_claimer, "liquid_claimer").map_err(Status::from)?;
+        Self::require_swap_role(&caller, liquid_claimer, "liquid_claimer").map_err(Status::from)?;
 
         if let Some(claim_txid) = record.claim_txid.clone() {
             return Ok(Response::new(pb::AssetClaim { claim_txid }));
         }
 
-        let preimage_hex = record
-            .ln_preimage_hex
-            .clone()
-            .ok_or_else(|| Status::failed_precondition("swap has not been paid yet"))?;
-        let preimage: [u8; 32] = hex::decode(preimage_hex)
-            .map_err(|e| Status::internal(format!("decode preimage_hex: {e:#}")))?
-            .try_into()
-            .map_err(|_| Status::internal("preimage must be 32 bytes"))?;
+        if record.buyer_claim_pubkey_hex.is_some() {
+            return Err(Status::failed_precondition(
+                "swap uses a buyer-held claim key; the buyer must claim locally",
+            ));
+        }
 
-        let claim_fee_sats = if req.claim_fee_sats == 0 {
-            DEFAULT_CLAIM_FEE_SATS
-        } else {
-            req.claim_fee_sats
-        };
+        if record.ln_preimage_hex.is_none() {
+            return Err(Status::failed_precondition("swap has not been paid yet"));
+        }
+        let preimage = Self::stored_preimage(&record)
+            .map_err(|e| Status::internal(format!("load preimage: {e:#}")))?;
 
         let wallet = self.wallet.clone();
         let store = self.store.clone();
         let cfg = self.cfg.clone();
-        let record_swap_id = record.swap_id.clone();
-        let record_direction = record.direction;
-        let record_buyer_liquid_address = record.buyer_liquid_address.clone();
+        let fee_estimator = self.fee_estimator;
 
         let claim_txid = tokio::task::spawn_blocking(move || -> Result<String> {
             let mut wallet = wallet.lock().expect("wallet mutex poisoned");
             wallet.sync().context("sync liquid wallet")?;
 
-            let (claimer_key_index, expected_address) = match record_direction {
-                SwapDirection::LnToLiquid => (
-                    cfg.buyer_key_index,
-                    Some(record_buyer_liquid_address.as_str()),
-                ),
-                SwapDirection::LiquidToLn => (cfg.seller_key_index, None),
+            let tx = if req.claim_fee_sats == 0 {
+                let fee_rate = fee_estimator.fee_rate(&wallet);
+                build_at_fee_rate(fee_rate, |fee_sats| {
+                    Self::build_claim_tx(&mut wallet, &cfg, &record, preimage, fee_sats)
+                })?
+            } else {
+                Self::build_claim_tx(&mut wallet, &cfg, &record, preimage, req.claim_fee_sats)?
             };
 
-            let claimer_receive = wallet
-                .address_at(claimer_key_index)
-                .context("get claimer receive address")?;
-            if let Some(expected_address) = expected_address {
-                anyhow::ensure!(
-                    claimer_receive.to_string() == expected_address,
-                    "buyer_liquid_address mismatch"
-                );
-            }
-
-            let claimer_secret_key = derive_secret_key(wallet.signer(), claimer_key_index)
-                .context("derive claimer secret key")?;
-
-            let witness_script: Script = record
-                .witness_script_hex
-                .parse()
-                .map_err(|e| anyhow::anyhow!("parse witness_script_hex: {e:?}"))?;
-
-            let funding_txid =
-                Txid::from_str(&record.funding_txid).context("parse funding_txid")?;
-            let asset_id = AssetId::from_str(&record.asset_id).context("parse asset_id")?;
-            let funding = HtlcFunding {
-                funding_txid,
-                asset_vout: record.asset_vout,
-                lbtc_vout: record.lbtc_vout,
-                asset_id,
-                asset_amount: record.asset_amount,
-                policy_asset: wallet.policy_asset(),
-                fee_subsidy_sats: record.fee_subsidy_sats,
-            };
-
-            let tx = claim_tx_from_witness_script(
-                &witness_script,
-                &funding,
-                &claimer_receive,
-                &claimer_secret_key,
-                preimage,
-                claim_fee_sats,
-            )
-            .context("build claim tx")?;
-
+            // Persist only what the node accepted; the swap executor rebroadcasts it from here on.
             let txid = wallet
                 .broadcast_transaction(&tx)
                 .context("broadcast claim tx")?;
-
-            let mut store = store.lock().expect("store mutex poisoned");
             store
-                .upsert_swap_claim(&record_swap_id, &txid.to_string(), SwapStatus::Claimed)
-                .context("persist claim")?;
+                .lock()
+                .expect("store mutex poisoned")
+                .record_claim_broadcast(&record.swap_id, &txid.to_string(), &serialize_hex(&tx))
+                .context("persist claim tx")?;
 
             Ok(txid.to_string())
         })
@@ -1,16 +1,133 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context as _, Result};
 use rusqlite::{Connection, OptionalExtension as _, params};
+use tokio::sync::broadcast;
 
-use super::{QuoteRecord, SwapDirection, SwapRecord, SwapStatus};
+use super::{
+    ApiKeyRecord, ApiKeyRole, HtlcVersion, OfferVersionRecord, QuoteRecord, SwapDirection,
+    SwapRecord, SwapStatus, SwapTransition, SwapTxKind, SwapUpdate,
+};
+
+pub const SWAP_UPDATES_CAPACITY: usize = 256;
+
+const SWAP_COLUMNS: &str = r#"
+  swap_id,
+  quote_id,
+  direction,
+  bolt11_invoice,
+  payment_hash,
+  asset_id,
+  asset_amount,
+  total_price_msat,
+  buyer_liquid_address,
+  fee_subsidy_sats,
+  refund_lock_height,
+  p2wsh_address,
+  witness_script_hex,
+  funding_txid,
+  asset_vout,
+  lbtc_vout,
+  min_funding_confs,
+  ln_payment_id,
+  ln_preimage_hex,
+  claim_txid,
+  status,
+  buyer_claim_pubkey_hex,
+  htlc_version,
+  htlc_blinding_key_hex,
+  created_at,
+  owner,
+  refund_txid
+"#;
+
+const QUOTE_COLUMNS: &str = r#"
+  quote_id,
+  offer_id,
+  direction,
+  asset_id,
+  asset_amount,
+  min_funding_confs,
+  total_price_msat,
+  price_msat_per_asset_unit,
+  fee_subsidy_sats,
+  refund_delta_blocks,
+  invoice_expiry_secs,
+  max_min_funding_confs,
+  swap_id,
+  created_at,
+  expires_at,
+  supported_directions,
+  min_asset_amount,
+  max_asset_amount,
+  price_source,
+  source_price_msat_per_asset_unit,
+  spread_bps,
+  priced_at,
+  max_open_notional_msat,
+  max_buyer_open_notional_msat,
+  buyer_id
+"#;
+
+const API_KEY_COLUMNS: &str = r#"
+  key_id,
+  secret_hash,
+  role,
+  owner,
+  created_at,
+  revoked_at
+"#;
+
+const OFFER_VERSION_COLUMNS: &str = r#"
+  asset_id,
+  version,
+  supported_directions,
+  price_msat_per_asset_unit,
+  fee_subsidy_sats,
+  refund_delta_blocks,
+  min_asset_amount,
+  max_asset_amount,
+  paused,
+  created_at
+"#;
+
+#[derive(Debug, Clone, Default)]
+pub struct SwapFilter {
+    pub status: Option<SwapStatus>,
+    pub direction: Option<SwapDirection>,
+    pub asset_id: Option<String>,
+    pub created_after: Option<u64>,
+    pub created_before: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QuoteFilter {
+    pub direction: Option<SwapDirection>,
+    pub asset_id: Option<String>,
+    pub created_after: Option<u64>,
+    pub created_before: Option<u64>,
+}
+
+/// Position after the last row of a page, in `(created_at, id)` descending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub created_at: u64,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpenNotional {
+    pub total_msat: u64,
+    pub buyer_msat: u64,
+}
 
 #[derive(Debug)]
 pub struct SqliteStore {
     conn: Connection,
     path: PathBuf,
+    updates: broadcast::Sender<SwapUpdate>,
 }
 
 impl SqliteStore {
@@ -31,279 +148,448 @@ impl SqliteStore {
 
         migrate(&conn).context("migrate sqlite schema")?;
 
-        Ok(Self { conn, path })
+        let (updates, _) = broadcast::channel(SWAP_UPDATES_CAPACITY);
+        Ok(Self {
+            conn,
+            path,
+            updates,
+        })
     }
 
     pub fn path(&self) -> &Path {
         &self.path
     }
 
+    /// Every swap status change committed through this store, in commit order.
+    pub fn subscribe_swaps(&self) -> broadcast::Receiver<SwapUpdate> {
+        self.updates.subscribe()
+    }
+
     pub fn insert_quote(&mut self, record: &QuoteRecord) -> Result<()> {
-        self.conn
-            .execute(
+        insert_quote_row(&self.conn, record)
+    }
+
+    /// Inserts `record` and reserves `amounts` (asset id, amount) for it in one transaction.
+    ///
+    /// The reservation follows the quote: it lapses at `expires_at` unless a swap takes the quote,
+    /// and a swap keeps it until [`Self::insert_swap`] records the swap's funding.
+    pub fn insert_quote_with_reservations(
+        &mut self,
+        record: &QuoteRecord,
+        amounts: &[(String, u64)],
+    ) -> Result<()> {
+        let tx = self.conn.transaction().context("begin insert quote")?;
+        insert_quote_row(&tx, record)?;
+        for (asset_id, amount) in amounts {
+            tx.execute(
                 r#"
-INSERT INTO quotes (
-  quote_id,
-  offer_id,
-  direction,
-  asset_id,
-  asset_amount,
-  min_funding_confs,
-  total_price_msat,
-  price_msat_per_asset_unit,
-  fee_subsidy_sats,
-  refund_delta_blocks,
-  invoice_expiry_secs,
-  max_min_funding_confs,
-  swap_id
-) VALUES (
-  ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13
-)
+INSERT INTO inventory_reservations (quote_id, asset_id, amount, swap_id, expires_at)
+VALUES (?1, ?2, ?3, NULL, ?4)
 "#,
                 params![
                     &record.quote_id,
-                    &record.offer_id,
-                    direction_to_str(record.direction),
-                    &record.asset_id,
-                    record.asset_amount,
-                    record.min_funding_confs,
-                    record.total_price_msat,
-                    record.price_msat_per_asset_unit,
-                    record.fee_subsidy_sats,
-                    record.refund_delta_blocks,
-                    record.invoice_expiry_secs,
-                    record.max_min_funding_confs,
-                    record.swap_id.as_deref(),
+                    asset_id,
+                    *amount as i64,
+                    record.expires_at as i64
                 ],
             )
-            .with_context(|| format!("insert quote {}", record.quote_id))?;
+            .with_context(|| format!("reserve inventory quote_id={}", record.quote_id))?;
+        }
+        tx.commit().context("commit insert quote")?;
         Ok(())
     }
 
-    pub fn get_quote(&self, quote_id: &str) -> Result<Option<QuoteRecord>> {
+    /// Sums `total_price_msat` over quotes taken by swaps that are not yet claimed, refunded, or
+    /// failed: across all buyers, and for `buyer_id` alone.
+    pub fn open_notional(&self, buyer_id: &str) -> Result<OpenNotional> {
         self.conn
             .query_row(
                 r#"
 SELECT
-  quote_id,
-  offer_id,
-  direction,
-  asset_id,
-  asset_amount,
-  min_funding_confs,
-  total_price_msat,
-  price_msat_per_asset_unit,
-  fee_subsidy_sats,
-  refund_delta_blocks,
-  invoice_expiry_secs,
-  max_min_funding_confs,
-  swap_id
-FROM quotes
-WHERE quote_id = ?1
+  COALESCE(SUM(q.total_price_msat), 0),
+  COALESCE(SUM(CASE WHEN q.buyer_id = ?1 THEN q.total_price_msat ELSE 0 END), 0)
+FROM quotes q
+LEFT JOIN swaps s ON s.swap_id = q.swap_id
+WHERE q.swap_id IS NOT NULL
+  AND (s.status IS NULL OR s.status NOT IN ('claimed', 'refunded', 'failed'))
 "#,
-                params![quote_id],
+                params![buyer_id],
                 |row| {
-                    let asset_amount: i64 = row.get(4)?;
-                    let min_funding_confs: i64 = row.get(5)?;
-                    let total_price_msat: i64 = row.get(6)?;
-                    let price_msat_per_asset_unit: i64 = row.get(7)?;
-                    let fee_subsidy_sats: i64 = row.get(8)?;
-                    let refund_delta_blocks: i64 = row.get(9)?;
-                    let invoice_expiry_secs: i64 = row.get(10)?;
-                    let max_min_funding_confs: i64 = row.get(11)?;
-                    let direction_str: String = row.get(2)?;
-                    let direction = direction_from_str(&direction_str, 2)?;
-
-                    Ok(QuoteRecord {
-                        quote_id: row.get(0)?,
-                        offer_id: row.get(1)?,
-                        direction,
-                        asset_id: row.get(3)?,
-                        asset_amount: u64::try_from(asset_amount).map_err(|_| {
-                            rusqlite::Error::FromSqlConversionFailure(
-                                4,
-                                rusqlite::types::Type::Integer,
-                                format!("invalid asset_amount {asset_amount}").into(),
-                            )
-                        })?,
-                        min_funding_confs: u32::try_from(min_funding_confs).map_err(|_| {
-                            rusqlite::Error::FromSqlConversionFailure(
-                                5,
-                                rusqlite::types::Type::Integer,
-                                format!("invalid min_funding_confs {min_funding_confs}").into(),
-                            )
-                        })?,
-                        total_price_msat: u64::try_from(total_price_msat).map_err(|_| {
-                            rusqlite::Error::FromSqlConversionFailure(
-                                6,
-                                rusqlite::types::Type::Integer,
-                                format!("invalid total_price_msat {total_price_msat}").into(),
-                            )
-                        })?,
-                        price_msat_per_asset_unit: u64::try_from(price_msat_per_asset_unit)
-                            .map_err(|_| {
-                                rusqlite::Error::FromSqlConversionFailure(
-                                    7,
-                                    rusqlite::types::Type::Integer,
-                                    format!(
-                                        "invalid price_msat_per_asset_unit {price_msat_per_asset_unit}"
-                                    )
-                                    .into(),
-                                )
-                            })?,
-                        fee_subsidy_sats: u64::try_from(fee_subsidy_sats).map_err(|_| {
-                            rusqlite::Error::FromSqlConversionFailure(
-                                8,
-                                rusqlite::types::Type::Integer,
-                                format!("invalid fee_subsidy_sats {fee_subsidy_sats}").into(),
-                            )
-                        })?,
-                        refund_delta_blocks: u32::try_from(refund_delta_blocks).map_err(|_| {
-                            rusqlite::Error::FromSqlConversionFailure(
-                                9,
-                                rusqlite::types::Type::Integer,
-                                format!("invalid refund_delta_blocks {refund_delta_blocks}").into(),
-                            )
-                        })?,
-                        invoice_expiry_secs: u32::try_from(invoice_expiry_secs).map_err(|_| {
-                            rusqlite::Error::FromSqlConversionFailure(
-                                10,
-                                rusqlite::types::Type::Integer,
-                                format!("invalid invoice_expiry_secs {invoice_expiry_secs}").into(),
-                            )
-                        })?,
-                        max_min_funding_confs: u32::try_from(max_min_funding_confs).map_err(|_| {
-                            rusqlite::Error::FromSqlConversionFailure(
-                                11,
-                                rusqlite::types::Type::Integer,
-                                format!("invalid max_min_funding_confs {max_min_funding_confs}")
-                                    .into(),
-                            )
-                        })?,
-                        swap_id: row.get(12)?,
+                    let total_msat: i64 = row.get(0)?;
+                    let buyer_msat: i64 = row.get(1)?;
+                    Ok(OpenNotional {
+                        total_msat: total_msat as u64,
+                        buyer_msat: buyer_msat as u64,
                     })
                 },
             )
+            .context("query open notional")
+    }
+
+    /// Sums the reservations still held at `now`, by asset id.
+    pub fn reserved_inventory(&self, now: u64) -> Result<BTreeMap<String, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r#"
+SELECT asset_id, SUM(amount)
+FROM inventory_reservations
+WHERE released_at IS NULL AND (swap_id IS NOT NULL OR expires_at > ?1)
+GROUP BY asset_id
+"#,
+            )
+            .context("prepare reserved inventory")?;
+        let rows = stmt
+            .query_map(params![now as i64], |row| {
+                let amount: i64 = row.get(1)?;
+                Ok((row.get::<_, String>(0)?, amount as u64))
+            })
+            .context("query reserved inventory")?;
+
+        let mut out = BTreeMap::new();
+        for row in rows {
+            let (asset_id, amount) = row.context("read reserved inventory row")?;
+            out.insert(asset_id, amount);
+        }
+        Ok(out)
+    }
+
+    pub fn get_quote(&self, quote_id: &str) -> Result<Option<QuoteRecord>> {
+        self.conn
+            .query_row(
+                &format!("SELECT {QUOTE_COLUMNS} FROM quotes WHERE quote_id = ?1"),
+                params![quote_id],
+                row_to_quote_record,
+            )
             .optional()
             .with_context(|| format!("get quote {}", quote_id))
     }
 
-    pub fn set_quote_swap_id(&mut self, quote_id: &str, swap_id: &str) -> Result<()> {
-        let rows = self
+    pub fn list_quotes_page(
+        &self,
+        filter: &QuoteFilter,
+        after: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Vec<QuoteRecord>> {
+        let mut query = PageQuery::default();
+        if let Some(direction) = filter.direction {
+            query.push("direction = ?", direction_to_str(direction));
+        }
+        if let Some(asset_id) = &filter.asset_id {
+            query.push("asset_id = ?", asset_id.as_str());
+        }
+        query.push_time_range(filter.created_after, filter.created_before);
+        query.push_cursor(
+            "(created_at < ? OR (created_at = ? AND quote_id < ?))",
+            after,
+        );
+
+        let mut stmt = self
             .conn
+            .prepare(&format!(
+                "SELECT {QUOTE_COLUMNS} FROM quotes {} ORDER BY created_at DESC, quote_id DESC LIMIT {limit}",
+                query.where_clause()
+            ))
+            .context("prepare list quotes")?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params_from_iter(query.args.iter()),
+                row_to_quote_record,
+            )
+            .context("query list quotes")?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.context("read quote row")?);
+        }
+        Ok(out)
+    }
+
+    /// Links an unused quote to `swap_id`. Returns `false` if the quote is already linked.
+    pub fn reserve_quote(&mut self, quote_id: &str, swap_id: &str, buyer_id: &str) -> Result<bool> {
+        let tx = self.conn.transaction().context("begin reserve quote")?;
+        let rows = tx
             .execute(
-                "UPDATE quotes SET swap_id = ?2 WHERE quote_id = ?1",
+                r#"
+UPDATE quotes SET swap_id = ?2, buyer_id = ?3
+WHERE quote_id = ?1 AND swap_id IS NULL
+"#,
+                params![quote_id, swap_id, buyer_id],
+            )
+            .with_context(|| format!("reserve quote quote_id={quote_id}"))?;
+        if rows == 1 {
+            tx.execute(
+                "UPDATE inventory_reservations SET swap_id = ?2 WHERE quote_id = ?1",
                 params![quote_id, swap_id],
             )
-            .with_context(|| format!("set quote swap_id quote_id={quote_id}"))?;
-        anyhow::ensure!(rows == 1, "quote not found: {quote_id}");
+            .with_context(|| format!("link inventory quote_id={quote_id}"))?;
+        }
+        tx.commit().context("commit reserve quote")?;
+        Ok(rows == 1)
+    }
+
+    pub fn release_quote(&mut self, quote_id: &str, swap_id: &str) -> Result<()> {
+        let tx = self.conn.transaction().context("begin release quote")?;
+        tx.execute(
+            "UPDATE quotes SET swap_id = NULL, buyer_id = NULL WHERE quote_id = ?1 AND swap_id = ?2",
+            params![quote_id, swap_id],
+        )
+        .with_context(|| format!("release quote quote_id={quote_id}"))?;
+        tx.execute(
+            "UPDATE inventory_reservations SET swap_id = NULL WHERE quote_id = ?1 AND swap_id = ?2",
+            params![quote_id, swap_id],
+        )
+        .with_context(|| format!("unlink inventory quote_id={quote_id}"))?;
+        tx.commit().context("commit release quote")?;
         Ok(())
     }
 
-    pub fn insert_swap(&mut self, record: &SwapRecord) -> Result<()> {
+    /// Stores `record` as the next version of its asset's offer. `record.version` is ignored.
+    pub fn append_offer_version(
+        &mut self,
+        record: &OfferVersionRecord,
+    ) -> Result<OfferVersionRecord> {
+        let tx = self
+            .conn
+            .transaction()
+            .context("begin append offer version")?;
+        let latest: Option<i64> = tx
+            .query_row(
+                "SELECT MAX(version) FROM offer_versions WHERE asset_id = ?1",
+                params![&record.asset_id],
+                |row| row.get(0),
+            )
+            .context("query latest offer version")?;
+        let version = u32::try_from(latest.unwrap_or(0) + 1).context("offer version overflow")?;
+        tx.execute(
+            &format!(
+                r#"
+INSERT INTO offer_versions ({OFFER_VERSION_COLUMNS}) VALUES (
+  ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10
+)
+"#
+            ),
+            params![
+                &record.asset_id,
+                version,
+                directions_to_str(&record.supported_directions),
+                record.price_msat_per_asset_unit,
+                record.fee_subsidy_sats,
+                record.refund_delta_blocks,
+                record.min_asset_amount,
+                record.max_asset_amount,
+                record.paused,
+                record.created_at as i64,
+            ],
+        )
+        .with_context(|| format!("insert offer version asset_id={}", record.asset_id))?;
+        tx.commit().context("commit append offer version")?;
+
+        Ok(OfferVersionRecord {
+            version,
+            ..record.clone()
+        })
+    }
+
+    pub fn latest_offer_version(&self, asset_id: &str) -> Result<Option<OfferVersionRecord>> {
         self.conn
-            .execute(
+            .query_row(
+                &format!(
+                    r#"
+SELECT {OFFER_VERSION_COLUMNS} FROM offer_versions
+WHERE asset_id = ?1
+ORDER BY version DESC
+LIMIT 1
+"#
+                ),
+                params![asset_id],
+                row_to_offer_version_record,
+            )
+            .optional()
+            .with_context(|| format!("get latest offer version asset_id={asset_id}"))
+    }
+
+    /// Returns the latest version of every stored offer.
+    pub fn list_latest_offer_versions(&self) -> Result<Vec<OfferVersionRecord>> {
+        self.query_offer_versions(
+            &format!(
                 r#"
-INSERT INTO swaps (
-  swap_id,
-  quote_id,
-  direction,
-  bolt11_invoice,
-  payment_hash,
-  asset_id,
-  asset_amount,
-  total_price_msat,
-  buyer_liquid_address,
-  fee_subsidy_sats,
-  refund_lock_height,
-  p2wsh_address,
-  witness_script_hex,
-  funding_txid,
-  asset_vout,
-  lbtc_vout,
-  min_funding_confs,
-  ln_payment_id,
-  ln_preimage_hex,
-  claim_txid,
-  status
-) VALUES (
-  ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21
+SELECT {OFFER_VERSION_COLUMNS} FROM offer_versions o
+WHERE version = (SELECT MAX(version) FROM offer_versions WHERE asset_id = o.asset_id)
+ORDER BY asset_id
+"#
+            ),
+            params![],
+        )
+    }
+
+    /// Returns every version of an asset's offer, newest first.
+    pub fn list_offer_versions(&self, asset_id: &str) -> Result<Vec<OfferVersionRecord>> {
+        self.query_offer_versions(
+            &format!(
+                r#"
+SELECT {OFFER_VERSION_COLUMNS} FROM offer_versions
+WHERE asset_id = ?1
+ORDER BY version DESC
+"#
+            ),
+            params![asset_id],
+        )
+    }
+
+    fn query_offer_versions(
+        &self,
+        sql: &str,
+        args: impl rusqlite::Params,
+    ) -> Result<Vec<OfferVersionRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .context("prepare list offer versions")?;
+        let rows = stmt
+            .query_map(args, row_to_offer_version_record)
+            .context("query list offer versions")?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.context("read offer version row")?);
+        }
+        Ok(out)
+    }
+
+    pub fn insert_api_key(&mut self, record: &ApiKeyRecord) -> Result<()> {
+        self.conn
+            .execute(
+                &format!(
+                    r#"
+INSERT INTO api_keys ({API_KEY_COLUMNS}) VALUES (
+  ?1, ?2, ?3, ?4, ?5, ?6
 )
-"#,
+"#
+                ),
                 params![
-                    &record.swap_id,
-                    &record.quote_id,
-                    direction_to_str(record.direction),
-                    &record.bolt11_invoice,
-                    &record.payment_hash,
-                    &record.asset_id,
-                    record.asset_amount,
-                    record.total_price_msat,
-                    &record.buyer_liquid_address,
-                    record.fee_subsidy_sats,
-                    record.refund_lock_height,
-                    &record.p2wsh_address,
-                    &record.witness_script_hex,
-                    &record.funding_txid,
-                    record.asset_vout,
-                    record.lbtc_vout,
-                    record.min_funding_confs,
-                    record.ln_payment_id.as_deref(),
-                    record.ln_preimage_hex.as_deref(),
-                    record.claim_txid.as_deref(),
-                    status_to_str(record.status),
+                    &record.key_id,
+                    &record.secret_hash,
+                    api_key_role_to_str(record.role),
+                    &record.owner,
+                    record.created_at as i64,
+                    record.revoked_at.map(|t| t as i64),
                 ],
             )
-            .with_context(|| format!("insert swap {}", record.swap_id))?;
+            .with_context(|| format!("insert api key {}", record.key_id))?;
         Ok(())
     }
 
-    pub fn get_swap(&self, swap_id: &str) -> Result<Option<SwapRecord>> {
+    pub fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKeyRecord>> {
         self.conn
             .query_row(
-                r#"
-SELECT
-  swap_id,
-  quote_id,
-  direction,
-  bolt11_invoice,
-  payment_hash,
-  asset_id,
-  asset_amount,
-  total_price_msat,
-  buyer_liquid_address,
-  fee_subsidy_sats,
-  refund_lock_height,
-  p2wsh_address,
-  witness_script_hex,
-  funding_txid,
-  asset_vout,
-  lbtc_vout,
-  min_funding_confs,
-  ln_payment_id,
-  ln_preimage_hex,
-  claim_txid,
-  status
-FROM swaps
-WHERE swap_id = ?1
-"#,
-                params![swap_id],
-                row_to_swap_record,
+                &format!("SELECT {API_KEY_COLUMNS} FROM api_keys WHERE key_id = ?1"),
+                params![key_id],
+                row_to_api_key_record,
             )
             .optional()
-            .with_context(|| format!("get swap {}", swap_id))
+            .with_context(|| format!("get api key {key_id}"))
     }
 
-    pub fn update_swap_status(&mut self, swap_id: &str, status: SwapStatus) -> Result<()> {
-        let rows = self
-            .conn
+    /// Marks a key revoked at `now`. Revoking a revoked key keeps the first timestamp.
+    pub fn revoke_api_key(&mut self, key_id: &str, now: u64) -> Result<Option<ApiKeyRecord>> {
+        self.conn
             .execute(
+                "UPDATE api_keys SET revoked_at = ?2 WHERE key_id = ?1 AND revoked_at IS NULL",
+                params![key_id, now as i64],
+            )
+            .with_context(|| format!("revoke api key {key_id}"))?;
+        self.get_api_key(key_id)
+    }
+
+    pub fn list_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {API_KEY_COLUMNS} FROM api_keys ORDER BY created_at, key_id"
+            ))
+            .context("prepare list api keys")?;
+        let rows = stmt
+            .query_map([], row_to_api_key_record)
+            .context("query list api keys")?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.context("read api key row")?);
+        }
+        Ok(out)
+    }
+
+    /// Inserts a funded swap and releases its quote's inventory reservation.
+    pub fn insert_swap(&mut self, record: &SwapRecord) -> Result<()> {
+        let tx = self.conn.transaction().context("begin insert swap")?;
+        tx.execute(
+            &format!(
+                r#"
+INSERT INTO swaps ({SWAP_COLUMNS}) VALUES (
+  ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21,
+  ?22, ?23, ?24, ?25, ?26, ?27
+)
+"#
+            ),
+            params![
+                &record.swap_id,
+                &record.quote_id,
+                direction_to_str(record.direction),
+                &record.bolt11_invoice,
+                &record.payment_hash,
+                &record.asset_id,
+                record.asset_amount,
+                record.total_price_msat,
+                &record.buyer_liquid_address,
+                record.fee_subsidy_sats,
+                record.refund_lock_height,
+                &record.p2wsh_address,
+                &record.witness_script_hex,
+                &record.funding_txid,
+                record.asset_vout,
+                record.lbtc_vout,
+                record.min_funding_confs,
+                record.ln_payment_id.as_deref(),
+                record.ln_preimage_hex.as_deref(),
+                record.claim_txid.as_deref(),
+                status_to_str(record.status),
+                record.buyer_claim_pubkey_hex.as_deref(),
+                htlc_version_to_str(record.htlc_version),
+                record.htlc_blinding_key_hex.as_deref(),
+                record.created_at as i64,
+                &record.owner,
+                record.refund_txid.as_deref(),
+            ],
+        )
+        .with_context(|| format!("insert swap {}", record.swap_id))?;
+        let update = swap_update(&tx, &record.swap_id, None, record.status)?;
+        // The funding tx has left the wallet, so its balance no longer counts the reserved amounts.
+        tx.execute(
+            r#"
+UPDATE inventory_reservations
+SET released_at = ?2
+WHERE swap_id = ?1 AND released_at IS NULL
+"#,
+            params![&record.swap_id, record.created_at as i64],
+        )
+        .with_context(|| format!("release inventory {}", record.swap_id))?;
+        tx.commit().context("commit insert swap")?;
+        let _ = self.updates.send(update);
+        Ok(())
+    }
+
+    pub fn get_swap(&self, swap_id: &str) -> Result<Option<SwapRecord>> {
+        get_swap(&self.conn, swap_id)
+    }
+
+    pub fn update_swap_status(&mut self, swap_id: &str, status: SwapStatus) -> Result<()> {
+        self.update_swap_with_transition(swap_id, status, "update swap status", |tx| {
+            tx.execute(
                 "UPDATE swaps SET status = ?2 WHERE swap_id = ?1",
                 params![swap_id, status_to_str(status)],
             )
-            .with_context(|| format!("update swap status {swap_id}"))?;
-        anyhow::ensure!(rows == 1, "swap not found: {swap_id}");
-        Ok(())
+        })
     }
 
     pub fn upsert_swap_payment(
@@ -313,9 +599,8 @@ WHERE swap_id = ?1
         preimage_hex: &str,
         status: SwapStatus,
     ) -> Result<()> {
-        let rows = self
-            .conn
-            .execute(
+        self.update_swap_with_transition(swap_id, status, "update swap payment", |tx| {
+            tx.execute(
                 r#"
 UPDATE swaps
 SET ln_payment_id = ?2,
@@ -325,9 +610,7 @@ WHERE swap_id = ?1
 "#,
                 params![swap_id, payment_id, preimage_hex, status_to_str(status)],
             )
-            .with_context(|| format!("update swap payment {swap_id}"))?;
-        anyhow::ensure!(rows == 1, "swap not found: {swap_id}");
-        Ok(())
+        })
     }
 
     pub fn upsert_swap_claim(
@@ -336,9 +619,8 @@ WHERE swap_id = ?1
         claim_txid: &str,
         status: SwapStatus,
     ) -> Result<()> {
-        let rows = self
-            .conn
-            .execute(
+        self.update_swap_with_transition(swap_id, status, "update swap claim", |tx| {
+            tx.execute(
                 r#"
 UPDATE swaps
 SET claim_txid = ?2,
@@ -347,49 +629,318 @@ WHERE swap_id = ?1
 "#,
                 params![swap_id, claim_txid, status_to_str(status)],
             )
-            .with_context(|| format!("update swap claim {swap_id}"))?;
+        })
+    }
+
+    pub fn record_htlc_claim(
+        &mut self,
+        swap_id: &str,
+        claim_txid: &str,
+        preimage_hex: Option<&str>,
+    ) -> Result<()> {
+        self.update_swap_with_transition(swap_id, SwapStatus::Claimed, "record htlc claim", |tx| {
+            // A claim that beat our refund makes the refund tx unbroadcastable.
+            tx.execute(
+                "DELETE FROM swap_txs WHERE swap_id = ?1 AND kind = ?2",
+                params![swap_id, swap_tx_kind_to_str(SwapTxKind::Refund)],
+            )?;
+            tx.execute(
+                r#"
+UPDATE swaps
+SET claim_txid = ?2,
+    ln_preimage_hex = COALESCE(ln_preimage_hex, ?3),
+    status = ?4
+WHERE swap_id = ?1
+"#,
+                params![
+                    swap_id,
+                    claim_txid,
+                    preimage_hex,
+                    status_to_str(SwapStatus::Claimed)
+                ],
+            )
+        })
+    }
+
+    /// Moves the swap to `Claimed` and keeps `tx_hex` for rebroadcast until the claim confirms.
+    pub fn record_claim_broadcast(
+        &mut self,
+        swap_id: &str,
+        claim_txid: &str,
+        tx_hex: &str,
+    ) -> Result<()> {
+        self.update_swap_with_transition(
+            swap_id,
+            SwapStatus::Claimed,
+            "record claim broadcast",
+            |tx| {
+                tx.execute(
+                    r#"
+INSERT INTO swap_txs (swap_id, kind, txid, tx_hex)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT(swap_id, kind) DO UPDATE SET txid = excluded.txid, tx_hex = excluded.tx_hex
+"#,
+                    params![
+                        swap_id,
+                        swap_tx_kind_to_str(SwapTxKind::Claim),
+                        claim_txid,
+                        tx_hex
+                    ],
+                )?;
+                tx.execute(
+                    "UPDATE swaps SET claim_txid = ?2, status = ?3 WHERE swap_id = ?1",
+                    params![swap_id, claim_txid, status_to_str(SwapStatus::Claimed)],
+                )
+            },
+        )
+    }
+
+    /// Moves the swap to `RefundPending` and keeps `tx_hex` for rebroadcast until the refund
+    /// confirms.
+    pub fn record_refund_broadcast(
+        &mut self,
+        swap_id: &str,
+        refund_txid: &str,
+        tx_hex: &str,
+    ) -> Result<()> {
+        self.update_swap_with_transition(
+            swap_id,
+            SwapStatus::RefundPending,
+            "record refund broadcast",
+            |tx| {
+                tx.execute(
+                    r#"
+INSERT INTO swap_txs (swap_id, kind, txid, tx_hex)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT(swap_id, kind) DO UPDATE SET txid = excluded.txid, tx_hex = excluded.tx_hex
+"#,
+                    params![
+                        swap_id,
+                        swap_tx_kind_to_str(SwapTxKind::Refund),
+                        refund_txid,
+                        tx_hex
+                    ],
+                )?;
+                tx.execute(
+                    "UPDATE swaps SET refund_txid = ?2, status = ?3 WHERE swap_id = ?1",
+                    params![
+                        swap_id,
+                        refund_txid,
+                        status_to_str(SwapStatus::RefundPending)
+                    ],
+                )
+            },
+        )
+    }
+
+    pub fn set_swap_payment_id(&mut self, swap_id: &str, payment_id: Option<&str>) -> Result<()> {
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE swaps SET ln_payment_id = ?2 WHERE swap_id = ?1",
+                params![swap_id, payment_id],
+            )
+            .with_context(|| format!("set swap payment id {swap_id}"))?;
         anyhow::ensure!(rows == 1, "swap not found: {swap_id}");
         Ok(())
     }
 
-    pub fn list_swaps(&self) -> Result<Vec<SwapRecord>> {
+    pub fn put_swap_tx(
+        &mut self,
+        swap_id: &str,
+        kind: SwapTxKind,
+        txid: &str,
+        tx_hex: &str,
+    ) -> Result<()> {
+        self.conn
+            .execute(
+                r#"
+INSERT INTO swap_txs (swap_id, kind, txid, tx_hex)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT(swap_id, kind) DO UPDATE SET txid = excluded.txid, tx_hex = excluded.tx_hex
+"#,
+                params![swap_id, swap_tx_kind_to_str(kind), txid, tx_hex],
+            )
+            .with_context(|| format!("put swap tx {swap_id}"))?;
+        Ok(())
+    }
+
+    pub fn get_swap_tx(&self, swap_id: &str, kind: SwapTxKind) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT tx_hex FROM swap_txs WHERE swap_id = ?1 AND kind = ?2",
+                params![swap_id, swap_tx_kind_to_str(kind)],
+                |row| row.get(0),
+            )
+            .optional()
+            .with_context(|| format!("get swap tx {swap_id}"))
+    }
+
+    pub fn delete_swap_tx(&mut self, swap_id: &str, kind: SwapTxKind) -> Result<()> {
+        self.conn
+            .execute(
+                "DELETE FROM swap_txs WHERE swap_id = ?1 AND kind = ?2",
+                params![swap_id, swap_tx_kind_to_str(kind)],
+            )
+            .with_context(|| format!("delete swap tx {swap_id}"))?;
+        Ok(())
+    }
+
+    pub fn list_swap_transitions(&self, swap_id: &str) -> Result<Vec<SwapTransition>> {
         let mut stmt = self
             .conn
             .prepare(
                 r#"
-SELECT
-  swap_id,
-  quote_id,
-  direction,
-  bolt11_invoice,
-  payment_hash,
-  asset_id,
-  asset_amount,
-  total_price_msat,
-  buyer_liquid_address,
-  fee_subsidy_sats,
-  refund_lock_height,
-  p2wsh_address,
-  witness_script_hex,
-  funding_txid,
-  asset_vout,
-  lbtc_vout,
-  min_funding_confs,
-  ln_payment_id,
-  ln_preimage_hex,
-  claim_txid,
-  status
-FROM swaps
-ORDER BY swap_id
+SELECT swap_id, from_status, to_status, created_at
+FROM swap_transitions
+WHERE swap_id = ?1
+ORDER BY id
 "#,
             )
+            .context("prepare list swap transitions")?;
+
+        let rows = stmt
+            .query_map(params![swap_id], |row| {
+                let from_status: Option<String> = row.get(1)?;
+                let to_status: String = row.get(2)?;
+                let created_at: i64 = row.get(3)?;
+                Ok(SwapTransition {
+                    swap_id: row.get(0)?,
+                    from_status: from_status.map(|s| status_from_str(&s, 1)).transpose()?,
+                    to_status: status_from_str(&to_status, 2)?,
+                    created_at: created_at as u64,
+                })
+            })
+            .context("query list swap transitions")?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.context("read swap transition row")?);
+        }
+        Ok(out)
+    }
+
+    fn update_swap_with_transition(
+        &mut self,
+        swap_id: &str,
+        status: SwapStatus,
+        what: &str,
+        update: impl FnOnce(&rusqlite::Transaction<'_>) -> rusqlite::Result<usize>,
+    ) -> Result<()> {
+        let tx = self
+            .conn
+            .transaction()
+            .with_context(|| format!("begin {what} {swap_id}"))?;
+        let from_status: Option<String> = tx
+            .query_row(
+                "SELECT status FROM swaps WHERE swap_id = ?1",
+                params![swap_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .with_context(|| format!("{what} {swap_id}"))?;
+        let Some(from_status) = from_status else {
+            anyhow::bail!("swap not found: {swap_id}");
+        };
+
+        update(&tx).with_context(|| format!("{what} {swap_id}"))?;
+        let update = if from_status != status_to_str(status) {
+            let from_status =
+                status_from_str(&from_status, 0).with_context(|| format!("{what} {swap_id}"))?;
+            Some(swap_update(&tx, swap_id, Some(from_status), status)?)
+        } else {
+            None
+        };
+        tx.commit()
+            .with_context(|| format!("commit {what} {swap_id}"))?;
+        if let Some(update) = update {
+            let _ = self.updates.send(update);
+        }
+        Ok(())
+    }
+
+    pub fn list_swaps(&self) -> Result<Vec<SwapRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {SWAP_COLUMNS} FROM swaps ORDER BY swap_id"
+            ))
             .context("prepare list swaps")?;
 
-        let mut out = Vec::new();
+        let mut out = Vec::new();
+        let rows = stmt
+            .query_map([], row_to_swap_record)
+            .context("query list swaps")?;
+
+        for row in rows {
+            out.push(row.context("read swap row")?);
+        }
+        Ok(out)
+    }
+
+    /// Lists the swaps in any of `statuses`, for the background workers.
+    pub fn list_swaps_by_status(&self, statuses: &[SwapStatus]) -> Result<Vec<SwapRecord>> {
+        if statuses.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders = vec!["?"; statuses.len()].join(", ");
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {SWAP_COLUMNS} FROM swaps WHERE status IN ({placeholders}) ORDER BY swap_id"
+            ))
+            .context("prepare list swaps by status")?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params_from_iter(statuses.iter().map(|s| status_to_str(*s))),
+                row_to_swap_record,
+            )
+            .context("query list swaps by status")?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.context("read swap row")?);
+        }
+        Ok(out)
+    }
+
+    pub fn list_swaps_page(
+        &self,
+        filter: &SwapFilter,
+        after: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Vec<SwapRecord>> {
+        let mut query = PageQuery::default();
+        if let Some(status) = filter.status {
+            query.push("status = ?", status_to_str(status));
+        }
+        if let Some(direction) = filter.direction {
+            query.push("direction = ?", direction_to_str(direction));
+        }
+        if let Some(asset_id) = &filter.asset_id {
+            query.push("asset_id = ?", asset_id.as_str());
+        }
+        query.push_time_range(filter.created_after, filter.created_before);
+        query.push_cursor(
+            "(created_at < ? OR (created_at = ? AND swap_id < ?))",
+            after,
+        );
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {SWAP_COLUMNS} FROM swaps {} ORDER BY created_at DESC, swap_id DESC LIMIT {limit}",
+                query.where_clause()
+            ))
+            .context("prepare list swaps page")?;
         let rows = stmt
-            .query_map([], row_to_swap_record)
-            .context("query list swaps")?;
+            .query_map(
+                rusqlite::params_from_iter(query.args.iter()),
+                row_to_swap_record,
+            )
+            .context("query list swaps page")?;
 
+        let mut out = Vec::new();
         for row in rows {
             out.push(row.context("read swap row")?);
         }
@@ -397,6 +948,240 @@ ORDER BY swap_id
     }
 }
 
+#[derive(Default)]
+struct PageQuery {
+    clauses: Vec<&'static str>,
+    args: Vec<rusqlite::types::Value>,
+}
+
+impl PageQuery {
+    fn push(&mut self, clause: &'static str, value: &str) {
+        self.clauses.push(clause);
+        self.args.push(value.to_string().into());
+    }
+
+    fn push_time_range(&mut self, created_after: Option<u64>, created_before: Option<u64>) {
+        if let Some(created_after) = created_after {
+            self.clauses.push("created_at >= ?");
+            self.args.push((created_after as i64).into());
+        }
+        if let Some(created_before) = created_before {
+            self.clauses.push("created_at < ?");
+            self.args.push((created_before as i64).into());
+        }
+    }
+
+    /// `clause` compares `(created_at, id)` against the cursor, binding `created_at` twice.
+    fn push_cursor(&mut self, clause: &'static str, after: Option<&PageCursor>) {
+        let Some(after) = after else {
+            return;
+        };
+        self.clauses.push(clause);
+        self.args.push((after.created_at as i64).into());
+        self.args.push((after.created_at as i64).into());
+        self.args.push(after.id.clone().into());
+    }
+
+    fn where_clause(&self) -> String {
+        if self.clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", self.clauses.join(" AND "))
+        }
+    }
+}
+
+fn row_to_quote_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<QuoteRecord> {
+    let asset_amount: i64 = row.get(4)?;
+    let min_funding_confs: i64 = row.get(5)?;
+    let total_price_msat: i64 = row.get(6)?;
+    let price_msat_per_asset_unit: i64 = row.get(7)?;
+    let fee_subsidy_sats: i64 = row.get(8)?;
+    let refund_delta_blocks: i64 = row.get(9)?;
+    let invoice_expiry_secs: i64 = row.get(10)?;
+    let max_min_funding_confs: i64 = row.get(11)?;
+    let created_at: i64 = row.get(13)?;
+    let expires_at: i64 = row.get(14)?;
+    let supported_directions: String = row.get(15)?;
+    let min_asset_amount: i64 = row.get(16)?;
+    let max_asset_amount: i64 = row.get(17)?;
+    let source_price_msat_per_asset_unit: i64 = row.get(19)?;
+    let priced_at: i64 = row.get(21)?;
+    let max_open_notional_msat: i64 = row.get(22)?;
+    let max_buyer_open_notional_msat: i64 = row.get(23)?;
+    let direction_str: String = row.get(2)?;
+    let direction = direction_from_str(&direction_str, 2)?;
+
+    Ok(QuoteRecord {
+        quote_id: row.get(0)?,
+        offer_id: row.get(1)?,
+        direction,
+        asset_id: row.get(3)?,
+        asset_amount: u64::try_from(asset_amount).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                4,
+                rusqlite::types::Type::Integer,
+                format!("invalid asset_amount {asset_amount}").into(),
+            )
+        })?,
+        min_funding_confs: u32::try_from(min_funding_confs).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                5,
+                rusqlite::types::Type::Integer,
+                format!("invalid min_funding_confs {min_funding_confs}").into(),
+            )
+        })?,
+        total_price_msat: u64::try_from(total_price_msat).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                6,
+                rusqlite::types::Type::Integer,
+                format!("invalid total_price_msat {total_price_msat}").into(),
+            )
+        })?,
+        price_msat_per_asset_unit: u64::try_from(price_msat_per_asset_unit).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                7,
+                rusqlite::types::Type::Integer,
+                format!("invalid price_msat_per_asset_unit {price_msat_per_asset_unit}").into(),
+            )
+        })?,
+        fee_subsidy_sats: u64::try_from(fee_subsidy_sats).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                8,
+                rusqlite::types::Type::Integer,
+                format!("invalid fee_subsidy_sats {fee_subsidy_sats}").into(),
+            )
+        })?,
+        refund_delta_blocks: u32::try_from(refund_delta_blocks).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                9,
+                rusqlite::types::Type::Integer,
+                format!("invalid refund_delta_blocks {refund_delta_blocks}").into(),
+            )
+        })?,
+        invoice_expiry_secs: u32::try_from(invoice_expiry_secs).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                10,
+                rusqlite::types::Type::Integer,
+                format!("invalid invoice_expiry_secs {invoice_expiry_secs}").into(),
+            )
+        })?,
+        max_min_funding_confs: u32::try_from(max_min_funding_confs).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                11,
+                rusqlite::types::Type::Integer,
+                format!("invalid max_min_funding_confs {max_min_funding_confs}").into(),
+            )
+        })?,
+        swap_id: row.get(12)?,
+        buyer_id: row.get(24)?,
+        created_at: u64::try_from(created_at).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                13,
+                rusqlite::types::Type::Integer,
+                format!("invalid created_at {created_at}").into(),
+            )
+        })?,
+        expires_at: u64::try_from(expires_at).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                14,
+                rusqlite::types::Type::Integer,
+                format!("invalid expires_at {expires_at}").into(),
+            )
+        })?,
+        supported_directions: directions_from_str(&supported_directions, 15)?,
+        min_asset_amount: u64::try_from(min_asset_amount).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                16,
+                rusqlite::types::Type::Integer,
+                format!("invalid min_asset_amount {min_asset_amount}").into(),
+            )
+        })?,
+        max_asset_amount: u64::try_from(max_asset_amount).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                17,
+                rusqlite::types::Type::Integer,
+                format!("invalid max_asset_amount {max_asset_amount}").into(),
+            )
+        })?,
+        price_source: row.get(18)?,
+        source_price_msat_per_asset_unit: u64::try_from(source_price_msat_per_asset_unit).map_err(
+            |_| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    19,
+                    rusqlite::types::Type::Integer,
+                    format!(
+                        "invalid source_price_msat_per_asset_unit {source_price_msat_per_asset_unit}"
+                    )
+                    .into(),
+                )
+            },
+        )?,
+        spread_bps: row.get(20)?,
+        priced_at: u64::try_from(priced_at).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                21,
+                rusqlite::types::Type::Integer,
+                format!("invalid priced_at {priced_at}").into(),
+            )
+        })?,
+        max_open_notional_msat: u64::try_from(max_open_notional_msat).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                22,
+                rusqlite::types::Type::Integer,
+                format!("invalid max_open_notional_msat {max_open_notional_msat}").into(),
+            )
+        })?,
+        max_buyer_open_notional_msat: u64::try_from(max_buyer_open_notional_msat).map_err(
+            |_| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    23,
+                    rusqlite::types::Type::Integer,
+                    format!("invalid max_buyer_open_notional_msat {max_buyer_open_notional_msat}")
+                        .into(),
+                )
+            },
+        )?,
+    })
+}
+
+fn row_to_offer_version_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<OfferVersionRecord> {
+    let supported_directions: String = row.get(2)?;
+    let price_msat_per_asset_unit: i64 = row.get(3)?;
+    let fee_subsidy_sats: i64 = row.get(4)?;
+    let min_asset_amount: i64 = row.get(6)?;
+    let max_asset_amount: i64 = row.get(7)?;
+    let created_at: i64 = row.get(9)?;
+
+    Ok(OfferVersionRecord {
+        asset_id: row.get(0)?,
+        version: row.get(1)?,
+        supported_directions: directions_from_str(&supported_directions, 2)?,
+        price_msat_per_asset_unit: price_msat_per_asset_unit as u64,
+        fee_subsidy_sats: fee_subsidy_sats as u64,
+        refund_delta_blocks: row.get(5)?,
+        min_asset_amount: min_asset_amount as u64,
+        max_asset_amount: max_asset_amount as u64,
+        paused: row.get(8)?,
+        created_at: created_at as u64,
+    })
+}
+
+fn row_to_api_key_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<ApiKeyRecord> {
+    let role: String = row.get(2)?;
+    let created_at: i64 = row.get(4)?;
+    let revoked_at: Option<i64> = row.get(5)?;
+
+    Ok(ApiKeyRecord {
+        key_id: row.get(0)?,
+        secret_hash: row.get(1)?,
+        role: api_key_role_from_str(&role, 2)?,
+        owner: row.get(3)?,
+        created_at: created_at as u64,
+        revoked_at: revoked_at.map(|t| t as u64),
+    })
+}
+
 fn row_to_swap_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<SwapRecord> {
     let asset_amount: i64 = row.get(6)?;
     let total_price_msat: i64 = row.get(7)?;
@@ -405,6 +1190,7 @@ fn row_to_swap_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<SwapRecord> {
     let asset_vout: i64 = row.get(14)?;
     let lbtc_vout: i64 = row.get(15)?;
     let min_funding_confs: i64 = row.get(16)?;
+    let created_at: i64 = row.get(24)?;
 
     let direction_str: String = row.get(2)?;
     let direction = direction_from_str(&direction_str, 2)?;
@@ -412,6 +1198,9 @@ fn row_to_swap_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<SwapRecord> {
     let status_str: String = row.get(20)?;
     let status = status_from_str(&status_str, 20)?;
 
+    let htlc_version_str: String = row.get(22)?;
+    let htlc_version = htlc_version_from_str(&htlc_version_str, 22)?;
+
     Ok(SwapRecord {
         swap_id: row.get(0)?,
         quote_id: row.get(1)?,
@@ -476,6 +1265,106 @@ fn row_to_swap_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<SwapRecord> {
         ln_preimage_hex: row.get(18)?,
         claim_txid: row.get(19)?,
         status,
+        buyer_claim_pubkey_hex: row.get(21)?,
+        htlc_version,
+        htlc_blinding_key_hex: row.get(23)?,
+        owner: row.get(25)?,
+        refund_txid: row.get(26)?,
+        created_at: u64::try_from(created_at).map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                24,
+                rusqlite::types::Type::Integer,
+                format!("invalid created_at {created_at}").into(),
+            )
+        })?,
+    })
+}
+
+fn insert_quote_row(conn: &Connection, record: &QuoteRecord) -> Result<()> {
+    conn.execute(
+        &format!(
+            r#"
+INSERT INTO quotes ({QUOTE_COLUMNS}) VALUES (
+  ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+  ?19, ?20, ?21, ?22, ?23, ?24, ?25
+)
+"#
+        ),
+        params![
+            &record.quote_id,
+            &record.offer_id,
+            direction_to_str(record.direction),
+            &record.asset_id,
+            record.asset_amount,
+            record.min_funding_confs,
+            record.total_price_msat,
+            record.price_msat_per_asset_unit,
+            record.fee_subsidy_sats,
+            record.refund_delta_blocks,
+            record.invoice_expiry_secs,
+            record.max_min_funding_confs,
+            record.swap_id.as_deref(),
+            record.created_at as i64,
+            record.expires_at as i64,
+            directions_to_str(&record.supported_directions),
+            record.min_asset_amount as i64,
+            record.max_asset_amount as i64,
+            &record.price_source,
+            record.source_price_msat_per_asset_unit as i64,
+            record.spread_bps,
+            record.priced_at as i64,
+            record.max_open_notional_msat as i64,
+            record.max_buyer_open_notional_msat as i64,
+            record.buyer_id.as_deref(),
+        ],
+    )
+    .with_context(|| format!("insert quote {}", record.quote_id))?;
+    Ok(())
+}
+
+fn get_swap(conn: &Connection, swap_id: &str) -> Result<Option<SwapRecord>> {
+    conn.query_row(
+        &format!("SELECT {SWAP_COLUMNS} FROM swaps WHERE swap_id = ?1"),
+        params![swap_id],
+        row_to_swap_record,
+    )
+    .optional()
+    .with_context(|| format!("get swap {}", swap_id))
+}
+
+/// Records the transition inside `tx` and returns the update to publish once `tx` commits.
+fn swap_update(
+    tx: &rusqlite::Transaction<'_>,
+    swap_id: &str,
+    from_status: Option<SwapStatus>,
+    to_status: SwapStatus,
+) -> Result<SwapUpdate> {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    tx.execute(
+        r#"
+INSERT INTO swap_transitions (swap_id, from_status, to_status, created_at)
+VALUES (?1, ?2, ?3, ?4)
+"#,
+        params![
+            swap_id,
+            from_status.map(status_to_str),
+            status_to_str(to_status),
+            created_at as i64
+        ],
+    )
+    .with_context(|| format!("record swap transition {swap_id}"))?;
+    let swap = get_swap(tx, swap_id)?.with_context(|| format!("swap not found: {swap_id}"))?;
+    Ok(SwapUpdate {
+        transition: SwapTransition {
+            swap_id: swap_id.to_string(),
+            from_status,
+            to_status,
+            created_at,
+        },
+        swap,
     })
 }
 
@@ -495,7 +1384,19 @@ CREATE TABLE IF NOT EXISTS quotes (
   refund_delta_blocks INTEGER NOT NULL,
   invoice_expiry_secs INTEGER NOT NULL,
   max_min_funding_confs INTEGER NOT NULL,
-  swap_id TEXT
+  swap_id TEXT,
+  created_at INTEGER NOT NULL DEFAULT 0,
+  expires_at INTEGER NOT NULL DEFAULT 0,
+  supported_directions TEXT NOT NULL DEFAULT 'ln_to_liquid,liquid_to_ln',
+  min_asset_amount INTEGER NOT NULL DEFAULT 0,
+  max_asset_amount INTEGER NOT NULL DEFAULT 0,
+  price_source TEXT NOT NULL DEFAULT 'offer',
+  source_price_msat_per_asset_unit INTEGER NOT NULL DEFAULT 0,
+  spread_bps INTEGER NOT NULL DEFAULT 0,
+  priced_at INTEGER NOT NULL DEFAULT 0,
+  max_open_notional_msat INTEGER NOT NULL DEFAULT 0,
+  max_buyer_open_notional_msat INTEGER NOT NULL DEFAULT 0,
+  buyer_id TEXT
 );
 CREATE INDEX IF NOT EXISTS quotes_swap_id_idx ON quotes(swap_id);
 
@@ -520,14 +1421,82 @@ CREATE TABLE IF NOT EXISTS swaps (
   ln_payment_id TEXT,
   ln_preimage_hex TEXT,
   claim_txid TEXT,
-  status TEXT NOT NULL
+  status TEXT NOT NULL,
+  buyer_claim_pubkey_hex TEXT,
+  htlc_version TEXT NOT NULL DEFAULT 'p2wsh',
+  htlc_blinding_key_hex TEXT,
+  created_at INTEGER NOT NULL DEFAULT 0,
+  owner TEXT NOT NULL DEFAULT 'buyer',
+  refund_txid TEXT
 );
 CREATE INDEX IF NOT EXISTS swaps_status_idx ON swaps(status);
+
+CREATE TABLE IF NOT EXISTS swap_transitions (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  swap_id TEXT NOT NULL,
+  from_status TEXT,
+  to_status TEXT NOT NULL,
+  created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS swap_transitions_swap_id_idx ON swap_transitions(swap_id);
+
+CREATE TABLE IF NOT EXISTS swap_txs (
+  swap_id TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  txid TEXT NOT NULL,
+  tx_hex TEXT NOT NULL,
+  PRIMARY KEY (swap_id, kind)
+);
+
+CREATE TABLE IF NOT EXISTS offer_versions (
+  asset_id TEXT NOT NULL,
+  version INTEGER NOT NULL,
+  supported_directions TEXT NOT NULL,
+  price_msat_per_asset_unit INTEGER NOT NULL,
+  fee_subsidy_sats INTEGER NOT NULL,
+  refund_delta_blocks INTEGER NOT NULL,
+  min_asset_amount INTEGER NOT NULL,
+  max_asset_amount INTEGER NOT NULL,
+  paused INTEGER NOT NULL,
+  created_at INTEGER NOT NULL,
+  PRIMARY KEY (asset_id, version)
+);
+
+CREATE TABLE IF NOT EXISTS inventory_reservations (
+  quote_id TEXT NOT NULL,
+  asset_id TEXT NOT NULL,
+  amount INTEGER NOT NULL,
+  swap_id TEXT,
+  expires_at INTEGER NOT NULL,
+  released_at INTEGER,
+  PRIMARY KEY (quote_id, asset_id)
+);
+CREATE INDEX IF NOT EXISTS inventory_reservations_swap_id_idx ON inventory_reservations(swap_id);
+
+CREATE TABLE IF NOT EXISTS api_keys (
+  key_id TEXT PRIMARY KEY,
+  secret_hash TEXT NOT NULL,
+  role TEXT NOT NULL,
+  owner TEXT NOT NULL,
+  created_at INTEGER NOT NULL,
+  revoked_at INTEGER
+);
 "#,
     )
     .context("create tables")?;
 
     ensure_columns(conn).context("ensure columns")?;
+
+    conn.execute_batch(
+        r#"
+CREATE INDEX IF NOT EXISTS swaps_created_at_idx ON swaps(created_at, swap_id);
+CREATE INDEX IF NOT EXISTS swaps_status_created_at_idx ON swaps(status, created_at);
+CREATE INDEX IF NOT EXISTS swaps_asset_id_created_at_idx ON swaps(asset_id, created_at);
+CREATE INDEX IF NOT EXISTS quotes_created_at_idx ON quotes(created_at, quote_id);
+CREATE INDEX IF NOT EXISTS quotes_asset_id_created_at_idx ON quotes(asset_id, created_at);
+"#,
+    )
+    .context("create list indexes")?;
     Ok(())
 }
 
@@ -564,6 +1533,30 @@ fn ensure_columns(conn: &Connection) -> Result<()> {
     ensure_column(conn, "swaps", &swaps_cols, "ln_payment_id", "TEXT")?;
     ensure_column(conn, "swaps", &swaps_cols, "ln_preimage_hex", "TEXT")?;
     ensure_column(conn, "swaps", &swaps_cols, "claim_txid", "TEXT")?;
+    ensure_column(conn, "swaps", &swaps_cols, "buyer_claim_pubkey_hex", "TEXT")?;
+    ensure_column(
+        conn,
+        "swaps",
+        &swaps_cols,
+        "htlc_version",
+        "TEXT NOT NULL DEFAULT 'p2wsh'",
+    )?;
+    ensure_column(conn, "swaps", &swaps_cols, "htlc_blinding_key_hex", "TEXT")?;
+    ensure_column(
+        conn,
+        "swaps",
+        &swaps_cols,
+        "created_at",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "swaps",
+        &swaps_cols,
+        "owner",
+        "TEXT NOT NULL DEFAULT 'buyer'",
+    )?;
+    ensure_column(conn, "swaps", &swaps_cols, "refund_txid", "TEXT")?;
 
     let quotes_cols = table_columns(conn, "quotes").context("read quotes columns")?;
     ensure_column(
@@ -574,6 +1567,84 @@ fn ensure_columns(conn: &Connection) -> Result<()> {
         "TEXT NOT NULL DEFAULT 'ln_to_liquid'",
     )?;
     ensure_column(conn, "quotes", &quotes_cols, "swap_id", "TEXT")?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "created_at",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "expires_at",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "supported_directions",
+        "TEXT NOT NULL DEFAULT 'ln_to_liquid,liquid_to_ln'",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "min_asset_amount",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "max_asset_amount",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "price_source",
+        "TEXT NOT NULL DEFAULT 'offer'",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "source_price_msat_per_asset_unit",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "spread_bps",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "priced_at",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "max_open_notional_msat",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        conn,
+        "quotes",
+        &quotes_cols,
+        "max_buyer_open_notional_msat",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(conn, "quotes", &quotes_cols, "buyer_id", "TEXT")?;
 
     Ok(())
 }
@@ -626,12 +1697,25 @@ fn direction_from_str(s: &str, col: usize) -> rusqlite::Result<SwapDirection> {
     }
 }
 
+fn directions_to_str(directions: &[SwapDirection]) -> String {
+    directions
+        .iter()
+        .map(|d| direction_to_str(*d))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn directions_from_str(s: &str, col: usize) -> rusqlite::Result<Vec<SwapDirection>> {
+    s.split(',').map(|d| direction_from_str(d, col)).collect()
+}
+
 fn status_to_str(status: SwapStatus) -> &'static str {
     match status {
         SwapStatus::Created => "created",
         SwapStatus::Funded => "funded",
         SwapStatus::Paid => "paid",
         SwapStatus::Claimed => "claimed",
+        SwapStatus::RefundPending => "refund_pending",
         SwapStatus::Refunded => "refunded",
         SwapStatus::Failed => "failed",
     }
@@ -643,6 +1727,7 @@ fn status_from_str(s: &str, col: usize) -> rusqlite::Result<SwapStatus> {
         "funded" => Ok(SwapStatus::Funded),
         "paid" => Ok(SwapStatus::Paid),
         "claimed" => Ok(SwapStatus::Claimed),
+        "refund_pending" => Ok(SwapStatus::RefundPending),
         "refunded" => Ok(SwapStatus::Refunded),
         "failed" => Ok(SwapStatus::Failed),
         other => Err(rusqlite::Error::FromSqlConversionFailure(
@@ -652,3 +1737,50 @@ fn status_from_str(s: &str, col: usize) -> rusqlite::Result<SwapStatus> {
         )),
     }
 }
+
+fn swap_tx_kind_to_str(kind: SwapTxKind) -> &'static str {
+    match kind {
+        SwapTxKind::Funding => "funding",
+        SwapTxKind::Cpfp => "cpfp",
+        SwapTxKind::Claim => "claim",
+        SwapTxKind::Refund => "refund",
+    }
+}
+
+fn htlc_version_to_str(version: HtlcVersion) -> &'static str {
+    match version {
+        HtlcVersion::P2wsh => "p2wsh",
+        HtlcVersion::Taproot => "taproot",
+    }
+}
+
+fn htlc_version_from_str(s: &str, col: usize) -> rusqlite::Result<HtlcVersion> {
+    match s {
+        "p2wsh" => Ok(HtlcVersion::P2wsh),
+        "taproot" => Ok(HtlcVersion::Taproot),
+        other => Err(rusqlite::Error::FromSqlConversionFailure(
+            col,
+            rusqlite::types::Type::Text,
+            format!("unknown htlc version: {other}").into(),
+        )),
+    }
+}
+
+fn api_key_role_to_str(role: ApiKeyRole) -> &'static str {
+    match role {
+        ApiKeyRole::Buyer => "buyer",
+        ApiKeyRole::Seller => "seller",
+    }
+}
+
+fn api_key_role_from_str(s: &str, col: usize) -> rusqlite::Result<ApiKeyRole> {
+    match s {
+        "buyer" => Ok(ApiKeyRole::Buyer),
+        "seller" => Ok(ApiKeyRole::Seller),
+        other => Err(rusqlite::Error::FromSqlConversionFailure(
+            col,
+            rusqlite::types::Type::Text,
+            format!("unknown api key role: {other}").into(),
+        )),
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+
+/// Liquid's target block interval.
+pub const LIQUID_BLOCK_INTERVAL_SECS: u64 = 60;
+/// Bitcoin's target block interval; `min_final_cltv_expiry_delta` is counted in Bitcoin blocks.
+pub const BITCOIN_BLOCK_INTERVAL_SECS: u64 = 600;
+
+/// Liquid blocks expected within `secs`, rounded up.
+pub fn liquid_blocks_for_secs(secs: u64) -> u64 {
+    secs.div_ceil(LIQUID_BLOCK_INTERVAL_SECS)
+}
+
+/// Liquid blocks left before the HTLC becomes refundable once `secs` have passed. Negative when the
+/// timelock runs out first.
+pub fn margin_blocks(refund_delta_blocks: u32, secs: u64) -> i64 {
+    i64::from(refund_delta_blocks).saturating_sub_unsigned(liquid_blocks_for_secs(secs))
+}
+
+/// Ensures an invoice issued with the HTLC cannot be paid after the HTLC is refundable.
+pub fn check_invoice_expiry(
+    refund_delta_blocks: u32,
+    invoice_expiry_secs: u64,
+    min_margin_blocks: u32,
+) -> Result<()> {
+    let margin = margin_blocks(refund_delta_blocks, invoice_expiry_secs);
+    anyhow::ensure!(
+        margin >= i64::from(min_margin_blocks),
+        "refund_delta_blocks {refund_delta_blocks} leaves {margin} blocks after a \
+         {invoice_expiry_secs}s invoice expires (need >= {min_margin_blocks})"
+    );
+    Ok(())
+}
+
+/// Ensures a buyer's invoice can neither be paid nor held unsettled past the refund height.
+///
+/// `secs_until_expiry` is the invoice's remaining lifetime; `refund_delta_blocks` is how far the
+/// refund height will be above the tip.
+pub fn check_buyer_invoice(
+    refund_delta_blocks: u32,
+    secs_until_expiry: u64,
+    min_final_cltv_expiry_delta: u64,
+    min_margin_blocks: u32,
+) -> Result<()> {
+    check_invoice_expiry(refund_delta_blocks, secs_until_expiry, min_margin_blocks)?;
+    let hold_secs = min_final_cltv_expiry_delta.saturating_mul(BITCOIN_BLOCK_INTERVAL_SECS);
+    let margin = margin_blocks(refund_delta_blocks, hold_secs);
+    anyhow::ensure!(
+        margin >= i64::from(min_margin_blocks),
+        "min_final_cltv_expiry_delta {min_final_cltv_expiry_delta} leaves {margin} blocks before \
+         the refund height (refund_delta_blocks {refund_delta_blocks}, need >= {min_margin_blocks})"
+    );
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use std::str::FromStr as _;
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::elements::bitcoin::secp256k1::{Parity, XOnlyPublicKey};
+use lwk_wollet::elements::confidential::{Asset, Value};
+use lwk_wollet::elements::secp256k1_zkp::{Secp256k1, SecretKey};
+use lwk_wollet::elements::{Address, AssetId, Script, Transaction, Txid};
+
+use crate::lightning::invoice::{amount_msat_from_bolt11, payment_hash_from_bolt11};
+use crate::liquid::htlc::{HtlcSpec, pubkey_hash160};
+use crate::liquid::taproot::TaprootHtlcSpec;
+use crate::proto::v1 as pb;
+
+#[derive(Debug, Clone)]
+pub struct SwapExpectations {
+    pub user_pubkey_hash160: [u8; 20],
+    pub asset_id: AssetId,
+    pub asset_amount: u64,
+    pub total_price_msat: u64,
+    pub fee_subsidy_sats: u64,
+    pub policy_asset: AssetId,
+    pub tip_height: u32,
+    pub min_refund_blocks: u32,
+}
+
+pub fn funding_txid(swap: &pb::Swap) -> Result<Txid> {
+    let liquid = swap
+        .liquid
+        .as_ref()
+        .context("swap is missing liquid details")?;
+    Txid::from_str(&liquid.funding_txid).context("parse funding_txid")
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifiedHtlc {
+    pub payment_hash: [u8; 32],
+    pub refund_lock_height: u32,
+}
+
+struct HtlcTerms {
+    payment_hash: [u8; 32],
+    refund_lock_height: u32,
+    script_pubkey: Script,
+    claimer_pubkey_hash160s: Vec<[u8; 20]>,
+    refunder_pubkey_hash160s: Vec<[u8; 20]>,
+}
+
+pub fn verify_swap(
+    swap: &pb::Swap,
+    expected: &SwapExpectations,
+    funding_tx: &Transaction,
+) -> Result<VerifiedHtlc> {
+    let liquid = swap
+        .liquid
+        .as_ref()
+        .context("swap is missing liquid details")?;
+
+    let p2wsh_address = Address::from_str(&liquid.p2wsh_address).context("parse p2wsh_address")?;
+    let htlc_version = pb::HtlcVersion::try_from(liquid.htlc_version)
+        .map_err(|_| anyhow::anyhow!("unknown htlc version: {}", liquid.htlc_version))?;
+    let terms = match htlc_version {
+        pb::HtlcVersion::Unspecified | pb::HtlcVersion::P2wsh => {
+            let witness_script = Script::from(liquid.witness_script.clone());
+            let spec =
+                HtlcSpec::parse_witness_script(&witness_script).context("parse witness_script")?;
+            anyhow::ensure!(
+                spec.witness_script() == witness_script,
+                "witness_script is not the canonical HTLC script"
+            );
+            HtlcTerms {
+                payment_hash: spec.payment_hash,
+                refund_lock_height: spec.refund_lock_height,
+                script_pubkey: spec.p2wsh_address(p2wsh_address.params).script_pubkey(),
+                claimer_pubkey_hash160s: vec![spec.claimer_pubkey_hash160],
+                refunder_pubkey_hash160s: vec![spec.refunder_pubkey_hash160],
+            }
+        }
+        pb::HtlcVersion::Taproot => {
+            let spec = TaprootHtlcSpec::parse_encoded_leaves(&liquid.witness_script)
+                .context("parse taproot leaves")?;
+            HtlcTerms {
+                payment_hash: spec.payment_hash,
+                refund_lock_height: spec.refund_lock_height,
+                script_pubkey: spec
+                    .p2tr_address(p2wsh_address.params)
+                    .context("derive taproot address")?
+                    .script_pubkey(),
+                claimer_pubkey_hash160s: xonly_pubkey_hash160s(&spec.claimer_pubkey),
+                refunder_pubkey_hash160s: xonly_pubkey_hash160s(&spec.refunder_pubkey),
+            }
+        }
+    };
+    let htlc_script_pubkey = terms.script_pubkey;
+    anyhow::ensure!(
+        p2wsh_address.script_pubkey() == htlc_script_pubkey,
+        "p2wsh_address does not commit to witness_script"
+    );
+
+    let invoice_payment_hash =
+        payment_hash_from_bolt11(&swap.bolt11_invoice).context("parse bolt11_invoice")?;
+    anyhow::ensure!(
+        terms.payment_hash == invoice_payment_hash,
+        "HTLC payment hash does not match bolt11_invoice"
+    );
+    anyhow::ensure!(
+        swap.payment_hash == hex::encode(invoice_payment_hash),
+        "swap payment_hash does not match bolt11_invoice"
+    );
+    let invoice_amount_msat =
+        amount_msat_from_bolt11(&swap.bolt11_invoice).context("parse bolt11_invoice")?;
+    anyhow::ensure!(
+        invoice_amount_msat == Some(expected.total_price_msat),
+        "bolt11_invoice amount mismatch: expected {} msat, got {:?} msat",
+        expected.total_price_msat,
+        invoice_amount_msat
+    );
+
+    let direction = pb::SwapDirection::try_from(swap.direction)
+        .map_err(|_| anyhow::anyhow!("unknown swap direction: {}", swap.direction))?;
+    let user_pubkey_hash160s = match direction {
+        pb::SwapDirection::LnToLiquid => &terms.claimer_pubkey_hash160s,
+        pb::SwapDirection::LiquidToLn => &terms.refunder_pubkey_hash160s,
+        pb::SwapDirection::Unspecified => anyhow::bail!("swap direction is unspecified"),
+    };
+    anyhow::ensure!(
+        user_pubkey_hash160s.contains(&expected.user_pubkey_hash160),
+        "HTLC {} key does not belong to the user",
+        match direction {
+            pb::SwapDirection::LnToLiquid => "claim",
+            _ => "refund",
+        }
+    );
+
+    anyhow::ensure!(
+        terms.refund_lock_height == liquid.refund_lock_height,
+        "refund_lock_height mismatch: witness_script has {}, swap has {}",
+        terms.refund_lock_height,
+        liquid.refund_lock_height
+    );
+    let min_refund_lock_height = expected
+        .tip_height
+        .saturating_add(expected.min_refund_blocks);
+    anyhow::ensure!(
+        terms.refund_lock_height >= min_refund_lock_height,
+        "refund_lock_height {} is too close to tip {} (need >= {})",
+        terms.refund_lock_height,
+        expected.tip_height,
+        min_refund_lock_height
+    );
+
+    let asset_id = AssetId::from_str(&liquid.asset_id).context("parse asset_id")?;
+    anyhow::ensure!(
+        asset_id == expected.asset_id,
+        "asset_id mismatch: expected {}, got {asset_id}",
+        expected.asset_id
+    );
+    anyhow::ensure!(
+        liquid.asset_amount == expected.asset_amount,
+        "asset_amount mismatch: expected {}, got {}",
+        expected.asset_amount,
+        liquid.asset_amount
+    );
+    anyhow::ensure!(
+        liquid.fee_subsidy_sats == expected.fee_subsidy_sats,
+        "fee_subsidy_sats mismatch: expected {}, got {}",
+        expected.fee_subsidy_sats,
+        liquid.fee_subsidy_sats
+    );
+
+    anyhow::ensure!(
+        funding_tx.txid() == funding_txid(swap)?,
+        "funding transaction does not match funding_txid"
+    );
+    let blinding_key = if liquid.blinding_key.is_empty() {
+        None
+    } else {
+        Some(SecretKey::from_slice(&liquid.blinding_key).context("parse blinding_key")?)
+    };
+    verify_output(
+        funding_tx,
+        liquid.asset_vout,
+        &htlc_script_pubkey,
+        expected.asset_id,
+        expected.asset_amount,
+        blinding_key,
+    )
+    .context("asset output")?;
+    verify_output(
+        funding_tx,
+        liquid.lbtc_vout,
+        &htlc_script_pubkey,
+        expected.policy_asset,
+        expected.fee_subsidy_sats,
+        blinding_key,
+    )
+    .context("fee subsidy output")?;
+
+    Ok(VerifiedHtlc {
+        payment_hash: terms.payment_hash,
+        refund_lock_height: terms.refund_lock_height,
+    })
+}
+
+fn xonly_pubkey_hash160s(pubkey: &XOnlyPublicKey) -> Vec<[u8; 20]> {
+    [Parity::Even, Parity::Odd]
+        .into_iter()
+        .map(|parity| pubkey_hash160(&pubkey.public_key(parity).serialize()))
+        .collect()
+}
+
+fn verify_output(
+    tx: &Transaction,
+    vout: u32,
+    script_pubkey: &Script,
+    asset_id: AssetId,
+    value: u64,
+    blinding_key: Option<SecretKey>,
+) -> Result<()> {
+    let out = tx
+        .output
+        .get(vout as usize)
+        .with_context(|| format!("funding tx has no output {vout}"))?;
+    anyhow::ensure!(
+        &out.script_pubkey == script_pubkey,
+        "output {vout} does not pay the HTLC"
+    );
+    let (out_asset, out_value) = match (out.asset, out.value) {
+        (Asset::Explicit(out_asset), Value::Explicit(out_value)) => (out_asset, out_value),
+        _ => {
+            let blinding_key = blinding_key.with_context(|| {
+                format!("output {vout} is blinded but the swap has no blinding_key")
+            })?;
+            let secrets = out
+                .unblind(&Secp256k1::new(), blinding_key)
+                .map_err(|e| anyhow::anyhow!("unblind output {vout}: {e:?}"))?;
+            (secrets.asset, secrets.value)
+        }
+    };
+    anyhow::ensure!(
+        out_asset == asset_id,
+        "output {vout} asset mismatch: expected {asset_id}, got {out_asset}"
+    );
+    anyhow::ensure!(
+        out_value == value,
+        "output {vout} value mismatch: expected {value}, got {out_value}"
+    );
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+
+use ln_liquid_swap::lightning::backend::{LightningBackend, PaymentState, wait_preimage};
+use ln_liquid_swap::lightning::fake::{FakeLightningNode, FakePaymentBehavior};
+use ln_liquid_swap::lightning::invoice::{
+    amount_msat_from_bolt11, is_expired_bolt11, payment_hash_from_bolt11,
+};
+use ln_liquid_swap::liquid::htlc::sha256_preimage;
+use ln_liquid_swap::swap::payment::execute_lightning_payment;
+use ln_liquid_swap::swap::{HtlcVersion, SwapDirection, SwapRecord, SwapStatus};
+
+fn funded_swap(bolt11_invoice: &str) -> Result<SwapRecord> {
+    let payment_hash = payment_hash_from_bolt11(bolt11_invoice).context("parse invoice")?;
+    Ok(SwapRecord {
+        swap_id: "swap-a".to_string(),
+        quote_id: "quote-a".to_string(),
+        direction: SwapDirection::LnToLiquid,
+        bolt11_invoice: bolt11_invoice.to_string(),
+        payment_hash: hex::encode(payment_hash),
+        asset_id: "asset_id:swap-a".to_string(),
+        asset_amount: 1000,
+        total_price_msat: 1_000_000,
+        buyer_liquid_address: "buyer_liquid_address:swap-a".to_string(),
+        fee_subsidy_sats: 10_000,
+        refund_lock_height: 123,
+        p2wsh_address: "p2wsh:swap-a".to_string(),
+        witness_script_hex: "00".to_string(),
+        funding_txid: "funding_txid:swap-a".to_string(),
+        asset_vout: 0,
+        lbtc_vout: 1,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Funded,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    })
+}
+
+#[tokio::test]
+async fn fake_node_issues_signed_bolt11_invoices() -> Result<()> {
+    let node = FakeLightningNode::new();
+    let invoice = node
+        .create_invoice(1_000_000, "swap:test".to_string(), 3600)
+        .await
+        .context("create invoice")?;
+
+    assert_eq!(amount_msat_from_bolt11(&invoice)?, Some(1_000_000));
+    assert!(!is_expired_bolt11(&invoice)?);
+
+    let payment_hash = payment_hash_from_bolt11(&invoice)?;
+    let preimage = node
+        .preimage_for(&payment_hash)
+        .context("fake node must know the preimage of its own invoice")?;
+    assert_eq!(sha256_preimage(&preimage), payment_hash);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn lightning_payment_reveals_matching_preimage() -> Result<()> {
+    let payee = FakeLightningNode::new();
+    let payer = FakeLightningNode::new();
+    let invoice = payee
+        .create_invoice(1_000_000, "swap:swap-a".to_string(), 3600)
+        .await?;
+    let record = funded_swap(&invoice)?;
+
+    let status = execute_lightning_payment(&payer, &record, Duration::from_secs(1))
+        .await
+        .unwrap_err();
+    assert!(status.message().contains("payment failed"), "{status:?}");
+
+    let payment_hash = payment_hash_from_bolt11(&invoice)?;
+    payer.learn_preimage(
+        payee
+            .preimage_for(&payment_hash)
+            .context("payee preimage")?,
+    );
+
+    let result = execute_lightning_payment(&payer, &record, Duration::from_secs(1))
+        .await
+        .map_err(|s| anyhow::anyhow!("{s:?}"))?;
+    assert_eq!(sha256_preimage(&result.preimage), payment_hash);
+    assert_eq!(payer.paid_invoices().len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn lightning_payment_rejects_preimage_hash_mismatch() -> Result<()> {
+    let node = FakeLightningNode::new();
+    let invoice = node
+        .create_invoice(1_000_000, "swap:swap-a".to_string(), 3600)
+        .await?;
+    let record = funded_swap(&invoice)?;
+
+    node.set_pay_behavior(FakePaymentBehavior::WrongPreimage);
+    let status = execute_lightning_payment(&node, &record, Duration::from_secs(1))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Internal);
+    assert_eq!(status.message(), "preimage hash mismatch");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn lightning_payment_surfaces_failures_and_timeouts() -> Result<()> {
+    let node = FakeLightningNode::new();
+    let invoice = node
+        .create_invoice(1_000_000, "swap:swap-a".to_string(), 3600)
+        .await?;
+    let record = funded_swap(&invoice)?;
+
+    node.set_pay_behavior(FakePaymentBehavior::Fail(
+        "insufficient liquidity".to_string(),
+    ));
+    let status = execute_lightning_payment(&node, &record, Duration::from_secs(1))
+        .await
+        .unwrap_err();
+    assert!(
+        status.message().contains("insufficient liquidity"),
+        "{status:?}"
+    );
+
+    node.set_pay_behavior(FakePaymentBehavior::Hang);
+    let status = execute_lightning_payment(&node, &record, Duration::from_millis(50))
+        .await
+        .unwrap_err();
+    assert!(status.message().contains("timeout"), "{status:?}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wait_preimage_follows_payment_updates() -> Result<()> {
+    let node = FakeLightningNode::new();
+    let invoice = node
+        .create_invoice(1_000_000, "swap:swap-a".to_string(), 3600)
+        .await?;
+    let payment_hash = payment_hash_from_bolt11(&invoice)?;
+    let preimage = node.preimage_for(&payment_hash).context("preimage")?;
+
+    node.set_pay_behavior(FakePaymentBehavior::Hang);
+    let mut updates = node.subscribe_payments();
+    let payment_id = node.pay_invoice(invoice).await?;
+    assert_eq!(
+        node.payment_state(&payment_id).await?,
+        Some(PaymentState::Pending)
+    );
+
+    let settler = node.clone();
+    let settled_id = payment_id.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        settler.set_payment_state("unrelated-payment", PaymentState::Pending);
+        settler.set_payment_state(&settled_id, PaymentState::Succeeded(preimage));
+    });
+
+    let got = wait_preimage(&node, &mut updates, &payment_id, Duration::from_secs(5)).await?;
+    assert_eq!(got, preimage);
+    assert_eq!(node.payment_state("missing").await?, None);
+
+    Ok(())
+}
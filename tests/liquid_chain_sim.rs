@@ -0,0 +1,538 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::bitcoin::PublicKey;
+use lwk_wollet::elements::bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, Transaction, TxOut, TxOutWitness,
+};
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::backend::LightningBackend as _;
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::lightning::invoice::payment_hash_from_bolt11;
+use ln_liquid_swap::liquid::chain::{LiquidChain as _, confirmations};
+use ln_liquid_swap::liquid::fee::{
+    MIN_FEE_RATE_SAT_VB, build_at_fee_rate, cpfp_child_fee_rate, fee_for_vsize, tx_fee,
+    tx_fee_rate, tx_vsize,
+};
+use ln_liquid_swap::liquid::htlc::{
+    HtlcFunding, HtlcSpec, claim_tx_from_witness_script, pubkey_hash160_from_p2wpkh_address,
+    refund_tx_from_witness_script, sha256_preimage,
+};
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::proto::v1::{CreateAssetClaimRequest, CreateLightningPaymentRequest};
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{
+    HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus, SwapTxKind,
+};
+
+const BUYER_TOKEN: &str = "buyer-token";
+const SELLER_TOKEN: &str = "seller-token";
+
+fn asset_id() -> Result<AssetId> {
+    AssetId::from_str("0101010101010101010101010101010101010101010101010101010101010101")
+        .context("asset_id")
+}
+
+fn policy_asset() -> Result<AssetId> {
+    AssetId::from_str("0202020202020202020202020202020202020202020202020202020202020202")
+        .context("policy_asset")
+}
+
+fn explicit_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+/// Broadcasts an input-less funding tx straight to the simulated chain.
+///
+/// The simulated chain never feeds transactions to the wallet, so these tests do not cover
+/// anything that spends wallet UTXOs or reads wallet balances, such as the funding tx CreateSwap
+/// builds. Those paths run against elementsd in `lwk_liquid_regtest_e2e` and `ln_liquid_swap_e2e`.
+fn fund_htlc(
+    chain: &SimulatedLiquidChain,
+    htlc_address: &Address,
+    asset_id: AssetId,
+    policy_asset: AssetId,
+) -> Result<HtlcFunding> {
+    let tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            explicit_output(htlc_address, asset_id, 5_000),
+            explicit_output(htlc_address, policy_asset, 2_000),
+        ],
+    };
+    let funding_txid = chain.broadcast(&tx).context("broadcast funding tx")?;
+    Ok(HtlcFunding {
+        funding_txid,
+        asset_vout: 0,
+        lbtc_vout: 1,
+        asset_id,
+        asset_amount: 5_000,
+        policy_asset,
+        fee_subsidy_sats: 2_000,
+        blinding: None,
+    })
+}
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+#[test]
+fn simulated_chain_mines_reorgs_and_evicts() -> Result<()> {
+    let mut chain = SimulatedLiquidChain::new();
+    assert_eq!(chain.tip_height()?, 0);
+    assert_eq!(chain.mine_blocks(10), 10);
+
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(&[3u8; 32]).context("secret key")?;
+    let address = Address::p2wpkh(
+        &PublicKey::new(secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+    let funding = fund_htlc(&chain, &address, asset_id()?, policy_asset()?)?;
+    let txid = funding.funding_txid;
+
+    assert_eq!(chain.mempool_txids(), vec![txid]);
+    let history = chain.script_history(&address.script_pubkey())?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(confirmations(history[0].height, chain.tip_height()?), 0);
+
+    chain.mine_blocks(3);
+    assert_eq!(chain.tx_height(&txid), Some(Some(11)));
+    let history = chain.script_history(&address.script_pubkey())?;
+    assert_eq!(confirmations(history[0].height, chain.tip_height()?), 3);
+
+    assert_eq!(chain.reorg(3)?, 10);
+    assert_eq!(chain.tx_height(&txid), Some(None));
+    assert!(chain.evict(&txid));
+    assert!(chain.get_transaction(&txid).is_err());
+    assert!(chain.script_history(&address.script_pubkey())?.is_empty());
+    assert!(chain.reorg(11).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn htlc_claim_and_refund_respect_chain_rules() -> Result<()> {
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let secp = Secp256k1::new();
+    let claimer_secret_key = SecretKey::from_slice(&[1u8; 32]).context("claimer secret key")?;
+    let refunder_secret_key = SecretKey::from_slice(&[2u8; 32]).context("refunder secret key")?;
+    let claimer_address = Address::p2wpkh(
+        &PublicKey::new(claimer_secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+    let refunder_address = Address::p2wpkh(
+        &PublicKey::new(refunder_secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+
+    let payment_preimage = [9u8; 32];
+    let refund_lock_height = 110;
+    let spec = HtlcSpec {
+        payment_hash: sha256_preimage(&payment_preimage),
+        claimer_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&claimer_address)?,
+        refunder_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&refunder_address)?,
+        refund_lock_height,
+    };
+    let witness_script = spec.witness_script();
+    let htlc_address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+
+    let funding = fund_htlc(&chain, &htlc_address, asset_id()?, policy_asset()?)?;
+    chain.mine_blocks(1);
+
+    let refund_tx = refund_tx_from_witness_script(
+        &witness_script,
+        refund_lock_height,
+        &funding,
+        &refunder_address,
+        &refunder_secret_key,
+        500,
+    )
+    .context("build refund tx")?;
+    let err = chain.broadcast(&refund_tx).unwrap_err();
+    assert!(format!("{err:#}").contains("non-final"), "{err:#}");
+
+    chain.reject_broadcasts(Some("node offline".to_string()));
+    let claim_tx = claim_tx_from_witness_script(
+        &witness_script,
+        &funding,
+        &claimer_address,
+        &claimer_secret_key,
+        payment_preimage,
+        500,
+    )
+    .context("build claim tx")?;
+    let err = chain.broadcast(&claim_tx).unwrap_err();
+    assert!(format!("{err:#}").contains("node offline"), "{err:#}");
+    chain.reject_broadcasts(None);
+
+    chain.mine_blocks(refund_lock_height - chain.height());
+    let claim_txid = chain.broadcast(&claim_tx).context("broadcast claim tx")?;
+    assert_eq!(chain.broadcast(&claim_tx)?, claim_txid);
+
+    let err = chain.broadcast(&refund_tx).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("txn-mempool-conflict"),
+        "{err:#}"
+    );
+
+    chain.mine_blocks(1);
+    let err = chain.broadcast(&refund_tx).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("bad-txns-inputs-missingorspent"),
+        "{err:#}"
+    );
+
+    let history = chain.script_history(&htlc_address.script_pubkey())?;
+    let txids: Vec<_> = history.iter().map(|h| h.txid).collect();
+    assert_eq!(txids, vec![funding.funding_txid, claim_txid]);
+
+    Ok(())
+}
+
+#[test]
+fn htlc_claim_can_be_replaced_by_fee() -> Result<()> {
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let secp = Secp256k1::new();
+    let claimer_secret_key = SecretKey::from_slice(&[1u8; 32]).context("claimer secret key")?;
+    let claimer_address = Address::p2wpkh(
+        &PublicKey::new(claimer_secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+    let payment_preimage = [9u8; 32];
+    let spec = HtlcSpec {
+        payment_hash: sha256_preimage(&payment_preimage),
+        claimer_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&claimer_address)?,
+        refunder_pubkey_hash160: [2u8; 20],
+        refund_lock_height: 200,
+    };
+    let witness_script = spec.witness_script();
+    let htlc_address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+    let funding = fund_htlc(&chain, &htlc_address, asset_id()?, policy_asset()?)?;
+    chain.mine_blocks(1);
+
+    let claim = |fee_sats| {
+        claim_tx_from_witness_script(
+            &witness_script,
+            &funding,
+            &claimer_address,
+            &claimer_secret_key,
+            payment_preimage,
+            fee_sats,
+        )
+    };
+    let original = build_at_fee_rate(0.1, claim)?;
+    assert!(tx_fee_rate(&original) >= 0.1);
+    let original_txid = chain.broadcast(&original)?;
+
+    // A replacement must pay for its own relay on top of the replaced fee.
+    let err = chain.broadcast(&claim(tx_fee(&original) + 1)?).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("txn-mempool-conflict"),
+        "{err:#}"
+    );
+
+    let replacement = build_at_fee_rate(1.0, claim)?;
+    assert!(tx_fee_rate(&replacement) >= 1.0);
+    let replacement_txid = chain.broadcast(&replacement)?;
+    assert_eq!(chain.mempool_txids(), vec![replacement_txid]);
+    assert!(chain.get_transaction(&original_txid).is_err());
+
+    chain.mine_blocks(1);
+    assert_eq!(
+        chain.tx_height(&replacement_txid),
+        Some(Some(chain.height()))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cpfp_child_pays_the_parent_shortfall() -> Result<()> {
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(&[3u8; 32]).context("secret key")?;
+    let address = Address::p2wpkh(
+        &PublicKey::new(secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+    let parent = |fee_sats: u64| -> Result<Transaction> {
+        Ok(Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![
+                explicit_output(&address, policy_asset()?, 5_000),
+                TxOut::new_fee(fee_sats, policy_asset()?),
+            ],
+        })
+    };
+    let child_vsize = 150;
+
+    for parent_fee in [0, 10] {
+        let parent = parent(parent_fee)?;
+        let package_fee = fee_for_vsize(tx_vsize(&parent) + child_vsize, 1.0);
+        let child_fee = fee_for_vsize(child_vsize, cpfp_child_fee_rate(&parent, child_vsize, 1.0));
+        assert!(parent_fee + child_fee >= package_fee);
+        // Rounding may cost the child at most one extra sat.
+        assert!(parent_fee + child_fee <= package_fee + 1);
+    }
+
+    // A parent that already pays the target still needs a relayable child.
+    let rich = parent(10_000)?;
+    assert_eq!(
+        cpfp_child_fee_rate(&rich, child_vsize, 1.0),
+        MIN_FEE_RATE_SAT_VB
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cpfp_is_skipped_without_wallet_change() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+    let mut wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+
+    // The output pays a wallet address, but the simulated chain never hands it to the wollet, so
+    // the wallet has no change to spend.
+    let parent = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![explicit_output(
+            &wallet.address_at(0)?,
+            wallet.policy_asset(),
+            5_000,
+        )],
+    };
+    let parent_txid = chain.broadcast(&parent)?;
+
+    assert!(wallet.build_and_broadcast_cpfp(&parent, 1.0)?.is_none());
+    assert_eq!(chain.mempool_txids(), vec![parent_txid]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn swap_service_pays_and_claims_against_simulated_chain() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    assert_eq!(wallet.tip_height(), 100);
+
+    let seller_key_index = 0;
+    let buyer_key_index = 1;
+    let seller_address = wallet.address_at(seller_key_index)?;
+    let buyer_address = wallet.address_at(buyer_key_index)?;
+    let policy_asset = wallet.policy_asset();
+    let asset_id = asset_id()?;
+
+    let seller_ln = FakeLightningNode::new();
+    let buyer_ln = FakeLightningNode::new();
+    let invoice = seller_ln
+        .create_invoice(5_000_000, "swap:swap-a".to_string(), 3600)
+        .await?;
+    let payment_hash = payment_hash_from_bolt11(&invoice)?;
+    buyer_ln.learn_preimage(
+        seller_ln
+            .preimage_for(&payment_hash)
+            .context("seller preimage")?,
+    );
+
+    let refund_lock_height = 200;
+    let spec = HtlcSpec {
+        payment_hash,
+        claimer_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&buyer_address)?,
+        refunder_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&seller_address)?,
+        refund_lock_height,
+    };
+    let htlc_address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+    let funding = fund_htlc(&chain, &htlc_address, asset_id, policy_asset)?;
+    chain.mine_blocks(1);
+
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    store.insert_quote(&QuoteRecord {
+        quote_id: "quote-a".to_string(),
+        offer_id: "offer-a".to_string(),
+        direction: SwapDirection::LnToLiquid,
+        asset_id: asset_id.to_string(),
+        asset_amount: funding.asset_amount,
+        min_funding_confs: 1,
+        total_price_msat: 5_000_000,
+        price_msat_per_asset_unit: 1000,
+        fee_subsidy_sats: funding.fee_subsidy_sats,
+        refund_delta_blocks: 100,
+        invoice_expiry_secs: 3600,
+        max_min_funding_confs: 6,
+        supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+        min_asset_amount: 0,
+        max_asset_amount: 0,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        price_source: "offer".to_string(),
+        source_price_msat_per_asset_unit: 1_000,
+        spread_bps: 0,
+        priced_at: 0,
+        swap_id: Some("swap-a".to_string()),
+        buyer_id: Some("buyer".to_string()),
+        created_at: 0,
+        expires_at: 0,
+    })?;
+    store.insert_swap(&SwapRecord {
+        swap_id: "swap-a".to_string(),
+        quote_id: "quote-a".to_string(),
+        direction: SwapDirection::LnToLiquid,
+        bolt11_invoice: invoice,
+        payment_hash: hex::encode(payment_hash),
+        asset_id: asset_id.to_string(),
+        asset_amount: funding.asset_amount,
+        total_price_msat: 5_000_000,
+        buyer_liquid_address: buyer_address.to_string(),
+        fee_subsidy_sats: funding.fee_subsidy_sats,
+        refund_lock_height,
+        p2wsh_address: htlc_address.to_string(),
+        witness_script_hex: hex::encode(spec.witness_script().to_bytes()),
+        funding_txid: funding.funding_txid.to_string(),
+        asset_vout: funding.asset_vout,
+        lbtc_vout: funding.lbtc_vout,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Funded,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    })?;
+    let store = Arc::new(Mutex::new(store));
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id: asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+                price_msat_per_asset_unit: 1000,
+                fee_subsidy_sats: funding.fee_subsidy_sats,
+                refund_delta_blocks: 100,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index,
+            buyer_key_index,
+            seller_token: SELLER_TOKEN.to_string(),
+            buyer_token: BUYER_TOKEN.to_string(),
+        },
+        Arc::new(buyer_ln.clone()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+
+    let payment = svc
+        .create_lightning_payment(with_auth(
+            CreateLightningPaymentRequest {
+                swap_id: "swap-a".to_string(),
+                payment_timeout_secs: 1,
+            },
+            BUYER_TOKEN,
+        )?)
+        .await
+        .map_err(|s| anyhow::anyhow!("{s:?}"))?
+        .into_inner();
+    let preimage: [u8; 32] = payment
+        .preimage
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("preimage must be 32 bytes"))?;
+    assert_eq!(sha256_preimage(&preimage), payment_hash);
+
+    let claim = svc
+        .create_asset_claim(with_auth(
+            CreateAssetClaimRequest {
+                swap_id: "swap-a".to_string(),
+                claim_fee_sats: 500,
+            },
+            BUYER_TOKEN,
+        )?)
+        .await
+        .map_err(|s| anyhow::anyhow!("{s:?}"))?
+        .into_inner();
+    assert_eq!(
+        chain
+            .mempool_txids()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec![claim.claim_txid.clone()]
+    );
+
+    chain.mine_blocks(1);
+    let store = store.lock().expect("store mutex poisoned");
+    let record = store.get_swap("swap-a")?.context("swap-a missing")?;
+    assert_eq!(record.status, SwapStatus::Claimed);
+    assert_eq!(
+        record.claim_txid.as_deref(),
+        Some(claim.claim_txid.as_str())
+    );
+    assert!(store.get_swap_tx("swap-a", SwapTxKind::Claim)?.is_some());
+
+    Ok(())
+}
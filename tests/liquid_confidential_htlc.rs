@@ -0,0 +1,192 @@
+use std::str::FromStr as _;
+
+use anyhow::{Context as _, Result};
+use ln_liquid_swap::liquid::htlc::{
+    HtlcBlinding, HtlcFunding, HtlcSpec, claim_tx_from_witness_script,
+    pubkey_hash160_from_p2wpkh_address, refund_tx_from_witness_script, sha256_preimage,
+};
+use lwk_wollet::elements::bitcoin::PublicKey;
+use lwk_wollet::elements::confidential::{
+    Asset, AssetBlindingFactor, Nonce, Value, ValueBlindingFactor,
+};
+use lwk_wollet::elements::secp256k1_zkp::{
+    PublicKey as ZkpPublicKey, Secp256k1, SecretKey as ZkpSecretKey,
+};
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, OutPoint, Script, Sequence, Transaction, TxIn,
+    TxInWitness, TxOut, TxOutSecrets, TxOutWitness, Txid,
+};
+
+fn explicit_input(vout: u32) -> Result<TxIn> {
+    Ok(TxIn {
+        previous_output: OutPoint::new(
+            Txid::from_str("0000000000000000000000000000000000000000000000000000000000000001")?,
+            vout,
+        ),
+        is_pegin: false,
+        script_sig: Script::new(),
+        sequence: Sequence::MAX,
+        asset_issuance: Default::default(),
+        witness: TxInWitness::default(),
+    })
+}
+
+fn blinded_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Confidential(address.blinding_pubkey.expect("confidential address")),
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+#[test]
+fn confidential_htlc_claim_and_refund_are_blinded() -> Result<()> {
+    let secp = Secp256k1::new();
+    let claimer_secret_key = ZkpSecretKey::from_slice(&[1u8; 32]).context("claimer secret key")?;
+    let refunder_secret_key =
+        ZkpSecretKey::from_slice(&[2u8; 32]).context("refunder secret key")?;
+    let receive_blinding_key =
+        ZkpSecretKey::from_slice(&[3u8; 32]).context("receive blinding key")?;
+    let htlc_blinding_key = ZkpSecretKey::from_slice(&[4u8; 32]).context("htlc blinding key")?;
+
+    let receive_blinding_pubkey = ZkpPublicKey::from_secret_key(&secp, &receive_blinding_key);
+    let claimer_address = Address::p2wpkh(
+        &PublicKey::new(claimer_secret_key.public_key(&secp)),
+        Some(receive_blinding_pubkey),
+        &AddressParams::ELEMENTS,
+    );
+    let refunder_address = Address::p2wpkh(
+        &PublicKey::new(refunder_secret_key.public_key(&secp)),
+        Some(receive_blinding_pubkey),
+        &AddressParams::ELEMENTS,
+    );
+
+    let preimage = [9u8; 32];
+    let spec = HtlcSpec {
+        payment_hash: sha256_preimage(&preimage),
+        claimer_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&claimer_address)?,
+        refunder_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&refunder_address)?,
+        refund_lock_height: 1_000,
+    };
+    let htlc_address = spec
+        .p2wsh_address(&AddressParams::ELEMENTS)
+        .to_confidential(ZkpPublicKey::from_secret_key(&secp, &htlc_blinding_key));
+
+    let asset_id =
+        AssetId::from_str("0101010101010101010101010101010101010101010101010101010101010101")?;
+    let policy_asset =
+        AssetId::from_str("0202020202020202020202020202020202020202020202020202020202020202")?;
+
+    let mut funding_tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![explicit_input(0)?, explicit_input(1)?],
+        output: vec![
+            blinded_output(&htlc_address, asset_id, 5_000),
+            blinded_output(&htlc_address, policy_asset, 2_000),
+            TxOut::new_fee(100, policy_asset),
+        ],
+    };
+    funding_tx
+        .blind(
+            &mut rand::thread_rng(),
+            &secp,
+            &[
+                TxOutSecrets::new(
+                    asset_id,
+                    AssetBlindingFactor::zero(),
+                    5_000,
+                    ValueBlindingFactor::zero(),
+                ),
+                TxOutSecrets::new(
+                    policy_asset,
+                    AssetBlindingFactor::zero(),
+                    2_100,
+                    ValueBlindingFactor::zero(),
+                ),
+            ],
+        )
+        .map_err(|e| anyhow::anyhow!("blind funding tx: {e:?}"))?;
+    assert!(!funding_tx.output[0].asset.is_explicit());
+
+    let funding = HtlcFunding {
+        funding_txid: funding_tx.txid(),
+        asset_vout: 0,
+        lbtc_vout: 1,
+        asset_id,
+        asset_amount: 5_000,
+        policy_asset,
+        fee_subsidy_sats: 2_000,
+        blinding: Some(HtlcBlinding::from_funding_tx(
+            &funding_tx,
+            0,
+            1,
+            htlc_blinding_key,
+        )?),
+    };
+
+    let claim_tx = claim_tx_from_witness_script(
+        &spec.witness_script(),
+        &funding,
+        &claimer_address,
+        &claimer_secret_key,
+        preimage,
+        500,
+    )
+    .context("build claim tx")?;
+    let claimed = claim_tx.output[0]
+        .unblind(&secp, receive_blinding_key)
+        .map_err(|e| anyhow::anyhow!("unblind claim output: {e:?}"))?;
+    assert_eq!(claimed.asset, asset_id);
+    assert_eq!(claimed.value, 5_000);
+    let subsidy = claim_tx.output[1]
+        .unblind(&secp, receive_blinding_key)
+        .map_err(|e| anyhow::anyhow!("unblind subsidy output: {e:?}"))?;
+    assert_eq!(subsidy.asset, policy_asset);
+    assert_eq!(subsidy.value, 1_500);
+    assert!(claim_tx.output[2].is_fee());
+
+    let refund_tx = refund_tx_from_witness_script(
+        &spec.witness_script(),
+        spec.refund_lock_height,
+        &funding,
+        &refunder_address,
+        &refunder_secret_key,
+        500,
+    )
+    .context("build refund tx")?;
+    assert!(!refund_tx.output[0].asset.is_explicit());
+    assert!(!refund_tx.output[1].value.is_explicit());
+
+    let unconfidential = claimer_address.to_unconfidential();
+    let err = claim_tx_from_witness_script(
+        &spec.witness_script(),
+        &funding,
+        &unconfidential,
+        &claimer_secret_key,
+        preimage,
+        500,
+    )
+    .expect_err("explicit receive address must be rejected");
+    assert!(format!("{err:#}").contains("requires a confidential receive address"));
+
+    let mut wrong_key = funding.clone();
+    if let Some(blinding) = wrong_key.blinding.as_mut() {
+        blinding.blinding_key = receive_blinding_key;
+    }
+    assert!(
+        claim_tx_from_witness_script(
+            &spec.witness_script(),
+            &wrong_key,
+            &claimer_address,
+            &claimer_secret_key,
+            preimage,
+            500,
+        )
+        .is_err()
+    );
+
+    Ok(())
+}
@@ -2,12 +2,12 @@ use std::str::FromStr as _;
 
 use anyhow::{Context as _, Result};
 use ln_liquid_swap::liquid::htlc::{
-    HtlcFunding, HtlcSpec, claim_tx_from_witness_script, pubkey_hash160_from_p2wpkh_address,
-    refund_tx_from_witness_script, sha256_preimage,
+    HtlcFunding, HtlcSpec, HtlcSpend, claim_tx_from_witness_script, classify_htlc_spend,
+    pubkey_hash160_from_p2wpkh_address, refund_tx_from_witness_script, sha256_preimage,
 };
 use lwk_wollet::elements::bitcoin::PublicKey;
 use lwk_wollet::elements::bitcoin::secp256k1::{Secp256k1, SecretKey};
-use lwk_wollet::elements::{Address, AddressParams, AssetId, LockTime, Txid};
+use lwk_wollet::elements::{Address, AddressParams, AssetId, LockTime, OutPoint, Txid};
 
 #[test]
 fn htlc_claim_and_refund_builds() -> Result<()> {
@@ -54,6 +54,7 @@ fn htlc_claim_and_refund_builds() -> Result<()> {
         )
         .context("policy_asset")?,
         fee_subsidy_sats: 2_000,
+        blinding: None,
     };
 
     let witness_script = spec.witness_script();
@@ -99,5 +100,26 @@ fn htlc_claim_and_refund_builds() -> Result<()> {
         refunder_address.script_pubkey()
     );
 
+    let asset_outpoint = OutPoint::new(funding.funding_txid, funding.asset_vout);
+    assert_eq!(
+        classify_htlc_spend(&claim_tx, &asset_outpoint, &payment_hash)?,
+        Some(HtlcSpend::Claim {
+            preimage: payment_preimage
+        })
+    );
+    assert_eq!(
+        classify_htlc_spend(&refund_tx, &asset_outpoint, &payment_hash)?,
+        Some(HtlcSpend::Refund)
+    );
+    assert_eq!(
+        classify_htlc_spend(
+            &claim_tx,
+            &OutPoint::new(funding.funding_txid, 7),
+            &payment_hash
+        )?,
+        None
+    );
+    assert!(classify_htlc_spend(&claim_tx, &asset_outpoint, &[0u8; 32]).is_err());
+
     Ok(())
 }
@@ -0,0 +1,132 @@
+use std::str::FromStr as _;
+
+use anyhow::{Context as _, Result};
+use ln_liquid_swap::liquid::htlc::{HtlcFunding, HtlcSpend, sha256_preimage};
+use ln_liquid_swap::liquid::taproot::{
+    TaprootHtlcSpec, classify_taproot_htlc_spend, taproot_claim_tx_script_path,
+    taproot_refund_tx_script_path,
+};
+use lwk_wollet::elements::bitcoin::PublicKey;
+use lwk_wollet::elements::bitcoin::hashes::Hash as _;
+use lwk_wollet::elements::bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lwk_wollet::elements::{Address, AddressParams, AssetId, BlockHash, LockTime, OutPoint, Txid};
+
+#[test]
+fn taproot_htlc_spend_paths_build_and_classify() -> Result<()> {
+    let secp = Secp256k1::new();
+    let claimer_secret_key = SecretKey::from_slice(&[1u8; 32]).context("claimer secret key")?;
+    let refunder_secret_key = SecretKey::from_slice(&[2u8; 32]).context("refunder secret key")?;
+    let claimer_address = Address::p2wpkh(
+        &PublicKey::new(claimer_secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+    let refunder_address = Address::p2wpkh(
+        &PublicKey::new(refunder_secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+
+    let payment_preimage = [9u8; 32];
+    let spec = TaprootHtlcSpec {
+        payment_hash: sha256_preimage(&payment_preimage),
+        claimer_pubkey: claimer_secret_key.x_only_public_key(&secp).0,
+        refunder_pubkey: refunder_secret_key.x_only_public_key(&secp).0,
+        refund_lock_height: 1_000,
+    };
+
+    let encoded = spec.encoded_leaves();
+    assert_eq!(TaprootHtlcSpec::parse_encoded_leaves(&encoded)?, spec);
+    let mut truncated = encoded.clone();
+    truncated.pop();
+    assert!(TaprootHtlcSpec::parse_encoded_leaves(&truncated).is_err());
+
+    // No party holds the internal key; both spends go through a leaf.
+    assert_eq!(spec.spend_info()?.internal_key(), spec.internal_key());
+    assert_ne!(spec.internal_key(), spec.claimer_pubkey);
+    assert_ne!(spec.internal_key(), spec.refunder_pubkey);
+
+    let address = spec.p2tr_address(&AddressParams::ELEMENTS)?;
+    assert_eq!(
+        TaprootHtlcSpec::parse_encoded_leaves(&encoded)?.p2tr_address(&AddressParams::ELEMENTS)?,
+        address
+    );
+
+    let funding = HtlcFunding {
+        funding_txid: Txid::from_str(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        )
+        .context("funding_txid")?,
+        asset_vout: 0,
+        lbtc_vout: 1,
+        asset_id: AssetId::from_str(
+            "0101010101010101010101010101010101010101010101010101010101010101",
+        )
+        .context("asset_id")?,
+        asset_amount: 5_000,
+        policy_asset: AssetId::from_str(
+            "0202020202020202020202020202020202020202020202020202020202020202",
+        )
+        .context("policy_asset")?,
+        fee_subsidy_sats: 2_000,
+        blinding: None,
+    };
+    let outpoint = OutPoint::new(funding.funding_txid, funding.asset_vout);
+    let genesis_hash = BlockHash::all_zeros();
+
+    let claim_tx = taproot_claim_tx_script_path(
+        &spec,
+        &funding,
+        &claimer_address,
+        &claimer_secret_key,
+        payment_preimage,
+        500,
+        genesis_hash,
+    )
+    .context("build script-path claim tx")?;
+    assert_eq!(claim_tx.lock_time, LockTime::ZERO);
+    assert_eq!(
+        classify_taproot_htlc_spend(&claim_tx, &outpoint, &spec)?,
+        Some(HtlcSpend::Claim {
+            preimage: payment_preimage
+        })
+    );
+    assert!(
+        taproot_claim_tx_script_path(
+            &spec,
+            &funding,
+            &claimer_address,
+            &claimer_secret_key,
+            [8u8; 32],
+            500,
+            genesis_hash,
+        )
+        .is_err()
+    );
+
+    let refund_tx = taproot_refund_tx_script_path(
+        &spec,
+        &funding,
+        &refunder_address,
+        &refunder_secret_key,
+        500,
+        genesis_hash,
+    )
+    .context("build script-path refund tx")?;
+    assert_eq!(
+        refund_tx.lock_time.to_consensus_u32(),
+        spec.refund_lock_height
+    );
+    assert_eq!(
+        classify_taproot_htlc_spend(&refund_tx, &outpoint, &spec)?,
+        Some(HtlcSpend::Refund)
+    );
+
+    let other = OutPoint::new(funding.funding_txid, 7);
+    assert_eq!(
+        classify_taproot_htlc_spend(&refund_tx, &other, &spec)?,
+        None
+    );
+
+    Ok(())
+}
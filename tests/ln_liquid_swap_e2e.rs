@@ -25,6 +25,7 @@ use support::lwk_wallet::LwkWalletFixture;
 use support::port::get_available_port;
 use support::wait::wait_for;
 
+use ln_liquid_swap::lightning::backend::LightningBackend;
 use ln_liquid_swap::lightning::invoice::payment_hash_from_bolt11;
 use ln_liquid_swap::lightning::ldk::LdkLightningClient;
 use ln_liquid_swap::liquid::htlc::sha256_preimage;
@@ -33,8 +34,11 @@ use ln_liquid_swap::proto::v1::swap_service_client::SwapServiceClient;
 use ln_liquid_swap::proto::v1::swap_service_server::SwapServiceServer;
 use ln_liquid_swap::proto::v1::{
     CreateAssetClaimRequest, CreateLightningPaymentRequest, CreateQuoteRequest, CreateSwapRequest,
-    SwapDirection,
+    HtlcVersion, SwapDirection, SwapStatus, WatchSwapRequest,
 };
+use ln_liquid_swap::swap;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
 use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
 use ln_liquid_swap::swap::store::SqliteStore;
 
@@ -297,11 +301,26 @@ async fn run_swap(direction: SwapDirection) -> Result<()> {
     let wallet = Arc::new(Mutex::new(seller_wallet));
 
     let cfg = SwapServiceConfig {
-        sell_asset_id: asset_id,
-        price_msat_per_asset_unit: 1_000,
-        fee_subsidy_sats: 10_000,
-        refund_delta_blocks: 20,
+        offers: OfferBook::new([AssetOffer {
+            asset_id: asset_id,
+            supported_directions: vec![
+                swap::SwapDirection::LnToLiquid,
+                swap::SwapDirection::LiquidToLn,
+            ],
+            price_msat_per_asset_unit: 1_000,
+            fee_subsidy_sats: 10_000,
+            refund_delta_blocks: 2_000,
+            min_asset_amount: 0,
+            max_asset_amount: 0,
+        }])?,
         invoice_expiry_secs: 3600,
+        min_timelock_margin_blocks: 0,
+        payment_safety_blocks: 0,
+        quote_ttl_secs: 600,
+        pricing: PricingPolicy::default(),
+        check_inventory: true,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
         seller_key_index: 0,
         buyer_key_index: 1,
         seller_token: "seller-token".to_string(),
@@ -312,12 +331,15 @@ async fn run_swap(direction: SwapDirection) -> Result<()> {
         SwapDirection::LiquidToLn => (&bob, &alice, "bob"),
         SwapDirection::Unspecified => anyhow::bail!("direction must be specified"),
     };
-    let ln = LdkLightningClient::new(ln_payer.rest_service_address().to_string());
+    let ln: Arc<dyn LightningBackend> = Arc::new(LdkLightningClient::new(
+        ln_payer.rest_service_address().to_string(),
+    ));
     let svc = SwapServiceImpl::new(cfg, ln, wallet.clone(), store);
 
     let port = get_available_port().context("select gRPC port")?;
     let listen_addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
     let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let executor = svc.clone();
     tokio::spawn(async move {
         let _ = Server::builder()
             .add_service(SwapServiceServer::new(svc))
@@ -366,33 +388,59 @@ async fn run_swap(direction: SwapDirection) -> Result<()> {
         String::new()
     };
 
-    let swap = {
-        let mut create_fut = Box::pin(swap_client.create_swap(with_auth(
+    let swap = swap_client
+        .create_swap(with_auth(
             "buyer-token",
             CreateSwapRequest {
                 quote_id: quote.quote_id.clone(),
                 buyer_liquid_address,
                 buyer_bolt11_invoice: buyer_invoice.clone(),
+                buyer_claim_pubkey: Vec::new(),
+                htlc_version: HtlcVersion::P2wsh as i32,
+                confidential: false,
             },
-        )));
+        ))
+        .await
+        .context("CreateSwap")?
+        .into_inner();
+    anyhow::ensure!(
+        swap.status == SwapStatus::Created as i32,
+        "CreateSwap must return before funding confirms"
+    );
 
-        let create_deadline = Instant::now() + Duration::from_secs(60);
-        let mut mine_interval = tokio::time::interval(Duration::from_secs(1));
-        loop {
-            if Instant::now() >= create_deadline {
-                anyhow::bail!("timeout waiting for CreateSwap to return");
-            }
+    let mut events = swap_client
+        .watch_swap(with_auth(
+            "buyer-token",
+            WatchSwapRequest {
+                swap_id: swap.swap_id.clone(),
+            },
+        ))
+        .await
+        .context("WatchSwap")?
+        .into_inner();
+    let funded_deadline = Instant::now() + Duration::from_secs(60);
+    let mut mine_interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        if Instant::now() >= funded_deadline {
+            anyhow::bail!("timeout waiting for swap to be funded");
+        }
 
-            tokio::select! {
-                resp = &mut create_fut => {
-                    break resp.context("CreateSwap")?.into_inner();
-                }
-                _ = mine_interval.tick() => {
-                    env.elementsd_generate(1);
+        tokio::select! {
+            event = events.message() => {
+                let event = event.context("WatchSwap")?.context("WatchSwap stream ended")?;
+                let status = event.swap.map(|s| s.status).unwrap_or_default();
+                if status == SwapStatus::Funded as i32 {
+                    anyhow::ensure!(event.funding_confirmations >= 1, "funded without confirmations");
+                    break;
                 }
             }
+            _ = mine_interval.tick() => {
+                env.elementsd_generate(1);
+                executor.drive_swaps().await.context("drive swaps")?;
+            }
         }
-    };
+    }
+    drop(events);
 
     anyhow::ensure!(swap.quote_id == quote.quote_id, "quote_id mismatch");
     if matches!(direction, SwapDirection::LiquidToLn) {
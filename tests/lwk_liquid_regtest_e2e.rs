@@ -3,7 +3,13 @@ mod support {
     pub mod lwk_wallet;
 }
 
+use std::time::{Duration, Instant};
+
 use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+
+use ln_liquid_swap::liquid::fee::{tx_fee, tx_fee_rate, tx_vsize};
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
 
 use support::lwk_env::LiquidRegtestEnv;
 use support::lwk_wallet::LwkWalletFixture;
@@ -124,3 +130,81 @@ fn lwk_liquid_regtest_wallet_issue_send_balance() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[ignore = "requires `elementsd` and liquid-enabled `electrs` binaries (run via `nix develop`)"]
+fn lwk_liquid_regtest_cpfp_bumps_funding_tx() -> Result<()> {
+    let _ = ln_liquid_swap::logging::init();
+
+    let env = LiquidRegtestEnv::start().context("start liquid regtest env")?;
+    let mut issuer = LwkWalletFixture::new(
+        "issuer",
+        ISSUER_MNEMONIC,
+        ISSUER_SLIP77,
+        &env.electrum_url(),
+    )
+    .context("create issuer wallet fixture")?;
+    issuer
+        .fund_lbtc(&env, 2_000_000)
+        .context("fund issuer with lbtc")?;
+    let (_issuance_txid, asset_id, _token_id) =
+        issuer.issue_asset(&env, 10_000, 1).context("issue asset")?;
+
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let mut wallet = LiquidWallet::new(
+        ISSUER_MNEMONIC,
+        ISSUER_SLIP77,
+        &env.electrum_url(),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let deadline = Instant::now() + Duration::from_secs(60);
+    loop {
+        wallet.sync().context("sync wallet")?;
+        if wallet.balance(&asset_id)? > 0 {
+            break;
+        }
+        anyhow::ensure!(Instant::now() < deadline, "issued asset not seen");
+        std::thread::sleep(Duration::from_millis(500));
+    }
+
+    // lwk builds the funding tx at the 0.1 sat/vB minimum, so 1 sat/vB needs a child.
+    let htlc_address = wallet.address_at(1)?.to_unconfidential();
+    let (parent, parent_txid, _, _) = wallet
+        .build_and_broadcast_funding(&htlc_address, asset_id, 1_000, 1_000, None)
+        .context("fund htlc")?;
+    anyhow::ensure!(
+        tx_fee_rate(&parent) < 1.0,
+        "funding tx already pays 1 sat/vB"
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let child = loop {
+        if let Some(child) = wallet
+            .build_and_broadcast_cpfp(&parent, 1.0)
+            .context("bump funding tx")?
+        {
+            break child;
+        }
+        anyhow::ensure!(Instant::now() < deadline, "funding change not seen");
+        std::thread::sleep(Duration::from_millis(500));
+    };
+
+    anyhow::ensure!(
+        child
+            .input
+            .iter()
+            .all(|input| input.previous_output.txid == parent_txid),
+        "cpfp child must only spend the funding change"
+    );
+    let package_rate =
+        (tx_fee(&parent) + tx_fee(&child)) as f64 / (tx_vsize(&parent) + tx_vsize(&child)) as f64;
+    // lwk sizes the child from its own weight estimate, which may differ by a few vbytes.
+    anyhow::ensure!(
+        package_rate >= 0.95,
+        "package pays {package_rate} sat/vB, want 1"
+    );
+
+    Ok(())
+}
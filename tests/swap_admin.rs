@@ -0,0 +1,191 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::admin_service_server::AdminService as _;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::SwapDirection;
+use ln_liquid_swap::swap::admin::AdminServiceImpl;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+fn quote_request(asset_id: AssetId, asset_amount: u64) -> pb::CreateQuoteRequest {
+    pb::CreateQuoteRequest {
+        direction: pb::SwapDirection::LnToLiquid as i32,
+        asset_id: asset_id.to_string(),
+        asset_amount,
+        min_funding_confs: 1,
+    }
+}
+
+#[tokio::test]
+async fn admin_updates_pauses_and_resumes_offers() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let asset_id = AssetId::from_str(&"01".repeat(32))?;
+    let store = Arc::new(Mutex::new(SqliteStore::open(
+        dir.path().join("swap_store.sqlite3"),
+    )?));
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid],
+                price_msat_per_asset_unit: 1_000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 1,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+    let admin = AdminServiceImpl::new(svc.clone(), store, "admin-token".to_string());
+
+    let stale_quote = svc
+        .create_quote(with_auth(quote_request(asset_id, 100), "seller-token")?)
+        .await?
+        .into_inner();
+
+    let update = pb::UpdateOfferRequest {
+        offer: Some(pb::Offer {
+            asset_id: asset_id.to_string(),
+            supported_directions: vec![pb::SwapDirection::LnToLiquid as i32],
+            price_msat_per_asset_unit: 2_000,
+            fee_subsidy_sats: 2_000,
+            refund_delta_blocks: 100,
+            min_asset_amount: 1,
+            max_asset_amount: 0,
+            ..Default::default()
+        }),
+    };
+    let err = admin
+        .update_offer(with_auth(update.clone(), "buyer-token")?)
+        .await
+        .expect_err("buyer must not update offers");
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    let v1 = admin
+        .update_offer(with_auth(update, "admin-token")?)
+        .await?
+        .into_inner();
+    assert_eq!(v1.version, 1);
+    assert!(!v1.paused);
+    assert_ne!(v1.offer_id, stale_quote.offer_id);
+
+    let err = svc
+        .create_swap(with_auth(
+            pb::CreateSwapRequest {
+                quote_id: stale_quote.quote_id,
+            },
+            "seller-token",
+        )?)
+        .await
+        .expect_err("quote of a replaced offer must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(err.message().contains("offer changed"), "{}", err.message());
+
+    let quote = svc
+        .create_quote(with_auth(quote_request(asset_id, 100), "seller-token")?)
+        .await?
+        .into_inner();
+    assert_eq!(quote.total_price_msat, 200_000);
+    assert_eq!(quote.offer_id, v1.offer_id);
+
+    let v2 = admin
+        .pause_offer(with_auth(
+            pb::PauseOfferRequest {
+                asset_id: asset_id.to_string(),
+            },
+            "admin-token",
+        )?)
+        .await?
+        .into_inner();
+    assert_eq!(v2.version, 2);
+    assert!(v2.paused);
+    assert_eq!(v2.offer_id, v1.offer_id);
+
+    let err = svc
+        .create_quote(with_auth(quote_request(asset_id, 100), "seller-token")?)
+        .await
+        .expect_err("paused offer must not quote");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(err.message().contains("offer paused"), "{}", err.message());
+    let offers = svc
+        .list_offers(with_auth(pb::ListOffersRequest {}, "buyer-token")?)
+        .await?
+        .into_inner()
+        .offers;
+    assert!(offers.is_empty());
+
+    let v3 = admin
+        .resume_offer(with_auth(
+            pb::ResumeOfferRequest {
+                asset_id: asset_id.to_string(),
+            },
+            "admin-token",
+        )?)
+        .await?
+        .into_inner();
+    assert_eq!(v3.version, 3);
+    assert!(!v3.paused);
+    svc.create_quote(with_auth(quote_request(asset_id, 100), "seller-token")?)
+        .await?;
+
+    let versions = admin
+        .list_offer_versions(with_auth(
+            pb::ListOfferVersionsRequest {
+                asset_id: asset_id.to_string(),
+            },
+            "admin-token",
+        )?)
+        .await?
+        .into_inner()
+        .versions;
+    assert_eq!(
+        versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+        [3, 2, 1]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::admin_service_server::AdminService as _;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::admin::AdminServiceImpl;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{HtlcVersion, SwapDirection, SwapRecord, SwapStatus};
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+fn sample_swap(swap_id: &str, owner: &str) -> SwapRecord {
+    SwapRecord {
+        swap_id: swap_id.to_string(),
+        quote_id: format!("quote:{swap_id}"),
+        direction: SwapDirection::LnToLiquid,
+        bolt11_invoice: format!("invoice:{swap_id}"),
+        payment_hash: "00".repeat(32),
+        asset_id: "01".repeat(32),
+        asset_amount: 100,
+        total_price_msat: 100_000,
+        buyer_liquid_address: format!("buyer_liquid_address:{swap_id}"),
+        fee_subsidy_sats: 2_000,
+        refund_lock_height: 200,
+        p2wsh_address: format!("p2wsh:{swap_id}"),
+        witness_script_hex: "00".to_string(),
+        funding_txid: "11".repeat(32),
+        asset_vout: 0,
+        lbtc_vout: 1,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Created,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: owner.to_string(),
+        created_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn api_keys_scope_swaps_to_their_owner() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let asset_id = AssetId::from_str(&"01".repeat(32))?;
+    let store = Arc::new(Mutex::new(SqliteStore::open(
+        dir.path().join("swap_store.sqlite3"),
+    )?));
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid],
+                price_msat_per_asset_unit: 1_000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 1,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: String::new(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+    let admin = AdminServiceImpl::new(svc.clone(), store.clone(), "admin-token".to_string());
+
+    let create_key = |role: pb::SwapRole, owner: &str| {
+        with_auth(
+            pb::CreateApiKeyRequest {
+                role: role as i32,
+                owner: owner.to_string(),
+            },
+            "admin-token",
+        )
+    };
+    let err = admin
+        .create_api_key(with_auth(
+            pb::CreateApiKeyRequest {
+                role: pb::SwapRole::Buyer as i32,
+                owner: "tenant-a".to_string(),
+            },
+            "seller-token",
+        )?)
+        .await
+        .expect_err("only the admin issues keys");
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+    let err = admin
+        .create_api_key(create_key(pb::SwapRole::Unspecified, "tenant-a")?)
+        .await
+        .expect_err("role is required");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    for owner in ["buyer", "seller"] {
+        let err = admin
+            .create_api_key(create_key(pb::SwapRole::Buyer, owner)?)
+            .await
+            .expect_err("static token owners are reserved");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    let tenant_a = admin
+        .create_api_key(create_key(pb::SwapRole::Buyer, "tenant-a")?)
+        .await?
+        .into_inner();
+    let tenant_b = admin
+        .create_api_key(create_key(pb::SwapRole::Buyer, "tenant-b")?)
+        .await?
+        .into_inner();
+    let ops = admin
+        .create_api_key(create_key(pb::SwapRole::Seller, "ops")?)
+        .await?
+        .into_inner();
+    let key_a = tenant_a.key.context("key missing")?;
+    assert!(tenant_a.token.starts_with(&format!("{}.", key_a.key_id)));
+    assert_eq!(key_a.owner, "tenant-a");
+
+    store
+        .lock()
+        .expect("store mutex poisoned")
+        .insert_swap(&sample_swap("swap-a", "tenant-a"))?;
+    let get_swap = |token: &str| {
+        with_auth(
+            pb::GetSwapRequest {
+                swap_id: "swap-a".to_string(),
+            },
+            token,
+        )
+    };
+
+    assert_eq!(
+        svc.get_swap(get_swap(&tenant_a.token)?)
+            .await?
+            .into_inner()
+            .swap_id,
+        "swap-a"
+    );
+    svc.get_swap(get_swap(&ops.token)?).await?;
+    svc.get_swap(get_swap("seller-token")?).await?;
+
+    let err = svc
+        .get_swap(get_swap(&tenant_b.token)?)
+        .await
+        .expect_err("other buyers must not see the swap");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let err = svc
+        .create_lightning_payment(with_auth(
+            pb::CreateLightningPaymentRequest {
+                swap_id: "swap-a".to_string(),
+                ..Default::default()
+            },
+            &tenant_b.token,
+        )?)
+        .await
+        .expect_err("other buyers must not pay for the swap");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let err = svc
+        .create_asset_claim(with_auth(
+            pb::CreateAssetClaimRequest {
+                swap_id: "swap-a".to_string(),
+                ..Default::default()
+            },
+            &tenant_b.token,
+        )?)
+        .await
+        .expect_err("other buyers must not claim the swap");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    // The owner gets past authorization to the swap's own checks.
+    let err = svc
+        .create_lightning_payment(with_auth(
+            pb::CreateLightningPaymentRequest {
+                swap_id: "swap-a".to_string(),
+                ..Default::default()
+            },
+            &tenant_a.token,
+        )?)
+        .await
+        .expect_err("swap is not funded");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+    let forged = format!("{}.{}", key_a.key_id, "00".repeat(32));
+    let err = svc
+        .get_swap(get_swap(&forged)?)
+        .await
+        .expect_err("wrong secret must be rejected");
+    assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+    let revoked = admin
+        .revoke_api_key(with_auth(
+            pb::RevokeApiKeyRequest {
+                key_id: key_a.key_id.clone(),
+            },
+            "admin-token",
+        )?)
+        .await?
+        .into_inner();
+    assert!(revoked.revoked_at > 0);
+    let err = svc
+        .get_swap(get_swap(&tenant_a.token)?)
+        .await
+        .expect_err("revoked key must be rejected");
+    assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+    let keys = admin
+        .list_api_keys(with_auth(pb::ListApiKeysRequest {}, "admin-token")?)
+        .await?
+        .into_inner()
+        .keys;
+    assert_eq!(keys.len(), 3);
+    assert_eq!(
+        keys.iter()
+            .filter(|k| k.revoked_at > 0)
+            .map(|k| k.key_id.as_str())
+            .collect::<Vec<_>>(),
+        vec![key_a.key_id.as_str()]
+    );
+
+    Ok(())
+}
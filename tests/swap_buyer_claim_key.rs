@@ -0,0 +1,212 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::bitcoin::PublicKey;
+use lwk_wollet::elements::bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, Transaction, TxOut, TxOutWitness,
+};
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::chain::LiquidChain as _;
+use ln_liquid_swap::liquid::htlc::{
+    HtlcFunding, HtlcSpec, claim_tx_from_witness_script, pubkey_hash160,
+    pubkey_hash160_from_p2wpkh_address, sha256_preimage,
+};
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1::CreateAssetClaimRequest;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus};
+
+fn explicit_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn buyer_held_claim_key_is_claimed_locally() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let seller_address = wallet.address_at(0)?;
+    let policy_asset = wallet.policy_asset();
+    let asset_id =
+        AssetId::from_str("0101010101010101010101010101010101010101010101010101010101010101")?;
+
+    let secp = Secp256k1::new();
+    let buyer_secret_key = SecretKey::from_slice(&[7u8; 32]).context("buyer secret key")?;
+    let buyer_pubkey = buyer_secret_key.public_key(&secp);
+    let buyer_address = Address::p2wpkh(
+        &PublicKey::new(buyer_pubkey),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+
+    let preimage = [5u8; 32];
+    let spec = HtlcSpec {
+        payment_hash: sha256_preimage(&preimage),
+        claimer_pubkey_hash160: pubkey_hash160(&buyer_pubkey.serialize()),
+        refunder_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&seller_address)?,
+        refund_lock_height: 200,
+    };
+    let witness_script = spec.witness_script();
+    let htlc_address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+
+    let funding_tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            explicit_output(&htlc_address, asset_id, 5_000),
+            explicit_output(&htlc_address, policy_asset, 2_000),
+        ],
+    };
+    let funding = HtlcFunding {
+        funding_txid: chain.broadcast(&funding_tx)?,
+        asset_vout: 0,
+        lbtc_vout: 1,
+        asset_id,
+        asset_amount: 5_000,
+        policy_asset,
+        fee_subsidy_sats: 2_000,
+        blinding: None,
+    };
+    chain.mine_blocks(1);
+
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    store.insert_quote(&QuoteRecord {
+        quote_id: "quote-a".to_string(),
+        offer_id: "offer-a".to_string(),
+        direction: SwapDirection::LnToLiquid,
+        asset_id: asset_id.to_string(),
+        asset_amount: funding.asset_amount,
+        min_funding_confs: 1,
+        total_price_msat: 5_000_000,
+        price_msat_per_asset_unit: 1000,
+        fee_subsidy_sats: funding.fee_subsidy_sats,
+        refund_delta_blocks: 100,
+        invoice_expiry_secs: 3600,
+        max_min_funding_confs: 6,
+        supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+        min_asset_amount: 0,
+        max_asset_amount: 0,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        price_source: "offer".to_string(),
+        source_price_msat_per_asset_unit: 1_000,
+        spread_bps: 0,
+        priced_at: 0,
+        swap_id: Some("swap-a".to_string()),
+        buyer_id: Some("buyer".to_string()),
+        created_at: 0,
+        expires_at: 0,
+    })?;
+    store.insert_swap(&SwapRecord {
+        swap_id: "swap-a".to_string(),
+        quote_id: "quote-a".to_string(),
+        direction: SwapDirection::LnToLiquid,
+        bolt11_invoice: "invoice:swap-a".to_string(),
+        payment_hash: hex::encode(spec.payment_hash),
+        asset_id: asset_id.to_string(),
+        asset_amount: funding.asset_amount,
+        total_price_msat: 5_000_000,
+        buyer_liquid_address: buyer_address.to_string(),
+        fee_subsidy_sats: funding.fee_subsidy_sats,
+        refund_lock_height: spec.refund_lock_height,
+        p2wsh_address: htlc_address.to_string(),
+        witness_script_hex: hex::encode(witness_script.to_bytes()),
+        funding_txid: funding.funding_txid.to_string(),
+        asset_vout: funding.asset_vout,
+        lbtc_vout: funding.lbtc_vout,
+        min_funding_confs: 1,
+        ln_payment_id: Some("payment-a".to_string()),
+        ln_preimage_hex: Some(hex::encode(preimage)),
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Paid,
+        buyer_claim_pubkey_hex: Some(hex::encode(buyer_pubkey.serialize())),
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    })?;
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id: asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+                price_msat_per_asset_unit: 1000,
+                fee_subsidy_sats: funding.fee_subsidy_sats,
+                refund_delta_blocks: 100,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        Arc::new(Mutex::new(store)),
+    );
+
+    let mut request = Request::new(CreateAssetClaimRequest {
+        swap_id: "swap-a".to_string(),
+        claim_fee_sats: 500,
+    });
+    request.metadata_mut().insert(
+        "authorization",
+        MetadataValue::try_from("Bearer buyer-token")?,
+    );
+    let status = svc.create_asset_claim(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    assert!(chain.mempool_txids().is_empty());
+
+    let claim_tx = claim_tx_from_witness_script(
+        &witness_script,
+        &funding,
+        &buyer_address,
+        &buyer_secret_key,
+        preimage,
+        500,
+    )
+    .context("build local claim tx")?;
+    let claim_txid = chain.broadcast(&claim_tx).context("broadcast claim")?;
+    assert_eq!(chain.mempool_txids(), vec![claim_txid]);
+
+    Ok(())
+}
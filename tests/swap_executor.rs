@@ -0,0 +1,297 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::bitcoin::PublicKey;
+use lwk_wollet::elements::bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::encode::serialize_hex;
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, Transaction, TxOut, TxOutWitness,
+};
+
+use ln_liquid_swap::lightning::backend::PaymentState;
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::chain::LiquidChain as _;
+use ln_liquid_swap::liquid::htlc::{
+    HtlcFunding, HtlcSpec, claim_tx_from_witness_script, pubkey_hash160_from_p2wpkh_address,
+    sha256_preimage,
+};
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{
+    HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus, SwapTxKind,
+};
+
+fn explicit_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+struct Htlc {
+    spec: HtlcSpec,
+    funding: HtlcFunding,
+    funding_tx: Transaction,
+    address: Address,
+}
+
+fn fund_htlc(
+    chain: &SimulatedLiquidChain,
+    spec: HtlcSpec,
+    asset_id: AssetId,
+    policy_asset: AssetId,
+) -> Result<Htlc> {
+    let address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+    let funding_tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            explicit_output(&address, asset_id, 5_000),
+            explicit_output(&address, policy_asset, 2_000),
+        ],
+    };
+    let funding = HtlcFunding {
+        funding_txid: chain.broadcast(&funding_tx)?,
+        asset_vout: 0,
+        lbtc_vout: 1,
+        asset_id,
+        asset_amount: 5_000,
+        policy_asset,
+        fee_subsidy_sats: 2_000,
+        blinding: None,
+    };
+    Ok(Htlc {
+        spec,
+        funding,
+        funding_tx,
+        address,
+    })
+}
+
+fn swap_record(swap_id: &str, status: SwapStatus, htlc: &Htlc) -> SwapRecord {
+    SwapRecord {
+        swap_id: swap_id.to_string(),
+        quote_id: format!("quote:{swap_id}"),
+        direction: SwapDirection::LiquidToLn,
+        bolt11_invoice: format!("invoice:{swap_id}"),
+        payment_hash: hex::encode(htlc.spec.payment_hash),
+        asset_id: htlc.funding.asset_id.to_string(),
+        asset_amount: htlc.funding.asset_amount,
+        total_price_msat: 5_000_000,
+        buyer_liquid_address: format!("buyer_liquid_address:{swap_id}"),
+        fee_subsidy_sats: htlc.funding.fee_subsidy_sats,
+        refund_lock_height: htlc.spec.refund_lock_height,
+        p2wsh_address: htlc.address.to_string(),
+        witness_script_hex: hex::encode(htlc.spec.witness_script().to_bytes()),
+        funding_txid: htlc.funding.funding_txid.to_string(),
+        asset_vout: htlc.funding.asset_vout,
+        lbtc_vout: htlc.funding.lbtc_vout,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    }
+}
+
+fn quote_record(record: &SwapRecord) -> QuoteRecord {
+    QuoteRecord {
+        quote_id: record.quote_id.clone(),
+        offer_id: format!("offer:{}", record.swap_id),
+        direction: record.direction,
+        asset_id: record.asset_id.clone(),
+        asset_amount: record.asset_amount,
+        min_funding_confs: 1,
+        total_price_msat: record.total_price_msat,
+        price_msat_per_asset_unit: 1000,
+        fee_subsidy_sats: record.fee_subsidy_sats,
+        refund_delta_blocks: 100,
+        invoice_expiry_secs: 3600,
+        max_min_funding_confs: 6,
+        supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+        min_asset_amount: 0,
+        max_asset_amount: 0,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        price_source: "offer".to_string(),
+        source_price_msat_per_asset_unit: 1_000,
+        spread_bps: 0,
+        priced_at: 0,
+        swap_id: Some(record.swap_id.clone()),
+        buyer_id: Some("buyer".to_string()),
+        created_at: 0,
+        expires_at: 0,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn executor_resumes_funding_payments_and_claims() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let policy_asset = wallet.policy_asset();
+    let asset_id =
+        AssetId::from_str("0101010101010101010101010101010101010101010101010101010101010101")?;
+
+    let secp = Secp256k1::new();
+    let claimer_secret_key = SecretKey::from_slice(&[3u8; 32]).context("claimer secret key")?;
+    let claimer_address = Address::p2wpkh(
+        &PublicKey::new(claimer_secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+    let spec = |preimage: [u8; 32]| -> Result<HtlcSpec> {
+        Ok(HtlcSpec {
+            payment_hash: sha256_preimage(&preimage),
+            claimer_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&claimer_address)?,
+            refunder_pubkey_hash160: [4u8; 20],
+            refund_lock_height: 500,
+        })
+    };
+
+    let created = fund_htlc(&chain, spec([5u8; 32])?, asset_id, policy_asset)?;
+    let paying = fund_htlc(&chain, spec([6u8; 32])?, asset_id, policy_asset)?;
+    let failing = fund_htlc(&chain, spec([7u8; 32])?, asset_id, policy_asset)?;
+    let claimed = fund_htlc(&chain, spec([8u8; 32])?, asset_id, policy_asset)?;
+    chain.evict(&created.funding.funding_txid);
+    chain.mine_blocks(1);
+
+    let claim_tx = claim_tx_from_witness_script(
+        &claimed.spec.witness_script(),
+        &claimed.funding,
+        &claimer_address,
+        &claimer_secret_key,
+        [8u8; 32],
+        500,
+    )?;
+
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    let mut records = vec![
+        swap_record("swap-created", SwapStatus::Created, &created),
+        swap_record("swap-paying", SwapStatus::Funded, &paying),
+        swap_record("swap-failing", SwapStatus::Funded, &failing),
+        swap_record("swap-claimed", SwapStatus::Claimed, &claimed),
+    ];
+    records[1].ln_payment_id = Some("payment-paying".to_string());
+    records[2].ln_payment_id = Some("payment-failing".to_string());
+    records[3].claim_txid = Some(claim_tx.txid().to_string());
+    for record in &records {
+        store.insert_quote(&quote_record(record))?;
+        store.insert_swap(record)?;
+    }
+    store.put_swap_tx(
+        "swap-created",
+        SwapTxKind::Funding,
+        &created.funding.funding_txid.to_string(),
+        &serialize_hex(&created.funding_tx),
+    )?;
+    store.put_swap_tx(
+        "swap-claimed",
+        SwapTxKind::Claim,
+        &claim_tx.txid().to_string(),
+        &serialize_hex(&claim_tx),
+    )?;
+    let store = Arc::new(Mutex::new(store));
+
+    let ln = FakeLightningNode::new();
+    ln.set_payment_state("payment-paying", PaymentState::Succeeded([6u8; 32]));
+    ln.set_payment_state(
+        "payment-failing",
+        PaymentState::Failed("no route".to_string()),
+    );
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id: asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+                price_msat_per_asset_unit: 1000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(ln),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+
+    assert_eq!(svc.drive_swaps().await?, 1);
+    let mempool = chain.mempool_txids();
+    assert!(mempool.contains(&created.funding.funding_txid));
+    assert!(mempool.contains(&claim_tx.txid()));
+
+    chain.mine_blocks(1);
+    assert_eq!(svc.drive_swaps().await?, 1);
+    assert_eq!(svc.drive_swaps().await?, 0);
+
+    let store = store.lock().expect("store mutex poisoned");
+    let swap = store.get_swap("swap-created")?.context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::Funded);
+    assert_eq!(
+        store.get_swap_tx("swap-created", SwapTxKind::Funding)?,
+        None
+    );
+    let transitions = store.list_swap_transitions("swap-created")?;
+    assert_eq!(
+        transitions
+            .iter()
+            .map(|t| (t.from_status, t.to_status))
+            .collect::<Vec<_>>(),
+        vec![
+            (None, SwapStatus::Created),
+            (Some(SwapStatus::Created), SwapStatus::Funded),
+        ]
+    );
+
+    let swap = store.get_swap("swap-paying")?.context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::Paid);
+    assert_eq!(swap.ln_preimage_hex, Some(hex::encode([6u8; 32])));
+
+    let swap = store.get_swap("swap-failing")?.context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::Funded);
+    assert_eq!(swap.ln_payment_id, None);
+
+    assert_eq!(store.get_swap_tx("swap-claimed", SwapTxKind::Claim)?, None);
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::SwapDirection;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{LEGACY_BUYER_OWNER, SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+#[tokio::test]
+async fn open_notional_is_capped_per_buyer_and_globally() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let buyer_address = wallet.address_at(1)?;
+    let asset_id = AssetId::from_str(&"01".repeat(32))?;
+    let store = Arc::new(Mutex::new(SqliteStore::open(
+        dir.path().join("swap_store.sqlite3"),
+    )?));
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid],
+                price_msat_per_asset_unit: 1_000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 1,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 250_000,
+            max_buyer_open_notional_msat: 150_000,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+
+    let offers = svc
+        .list_offers(with_auth(pb::ListOffersRequest {}, "buyer-token")?)
+        .await?
+        .into_inner()
+        .offers;
+    assert_eq!(offers[0].max_open_notional_msat, 250_000);
+    assert_eq!(offers[0].max_buyer_open_notional_msat, 150_000);
+
+    let quote = |asset_amount: u64| -> Result<Request<pb::CreateQuoteRequest>> {
+        with_auth(
+            pb::CreateQuoteRequest {
+                direction: pb::SwapDirection::LnToLiquid as i32,
+                asset_id: asset_id.to_string(),
+                asset_amount,
+                min_funding_confs: 1,
+            },
+            "seller-token",
+        )
+    };
+    let err = svc
+        .create_quote(quote(151)?)
+        .await
+        .expect_err("quote above the buyer cap must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        err.message().contains("open notional cap"),
+        "{}",
+        err.message()
+    );
+
+    let mut quote_ids = Vec::new();
+    for _ in 0..4 {
+        quote_ids.push(svc.create_quote(quote(100)?).await?.into_inner().quote_id);
+    }
+    let create_swap = |quote_id: &str| -> Result<Request<pb::CreateSwapRequest>> {
+        with_auth(
+            pb::CreateSwapRequest {
+                quote_id: quote_id.to_string(),
+                buyer_liquid_address: buyer_address.to_string(),
+                ..Default::default()
+            },
+            "buyer-token",
+        )
+    };
+
+    assert!(store.lock().expect("store mutex poisoned").reserve_quote(
+        &quote_ids[0],
+        "swap-0",
+        LEGACY_BUYER_OWNER
+    )?);
+    let err = svc
+        .create_swap(create_swap(&quote_ids[3])?)
+        .await
+        .expect_err("buyer cap must be enforced");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        err.message().contains("buyer open notional cap exceeded"),
+        "{}",
+        err.message()
+    );
+
+    {
+        let mut store = store.lock().expect("store mutex poisoned");
+        store.release_quote(&quote_ids[0], "swap-0")?;
+        assert!(store.reserve_quote(&quote_ids[1], "swap-1", "other-buyer")?);
+        assert!(store.reserve_quote(&quote_ids[2], "swap-2", "other-buyer")?);
+    }
+    let err = svc
+        .create_swap(create_swap(&quote_ids[3])?)
+        .await
+        .expect_err("global cap must be enforced");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        err.message()
+            .starts_with("open notional cap exceeded: 200000 msat open"),
+        "{}",
+        err.message()
+    );
+
+    Ok(())
+}
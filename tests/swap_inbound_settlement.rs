@@ -0,0 +1,211 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+
+use ln_liquid_swap::lightning::backend::LightningBackend as _;
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::lightning::invoice::payment_hash_from_bolt11;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus};
+
+fn sample_quote(quote_id: &str, swap_id: &str, direction: SwapDirection) -> QuoteRecord {
+    QuoteRecord {
+        quote_id: quote_id.to_string(),
+        offer_id: format!("offer_id:{quote_id}"),
+        direction,
+        asset_id: format!("asset_id:{quote_id}"),
+        asset_amount: 1000,
+        min_funding_confs: 1,
+        total_price_msat: 1_000_000,
+        price_msat_per_asset_unit: 1000,
+        fee_subsidy_sats: 10_000,
+        refund_delta_blocks: 144,
+        invoice_expiry_secs: 3600,
+        max_min_funding_confs: 6,
+        supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+        min_asset_amount: 0,
+        max_asset_amount: 0,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        price_source: "offer".to_string(),
+        source_price_msat_per_asset_unit: 1_000,
+        spread_bps: 0,
+        priced_at: 0,
+        swap_id: Some(swap_id.to_string()),
+        buyer_id: Some("buyer".to_string()),
+        created_at: 0,
+        expires_at: 0,
+    }
+}
+
+fn sample_swap(
+    swap_id: &str,
+    quote_id: &str,
+    direction: SwapDirection,
+    bolt11_invoice: &str,
+    status: SwapStatus,
+) -> Result<SwapRecord> {
+    let payment_hash = payment_hash_from_bolt11(bolt11_invoice).context("parse invoice")?;
+    Ok(SwapRecord {
+        swap_id: swap_id.to_string(),
+        quote_id: quote_id.to_string(),
+        direction,
+        bolt11_invoice: bolt11_invoice.to_string(),
+        payment_hash: hex::encode(payment_hash),
+        asset_id: format!("asset_id:{swap_id}"),
+        asset_amount: 1000,
+        total_price_msat: 1_000_000,
+        buyer_liquid_address: format!("buyer_liquid_address:{swap_id}"),
+        fee_subsidy_sats: 10_000,
+        refund_lock_height: 123,
+        p2wsh_address: format!("p2wsh:{swap_id}"),
+        witness_script_hex: "00".to_string(),
+        funding_txid: format!("funding_txid:{swap_id}"),
+        asset_vout: 0,
+        lbtc_vout: 1,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    })
+}
+
+#[tokio::test]
+async fn inbound_settlement_marks_ln_to_liquid_swap_paid() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+
+    let seller_ln = FakeLightningNode::new();
+    let ln_to_liquid_invoice = seller_ln
+        .create_invoice(1_000_000, "swap:swap-a".to_string(), 3600)
+        .await?;
+    let liquid_to_ln_invoice = seller_ln
+        .create_invoice(1_000_000, "swap:swap-b".to_string(), 3600)
+        .await?;
+
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    // A swap whose payment hash does not decode must not hold up the others.
+    let broken = SwapRecord {
+        payment_hash: "not-hex".to_string(),
+        ..sample_swap(
+            "swap-broken",
+            "quote-broken",
+            SwapDirection::LnToLiquid,
+            &ln_to_liquid_invoice,
+            SwapStatus::Funded,
+        )?
+    };
+    store.insert_quote(&sample_quote(
+        "quote-broken",
+        "swap-broken",
+        SwapDirection::LnToLiquid,
+    ))?;
+    store.insert_swap(&broken)?;
+    for (quote_id, swap_id, direction, invoice) in [
+        (
+            "quote-a",
+            "swap-a",
+            SwapDirection::LnToLiquid,
+            &ln_to_liquid_invoice,
+        ),
+        (
+            "quote-b",
+            "swap-b",
+            SwapDirection::LiquidToLn,
+            &liquid_to_ln_invoice,
+        ),
+    ] {
+        store.insert_quote(&sample_quote(quote_id, swap_id, direction))?;
+        store.insert_swap(&sample_swap(
+            swap_id,
+            quote_id,
+            direction,
+            invoice,
+            SwapStatus::Funded,
+        )?)?;
+    }
+    let store = Arc::new(Mutex::new(store));
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id: AssetId::from_str(
+                    "0101010101010101010101010101010101010101010101010101010101010101",
+                )?,
+                supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+                price_msat_per_asset_unit: 1000,
+                fee_subsidy_sats: 10_000,
+                refund_delta_blocks: 144,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(seller_ln.clone()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+
+    assert_eq!(svc.sync_inbound_settlements().await?, 0);
+
+    let ln_to_liquid_hash = payment_hash_from_bolt11(&ln_to_liquid_invoice)?;
+    let liquid_to_ln_hash = payment_hash_from_bolt11(&liquid_to_ln_invoice)?;
+    let settlement = seller_ln.receive_payment(&ln_to_liquid_hash)?;
+    seller_ln.receive_payment(&liquid_to_ln_hash)?;
+
+    assert_eq!(svc.sync_inbound_settlements().await?, 1);
+    assert_eq!(svc.sync_inbound_settlements().await?, 0);
+
+    let store = store.lock().expect("store mutex poisoned");
+    let paid = store.get_swap("swap-a")?.context("swap-a missing")?;
+    assert_eq!(paid.status, SwapStatus::Paid);
+    assert_eq!(
+        paid.ln_payment_id.as_deref(),
+        Some(settlement.payment_id.as_str())
+    );
+    assert_eq!(paid.ln_preimage_hex, Some(hex::encode(settlement.preimage)));
+
+    let untouched = store.get_swap("swap-b")?.context("swap-b missing")?;
+    assert_eq!(untouched.status, SwapStatus::Funded);
+    assert_eq!(untouched.ln_preimage_hex, None);
+
+    let broken = store
+        .get_swap("swap-broken")?
+        .context("swap-broken missing")?;
+    assert_eq!(broken.status, SwapStatus::Funded);
+
+    Ok(())
+}
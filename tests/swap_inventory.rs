@@ -0,0 +1,256 @@
+mod support {
+    #[allow(dead_code)]
+    pub mod lwk_env;
+    #[allow(dead_code)]
+    pub mod lwk_wallet;
+}
+
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::SwapDirection;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+
+use support::lwk_env::LiquidRegtestEnv;
+use support::lwk_wallet::LwkWalletFixture;
+
+const ISSUER_MNEMONIC: &str = lwk_test_util::TEST_MNEMONIC;
+const ISSUER_SLIP77: &str = lwk_test_util::TEST_MNEMONIC_SLIP77;
+
+const SELLER_MNEMONIC: &str =
+    "legal winner thank year wave sausage worth useful legal winner thank yellow";
+const SELLER_SLIP77: &str = "0000000000000000000000000000000000000000000000000000000000000002";
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+#[tokio::test]
+async fn create_quote_reserves_inventory() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let policy_asset = wallet.policy_asset();
+    let asset_id = AssetId::from_str(&"01".repeat(32))?;
+    let store = Arc::new(Mutex::new(SqliteStore::open(
+        dir.path().join("swap_store.sqlite3"),
+    )?));
+
+    let cfg = SwapServiceConfig {
+        offers: OfferBook::new([AssetOffer {
+            asset_id,
+            supported_directions: vec![SwapDirection::LnToLiquid],
+            price_msat_per_asset_unit: 1_000,
+            fee_subsidy_sats: 2_000,
+            refund_delta_blocks: 100,
+            min_asset_amount: 1,
+            max_asset_amount: 0,
+        }])?,
+        invoice_expiry_secs: 3600,
+        min_timelock_margin_blocks: 0,
+        payment_safety_blocks: 0,
+        quote_ttl_secs: 600,
+        pricing: PricingPolicy::default(),
+        check_inventory: true,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        seller_key_index: 0,
+        buyer_key_index: 1,
+        seller_token: "seller-token".to_string(),
+        buyer_token: "buyer-token".to_string(),
+    };
+    let wallet = Arc::new(Mutex::new(wallet));
+    let request = || {
+        with_auth(
+            pb::CreateQuoteRequest {
+                direction: pb::SwapDirection::LnToLiquid as i32,
+                asset_id: asset_id.to_string(),
+                asset_amount: 100,
+                min_funding_confs: 1,
+            },
+            "seller-token",
+        )
+    };
+
+    // The simulated wallet holds nothing, so there is nothing to sell.
+    let svc = SwapServiceImpl::new(
+        cfg.clone(),
+        Arc::new(FakeLightningNode::new()),
+        wallet.clone(),
+        store.clone(),
+    );
+    let err = svc
+        .create_quote(request()?)
+        .await
+        .expect_err("empty wallet must not quote");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        err.message().contains("insufficient inventory"),
+        "{}",
+        err.message()
+    );
+    assert!(
+        store
+            .lock()
+            .expect("store mutex poisoned")
+            .reserved_inventory(0)?
+            .is_empty()
+    );
+
+    // Without the balance check, quotes still record what they would take from the wallet.
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            check_inventory: false,
+            ..cfg
+        },
+        Arc::new(FakeLightningNode::new()),
+        wallet,
+        store.clone(),
+    );
+    let quote = svc.create_quote(request()?).await?.into_inner();
+    let reserved = store
+        .lock()
+        .expect("store mutex poisoned")
+        .reserved_inventory(quote.created_at)?;
+    assert_eq!(reserved.get(&asset_id.to_string()), Some(&100));
+    assert_eq!(reserved.get(&policy_asset.to_string()), Some(&2_000));
+    assert!(
+        store
+            .lock()
+            .expect("store mutex poisoned")
+            .reserved_inventory(quote.expires_at)?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[ignore = "requires `elementsd` and liquid-enabled `electrs` binaries (run via `nix develop`)"]
+async fn funded_swap_leaves_the_rest_of_the_inventory_quotable() -> Result<()> {
+    let env = LiquidRegtestEnv::start().context("start liquid regtest env")?;
+    let electrum_url = env.electrum_url();
+
+    let mut issuer = LwkWalletFixture::new("issuer", ISSUER_MNEMONIC, ISSUER_SLIP77, &electrum_url)
+        .context("create issuer wallet")?;
+    issuer.fund_lbtc(&env, 3_000_000).context("fund issuer")?;
+    let (_issuance_txid, asset_id, _token_id) =
+        issuer.issue_asset(&env, 50_000, 1).context("issue asset")?;
+
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let mut wallet = LiquidWallet::new(
+        SELLER_MNEMONIC,
+        SELLER_SLIP77,
+        &electrum_url,
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create seller wallet")?;
+    let seller_receive = wallet.address_at(0).context("get seller address")?;
+    env.elementsd_sendtoaddress(&seller_receive, 1_000_000, None);
+    env.elementsd_generate(1);
+    issuer
+        .send_asset(&env, &seller_receive, &asset_id, 10_000)
+        .context("send asset to seller")?;
+    wallet.sync().context("sync seller wallet")?;
+    assert_eq!(wallet.balance(&asset_id)?, 10_000);
+    let buyer_liquid_address = wallet.address_at(1).context("get buyer address")?;
+
+    let store = Arc::new(Mutex::new(SqliteStore::open(
+        dir.path().join("swap_store.sqlite3"),
+    )?));
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid],
+                price_msat_per_asset_unit: 1_000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 1,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: true,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+    let request = |asset_amount| {
+        with_auth(
+            pb::CreateQuoteRequest {
+                direction: pb::SwapDirection::LnToLiquid as i32,
+                asset_id: asset_id.to_string(),
+                asset_amount,
+                min_funding_confs: 1,
+            },
+            "seller-token",
+        )
+    };
+
+    let quote = svc.create_quote(request(6_000)?).await?.into_inner();
+    svc.create_swap(with_auth(
+        pb::CreateSwapRequest {
+            quote_id: quote.quote_id,
+            buyer_liquid_address: buyer_liquid_address.to_string(),
+            buyer_bolt11_invoice: String::new(),
+            buyer_claim_pubkey: Vec::new(),
+            htlc_version: pb::HtlcVersion::P2wsh as i32,
+            confidential: false,
+        },
+        "buyer-token",
+    )?)
+    .await?;
+    env.elementsd_generate(1);
+
+    // The funding tx took 6_000 out of the wallet, and its reservation went with it.
+    assert!(
+        store
+            .lock()
+            .expect("store mutex poisoned")
+            .reserved_inventory(0)?
+            .is_empty()
+    );
+    let err = svc
+        .create_quote(request(4_001)?)
+        .await
+        .expect_err("quote beyond the remaining inventory must fail");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    svc.create_quote(request(4_000)?).await?;
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::SwapDirection;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+fn asset(byte: u8) -> Result<AssetId> {
+    Ok(AssetId::from_str(&format!("{byte:02x}").repeat(32))?)
+}
+
+fn offer(asset_id: AssetId, price_msat_per_asset_unit: u64) -> AssetOffer {
+    AssetOffer {
+        asset_id,
+        supported_directions: vec![SwapDirection::LnToLiquid],
+        price_msat_per_asset_unit,
+        fee_subsidy_sats: 2_000,
+        refund_delta_blocks: 100,
+        min_asset_amount: 10,
+        max_asset_amount: 1_000,
+    }
+}
+
+fn quote_request(asset_id: AssetId, asset_amount: u64) -> pb::CreateQuoteRequest {
+    pb::CreateQuoteRequest {
+        direction: pb::SwapDirection::LnToLiquid as i32,
+        asset_id: asset_id.to_string(),
+        asset_amount,
+        min_funding_confs: 1,
+    }
+}
+
+#[test]
+fn offer_book_validates_and_loads_json() -> Result<()> {
+    let a = asset(1)?;
+    let b = asset(2)?;
+
+    let err = OfferBook::new([offer(a, 1), offer(a, 2)]).expect_err("duplicate asset");
+    assert!(err.to_string().contains("duplicate offer"));
+
+    let mut bad = offer(a, 1);
+    bad.min_asset_amount = 2_000;
+    let err = OfferBook::new([bad]).expect_err("min > max");
+    assert!(
+        err.to_string()
+            .contains("min_asset_amount > max_asset_amount")
+    );
+
+    let mut bad = offer(a, 1);
+    bad.supported_directions.clear();
+    assert!(OfferBook::new([bad]).is_err());
+    assert!(OfferBook::new(Vec::new()).is_err());
+
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let path = dir.path().join("offers.json");
+    std::fs::write(
+        &path,
+        format!(
+            r#"[
+  {{"asset_id": "{a}", "price_msat_per_asset_unit": 1000, "fee_subsidy_sats": 2000, "refund_delta_blocks": 100}},
+  {{"asset_id": "{b}", "supported_directions": ["liquid_to_ln"], "price_msat_per_asset_unit": 5,
+    "fee_subsidy_sats": 1000, "refund_delta_blocks": 50, "min_asset_amount": 1, "max_asset_amount": 9}}
+]"#
+        ),
+    )?;
+    let book = OfferBook::from_json_file(&path)?;
+    let got_a = book.get(&a).context("offer a")?;
+    assert!(got_a.supports(SwapDirection::LnToLiquid));
+    assert!(got_a.supports(SwapDirection::LiquidToLn));
+    assert_eq!(got_a.max_asset_amount, 0);
+    let got_b = book.get(&b).context("offer b")?;
+    assert_eq!(got_b.supported_directions, [SwapDirection::LiquidToLn]);
+    assert_eq!(got_b.max_asset_amount, 9);
+    assert_eq!(book.iter().count(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn quotes_use_the_offer_of_their_asset() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let (a, b) = (asset(1)?, asset(2)?);
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([offer(a, 1_000), offer(b, 7)])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        Arc::new(Mutex::new(SqliteStore::open(
+            dir.path().join("swap_store.sqlite3"),
+        )?)),
+    );
+
+    let offers = svc
+        .list_offers(with_auth(pb::ListOffersRequest {}, "buyer-token")?)
+        .await?
+        .into_inner()
+        .offers;
+    assert_eq!(offers.len(), 2);
+    assert!(offers.iter().all(|o| o.min_asset_amount == 10));
+
+    let quote_a = svc
+        .create_quote(with_auth(quote_request(a, 100), "seller-token")?)
+        .await?
+        .into_inner();
+    let quote_b = svc
+        .create_quote(with_auth(quote_request(b, 100), "seller-token")?)
+        .await?
+        .into_inner();
+    assert_eq!(quote_a.total_price_msat, 100_000);
+    assert_eq!(quote_b.total_price_msat, 700);
+    assert_ne!(quote_a.offer_id, quote_b.offer_id);
+    assert_eq!(quote_b.offer.context("offer")?.max_asset_amount, 1_000);
+
+    let stored = svc
+        .get_quote(with_auth(
+            pb::GetQuoteRequest {
+                quote_id: quote_b.quote_id.clone(),
+            },
+            "buyer-token",
+        )?)
+        .await?
+        .into_inner();
+    assert_eq!(stored.offer_id, quote_b.offer_id);
+    assert_eq!(stored.offer.context("offer")?.supported_directions.len(), 1);
+
+    for (request, message) in [
+        (quote_request(a, 9), "asset_amount must be >= 10"),
+        (quote_request(a, 1_001), "asset_amount must be <= 1000"),
+        (quote_request(asset(3)?, 100), "unsupported asset_id"),
+        (
+            pb::CreateQuoteRequest {
+                direction: pb::SwapDirection::LiquidToLn as i32,
+                ..quote_request(a, 100)
+            },
+            "unsupported direction",
+        ),
+    ] {
+        let err = svc
+            .create_quote(with_auth(request, "seller-token")?)
+            .await
+            .expect_err("quote must be rejected");
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+        assert!(err.message().contains(message), "{}", err.message());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, OutPoint, Transaction, TxIn, TxOut, TxOutWitness,
+    Txid,
+};
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::backend::LightningBackend as _;
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::lightning::invoice::payment_hash_from_bolt11;
+use ln_liquid_swap::liquid::chain::LiquidChain as _;
+use ln_liquid_swap::liquid::htlc::{HtlcSpec, pubkey_hash160_from_p2wpkh_address};
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{HtlcVersion, SwapDirection, SwapRecord, SwapStatus};
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+fn explicit_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+/// Funds an HTLC paying `invoice`'s hash and returns its swap, already marked funded.
+fn funded_swap(
+    chain: &SimulatedLiquidChain,
+    wallet: &LiquidWallet,
+    swap_id: &str,
+    invoice: String,
+    refund_lock_height: u32,
+) -> Result<SwapRecord> {
+    let asset_id = AssetId::from_str(&"01".repeat(32))?;
+    let spec = HtlcSpec {
+        payment_hash: payment_hash_from_bolt11(&invoice)?,
+        claimer_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&wallet.address_at(1)?)?,
+        refunder_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&wallet.address_at(0)?)?,
+        refund_lock_height,
+    };
+    let address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+    let funding_txid = chain.broadcast(&Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            explicit_output(&address, asset_id, 5_000),
+            explicit_output(&address, wallet.policy_asset(), 2_000),
+        ],
+    })?;
+    Ok(SwapRecord {
+        swap_id: swap_id.to_string(),
+        quote_id: format!("quote:{swap_id}"),
+        direction: SwapDirection::LnToLiquid,
+        payment_hash: hex::encode(spec.payment_hash),
+        bolt11_invoice: invoice,
+        asset_id: asset_id.to_string(),
+        asset_amount: 5_000,
+        total_price_msat: 5_000_000,
+        buyer_liquid_address: wallet.address_at(1)?.to_string(),
+        fee_subsidy_sats: 2_000,
+        refund_lock_height,
+        p2wsh_address: address.to_string(),
+        witness_script_hex: hex::encode(spec.witness_script().to_bytes()),
+        funding_txid: funding_txid.to_string(),
+        asset_vout: 0,
+        lbtc_vout: 1,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Funded,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn payment_requires_a_live_htlc_outside_the_safety_window() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+
+    let ln = FakeLightningNode::new();
+    let swap_a = funded_swap(
+        &chain,
+        &wallet,
+        "swap-a",
+        ln.create_invoice(5_000_000, "swap:swap-a".to_string(), 3600)
+            .await?,
+        200,
+    )?;
+    let swap_b = funded_swap(
+        &chain,
+        &wallet,
+        "swap-b",
+        ln.create_invoice(5_000_000, "swap:swap-b".to_string(), 3600)
+            .await?,
+        106,
+    )?;
+    let store = Arc::new(Mutex::new(SqliteStore::open(
+        dir.path().join("swap_store.sqlite3"),
+    )?));
+    for record in [&swap_a, &swap_b] {
+        store
+            .lock()
+            .expect("store mutex poisoned")
+            .insert_swap(record)?;
+    }
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id: AssetId::from_str(&swap_a.asset_id)?,
+                supported_directions: vec![SwapDirection::LnToLiquid],
+                price_msat_per_asset_unit: 1_000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 5,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(ln.clone()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+    let pay = |swap_id: &str| {
+        with_auth(
+            pb::CreateLightningPaymentRequest {
+                swap_id: swap_id.to_string(),
+                payment_timeout_secs: 1,
+            },
+            "buyer-token",
+        )
+    };
+    let rejected = async |swap_id: &str, message: &str| -> Result<()> {
+        let err = svc
+            .create_lightning_payment(pay(swap_id)?)
+            .await
+            .expect_err("payment must be refused");
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+        assert!(err.message().contains(message), "{}", err.message());
+        Ok(())
+    };
+
+    rejected("swap-a", "funding tx has 0 confirmations, need 1").await?;
+    chain.mine_blocks(1);
+
+    let funding_txid = Txid::from_str(&swap_a.funding_txid)?;
+    let spend = chain.broadcast(&Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(funding_txid, swap_a.lbtc_vout),
+            ..Default::default()
+        }],
+        output: vec![],
+    })?;
+    rejected("swap-a", &format!("already spent by {spend}")).await?;
+    assert!(chain.evict(&spend));
+
+    // Tip 101 plus the 5 block window reaches swap-b's refund height.
+    rejected("swap-b", "within 5 blocks of refund_lock_height 106").await?;
+    assert!(ln.paid_invoices().is_empty());
+
+    svc.create_lightning_payment(pay("swap-a")?).await?;
+    let record = store
+        .lock()
+        .expect("store mutex poisoned")
+        .get_swap("swap-a")?
+        .context("swap-a missing")?;
+    assert_eq!(record.status, SwapStatus::Paid);
+
+    Ok(())
+}
@@ -0,0 +1,313 @@
+use std::io::{Read as _, Write as _};
+use std::net::TcpListener;
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::SwapDirection;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::{
+    FilePriceSource, HttpPriceSource, PriceSource, PricingPolicy, SizeTier, StaticPriceSource,
+};
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+fn asset(byte: u8) -> Result<AssetId> {
+    Ok(AssetId::from_str(&format!("{byte:02x}").repeat(32))?)
+}
+
+fn quote_request(asset_id: AssetId, asset_amount: u64) -> pb::CreateQuoteRequest {
+    pb::CreateQuoteRequest {
+        direction: pb::SwapDirection::LnToLiquid as i32,
+        asset_id: asset_id.to_string(),
+        asset_amount,
+        min_funding_confs: 1,
+    }
+}
+
+fn service(
+    dir: &std::path::Path,
+    asset_id: AssetId,
+    pricing: PricingPolicy,
+) -> Result<SwapServiceImpl> {
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    Ok(SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid],
+                price_msat_per_asset_unit: 1_000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 1,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing,
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        Arc::new(Mutex::new(SqliteStore::open(
+            dir.join("swap_store.sqlite3"),
+        )?)),
+    ))
+}
+
+#[test]
+fn pricing_policy_applies_spread_and_size_tiers() -> Result<()> {
+    let policy = PricingPolicy {
+        spread_bps: 100,
+        size_tiers: vec!["1000:50".parse()?, "10000:10".parse()?],
+        ..Default::default()
+    };
+    policy.validate()?;
+
+    assert_eq!(policy.spread_bps_for(999), 100);
+    assert_eq!(policy.spread_bps_for(1_000), 50);
+    assert_eq!(policy.spread_bps_for(50_000), 10);
+    assert_eq!(policy.total_price_msat(1_000, 100), Some(101_000));
+    assert_eq!(policy.total_price_msat(1_000, 1_000), Some(1_005_000));
+    // Rounds up to the next msat.
+    assert_eq!(policy.total_price_msat(1, 1), Some(2));
+    assert_eq!(policy.total_price_msat(u64::MAX, 2), None);
+    assert_eq!(PricingPolicy::default().total_price_msat(7, 3), Some(21));
+
+    assert!("1000".parse::<SizeTier>().is_err());
+    let duplicate = PricingPolicy {
+        size_tiers: vec!["5:1".parse()?, "5:2".parse()?],
+        ..Default::default()
+    };
+    assert!(duplicate.validate().is_err());
+    let inverted = PricingPolicy {
+        min_notional_msat: 10,
+        max_notional_msat: 5,
+        ..Default::default()
+    };
+    assert!(inverted.validate().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn file_price_source_reloads_on_change() -> Result<()> {
+    let a = asset(1)?;
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let path = dir.path().join("prices.json");
+    std::fs::write(
+        &path,
+        format!(r#"{{"updated_at": 1700000000, "prices": {{"{a}": 1000}}}}"#),
+    )?;
+
+    let source = FilePriceSource::new(&path);
+    let price = source.price(&a)?.context("price")?;
+    assert_eq!(price.price_msat_per_asset_unit, 1_000);
+    assert_eq!(price.source, "file");
+    assert_eq!(price.observed_at, 1_700_000_000);
+    assert!(source.price(&asset(2)?)?.is_none());
+
+    std::fs::write(&path, format!(r#"{{"prices": {{"{a}": 1200}}}}"#))?;
+    let modified = SystemTime::now() + Duration::from_secs(10);
+    std::fs::File::options()
+        .write(true)
+        .open(&path)?
+        .set_modified(modified)?;
+    let price = source.price(&a)?.context("price")?;
+    assert_eq!(price.price_msat_per_asset_unit, 1_200);
+    assert!(price.observed_at > 1_700_000_000);
+
+    std::fs::write(&path, "not json")?;
+    std::fs::File::options()
+        .write(true)
+        .open(&path)?
+        .set_modified(modified + Duration::from_secs(10))?;
+    assert!(source.price(&a).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn http_price_source_fetches_json() -> Result<()> {
+    let a = asset(1)?;
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let body = format!(r#"{{"updated_at": 1700000000, "prices": {{"{a}": 2500}}}}"#);
+    let server = std::thread::spawn(move || -> Result<String> {
+        let (mut stream, _) = listener.accept()?;
+        let mut request = [0u8; 1024];
+        let n = stream.read(&mut request)?;
+        write!(
+            stream,
+            "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{body}"
+        )?;
+        Ok(String::from_utf8_lossy(&request[..n]).into_owned())
+    });
+
+    let source = HttpPriceSource::new(&format!("http://127.0.0.1:{port}/prices"))?;
+    let price = source.price(&a)?.context("price")?;
+    assert_eq!(price.price_msat_per_asset_unit, 2_500);
+    assert_eq!(price.source, "http");
+    assert_eq!(price.observed_at, 1_700_000_000);
+
+    let request = server.join().expect("oracle thread panicked")?;
+    assert!(request.starts_with("GET /prices HTTP/1.0\r\n"));
+
+    assert!(HttpPriceSource::new("https://oracle.example/prices").is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn quotes_use_the_price_source_and_policy() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let a = asset(1)?;
+    let svc = service(
+        dir.path(),
+        a,
+        PricingPolicy {
+            spread_bps: 200,
+            size_tiers: vec![SizeTier {
+                min_asset_amount: 500,
+                spread_bps: 100,
+            }],
+            min_notional_msat: 100_000,
+            max_notional_msat: 1_000_000,
+            max_price_age_secs: 0,
+        },
+    )?
+    .with_price_source(Arc::new(StaticPriceSource::new([(a, 1_500)])));
+
+    let quote = svc
+        .create_quote(with_auth(quote_request(a, 100), "seller-token")?)
+        .await?
+        .into_inner();
+    assert_eq!(quote.total_price_msat, 153_000);
+    assert_eq!(quote.price_source, "static");
+    assert_eq!(quote.source_price_msat_per_asset_unit, 1_500);
+    assert_eq!(quote.spread_bps, 200);
+    assert!(quote.priced_at > 0);
+    // The offer keeps its configured price; only the quote follows the source.
+    assert_eq!(
+        quote.offer.context("offer")?.price_msat_per_asset_unit,
+        1_000
+    );
+
+    let tiered = svc
+        .create_quote(with_auth(quote_request(a, 500), "seller-token")?)
+        .await?
+        .into_inner();
+    assert_eq!(tiered.total_price_msat, 757_500);
+    assert_eq!(tiered.spread_bps, 100);
+
+    let stored = svc
+        .get_quote(with_auth(
+            pb::GetQuoteRequest {
+                quote_id: tiered.quote_id.clone(),
+            },
+            "buyer-token",
+        )?)
+        .await?
+        .into_inner();
+    assert_eq!(stored.price_source, "static");
+    assert_eq!(stored.priced_at, tiered.priced_at);
+
+    for (amount, message) in [
+        (10, "total_price_msat must be >= 100000"),
+        (1_000, "total_price_msat must be <= 1000000"),
+    ] {
+        let err = svc
+            .create_quote(with_auth(quote_request(a, amount), "seller-token")?)
+            .await
+            .expect_err("notional bounds must be enforced");
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+        assert!(err.message().contains(message), "{}", err.message());
+    }
+
+    let svc = svc.with_price_source(Arc::new(StaticPriceSource::new([])));
+    let err = svc
+        .create_quote(with_auth(quote_request(a, 100), "seller-token")?)
+        .await
+        .expect_err("missing price must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_prices_are_rejected() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let a = asset(1)?;
+    let path = dir.path().join("prices.json");
+    std::fs::write(
+        &path,
+        format!(r#"{{"updated_at": 1700000000, "prices": {{"{a}": 1000}}}}"#),
+    )?;
+    let svc = service(
+        dir.path(),
+        a,
+        PricingPolicy {
+            max_price_age_secs: 60,
+            ..Default::default()
+        },
+    )?
+    .with_price_source(Arc::new(FilePriceSource::new(&path)));
+
+    let err = svc
+        .create_quote(with_auth(quote_request(a, 100), "seller-token")?)
+        .await
+        .expect_err("stale price must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        err.message().contains("older than 60s"),
+        "{}",
+        err.message()
+    );
+
+    std::fs::write(&path, format!(r#"{{"prices": {{"{a}": 1000}}}}"#))?;
+    std::fs::File::options()
+        .write(true)
+        .open(&path)?
+        .set_modified(SystemTime::now() + Duration::from_secs(5))?;
+    let quote = svc
+        .create_quote(with_auth(quote_request(a, 100), "seller-token")?)
+        .await?
+        .into_inner();
+    assert_eq!(quote.price_source, "file");
+    assert_eq!(quote.total_price_msat, 100_000);
+
+    Ok(())
+}
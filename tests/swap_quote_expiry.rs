@@ -0,0 +1,188 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus};
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+fn sample_swap(swap_id: &str, quote_id: &str, owner: &str) -> SwapRecord {
+    SwapRecord {
+        swap_id: swap_id.to_string(),
+        quote_id: quote_id.to_string(),
+        direction: SwapDirection::LnToLiquid,
+        bolt11_invoice: format!("invoice:{swap_id}"),
+        payment_hash: "00".repeat(32),
+        asset_id: "01".repeat(32),
+        asset_amount: 5,
+        total_price_msat: 5_000,
+        buyer_liquid_address: format!("buyer_liquid_address:{swap_id}"),
+        fee_subsidy_sats: 2_000,
+        refund_lock_height: 200,
+        p2wsh_address: format!("p2wsh:{swap_id}"),
+        witness_script_hex: "00".to_string(),
+        funding_txid: "11".repeat(32),
+        asset_vout: 0,
+        lbtc_vout: 1,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Created,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: owner.to_string(),
+        created_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn create_swap_rejects_expired_and_used_quotes() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let asset_id =
+        AssetId::from_str("0101010101010101010101010101010101010101010101010101010101010101")?;
+    let store = Arc::new(Mutex::new(SqliteStore::open(
+        dir.path().join("swap_store.sqlite3"),
+    )?));
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id: asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+                price_msat_per_asset_unit: 1000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+
+    let quote = svc
+        .create_quote(with_auth(
+            pb::CreateQuoteRequest {
+                direction: pb::SwapDirection::LnToLiquid as i32,
+                asset_id: asset_id.to_string(),
+                asset_amount: 5,
+                min_funding_confs: 1,
+            },
+            "seller-token",
+        )?)
+        .await?
+        .into_inner();
+    assert_eq!(quote.expires_at, quote.created_at + 600);
+
+    let create_swap = |quote_id: &str| -> Result<Request<pb::CreateSwapRequest>> {
+        with_auth(
+            pb::CreateSwapRequest {
+                quote_id: quote_id.to_string(),
+                buyer_liquid_address: "unused".to_string(),
+                ..Default::default()
+            },
+            "buyer-token",
+        )
+    };
+
+    let fresh = store
+        .lock()
+        .expect("store mutex poisoned")
+        .get_quote(&quote.quote_id)?
+        .context("quote missing")?;
+    let mut record = fresh.clone();
+
+    record.quote_id = "quote-expired".to_string();
+    record.expires_at = record.created_at;
+    store
+        .lock()
+        .expect("store mutex poisoned")
+        .insert_quote(&record)?;
+    let err = svc
+        .create_swap(create_swap("quote-expired")?)
+        .await
+        .expect_err("expired quote must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(err.message().contains("quote expired"));
+
+    assert!(store.lock().expect("store mutex poisoned").reserve_quote(
+        &quote.quote_id,
+        "swap-in-flight",
+        "buyer"
+    )?);
+    let err = svc
+        .create_swap(create_swap(&quote.quote_id)?)
+        .await
+        .expect_err("used quote must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(err.message().contains("quote already used"));
+
+    // A retry returns the buyer's own swap, but never a swap another buyer created from the quote.
+    for (quote_id, owner) in [("quote-mine", "buyer"), ("quote-taken", "other-buyer")] {
+        let swap = sample_swap(&format!("swap:{quote_id}"), quote_id, owner);
+        let mut store = store.lock().expect("store mutex poisoned");
+        store.insert_quote(&QuoteRecord {
+            quote_id: quote_id.to_string(),
+            ..fresh.clone()
+        })?;
+        assert!(store.reserve_quote(quote_id, &swap.swap_id, owner)?);
+        store.insert_swap(&swap)?;
+    }
+    let swap = svc
+        .create_swap(create_swap("quote-mine")?)
+        .await?
+        .into_inner();
+    assert_eq!(swap.swap_id, "swap:quote-mine");
+    let err = svc
+        .create_swap(create_swap("quote-taken")?)
+        .await
+        .expect_err("another buyer's quote must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(err.message().contains("quote already used"));
+
+    Ok(())
+}
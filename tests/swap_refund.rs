@@ -0,0 +1,399 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::bitcoin::PublicKey;
+use lwk_wollet::elements::bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::encode::deserialize;
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, Transaction, TxOut, TxOutWitness, Txid,
+};
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::chain::LiquidChain as _;
+use ln_liquid_swap::liquid::fee::{MIN_FEE_RATE_SAT_VB, tx_fee_rate};
+use ln_liquid_swap::liquid::htlc::{
+    HtlcFunding, HtlcSpec, claim_tx_from_witness_script, pubkey_hash160_from_p2wpkh_address,
+    sha256_preimage,
+};
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{
+    HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus, SwapTxKind,
+};
+
+fn explicit_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+struct Htlc {
+    spec: HtlcSpec,
+    funding: HtlcFunding,
+    address: Address,
+}
+
+fn fund_htlc(
+    chain: &SimulatedLiquidChain,
+    spec: HtlcSpec,
+    asset_id: AssetId,
+    policy_asset: AssetId,
+) -> Result<Htlc> {
+    let address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+    let funding_tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            explicit_output(&address, asset_id, 5_000),
+            explicit_output(&address, policy_asset, 2_000),
+        ],
+    };
+    let funding = HtlcFunding {
+        funding_txid: chain.broadcast(&funding_tx)?,
+        asset_vout: 0,
+        lbtc_vout: 1,
+        asset_id,
+        asset_amount: 5_000,
+        policy_asset,
+        fee_subsidy_sats: 2_000,
+        blinding: None,
+    };
+    Ok(Htlc {
+        spec,
+        funding,
+        address,
+    })
+}
+
+fn swap_record(swap_id: &str, htlc: &Htlc) -> SwapRecord {
+    SwapRecord {
+        swap_id: swap_id.to_string(),
+        quote_id: format!("quote:{swap_id}"),
+        direction: SwapDirection::LnToLiquid,
+        bolt11_invoice: format!("invoice:{swap_id}"),
+        payment_hash: hex::encode(htlc.spec.payment_hash),
+        asset_id: htlc.funding.asset_id.to_string(),
+        asset_amount: htlc.funding.asset_amount,
+        total_price_msat: 5_000_000,
+        buyer_liquid_address: format!("buyer_liquid_address:{swap_id}"),
+        fee_subsidy_sats: htlc.funding.fee_subsidy_sats,
+        refund_lock_height: htlc.spec.refund_lock_height,
+        p2wsh_address: htlc.address.to_string(),
+        witness_script_hex: hex::encode(htlc.spec.witness_script().to_bytes()),
+        funding_txid: htlc.funding.funding_txid.to_string(),
+        asset_vout: htlc.funding.asset_vout,
+        lbtc_vout: htlc.funding.lbtc_vout,
+        min_funding_confs: 1,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Funded,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    }
+}
+
+fn quote_record(record: &SwapRecord) -> QuoteRecord {
+    QuoteRecord {
+        quote_id: record.quote_id.clone(),
+        offer_id: format!("offer:{}", record.swap_id),
+        direction: record.direction,
+        asset_id: record.asset_id.clone(),
+        asset_amount: record.asset_amount,
+        min_funding_confs: 1,
+        total_price_msat: record.total_price_msat,
+        price_msat_per_asset_unit: 1000,
+        fee_subsidy_sats: record.fee_subsidy_sats,
+        refund_delta_blocks: 10,
+        invoice_expiry_secs: 3600,
+        max_min_funding_confs: 6,
+        supported_directions: vec![SwapDirection::LnToLiquid],
+        min_asset_amount: 0,
+        max_asset_amount: 0,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        price_source: "offer".to_string(),
+        source_price_msat_per_asset_unit: 1_000,
+        spread_bps: 0,
+        priced_at: 0,
+        swap_id: Some(record.swap_id.clone()),
+        buyer_id: Some("buyer".to_string()),
+        created_at: 0,
+        expires_at: 0,
+    }
+}
+
+fn swap_service(
+    asset_id: AssetId,
+    wallet: LiquidWallet,
+    store: Arc<Mutex<SqliteStore>>,
+) -> Result<SwapServiceImpl> {
+    Ok(SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid],
+                price_msat_per_asset_unit: 1000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 10,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        store,
+    ))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn refund_stays_pending_until_confirmed() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let policy_asset = wallet.policy_asset();
+    let asset_id = AssetId::from_str(&"01".repeat(32))?;
+    let refunder_pubkey_hash160 = pubkey_hash160_from_p2wpkh_address(&wallet.address_at(0)?)?;
+
+    let secp = Secp256k1::new();
+    let claimer_secret_key = SecretKey::from_slice(&[3u8; 32]).context("claimer secret key")?;
+    let claimer_address = Address::p2wpkh(
+        &PublicKey::new(claimer_secret_key.public_key(&secp)),
+        None,
+        &AddressParams::ELEMENTS,
+    );
+    let spec = |preimage: [u8; 32]| -> Result<HtlcSpec> {
+        Ok(HtlcSpec {
+            payment_hash: sha256_preimage(&preimage),
+            claimer_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&claimer_address)?,
+            refunder_pubkey_hash160,
+            refund_lock_height: 110,
+        })
+    };
+
+    let refunded = fund_htlc(&chain, spec([5u8; 32])?, asset_id, policy_asset)?;
+    let raced = fund_htlc(&chain, spec([6u8; 32])?, asset_id, policy_asset)?;
+    chain.mine_blocks(1);
+
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    // An expired swap whose refund cannot be built must not hold up the others.
+    let broken = SwapRecord {
+        funding_txid: "not-a-txid".to_string(),
+        ..swap_record("swap-broken", &refunded)
+    };
+    for record in [
+        broken,
+        swap_record("swap-refunded", &refunded),
+        swap_record("swap-raced", &raced),
+    ] {
+        store.insert_quote(&quote_record(&record))?;
+        store.insert_swap(&record)?;
+    }
+    let store = Arc::new(Mutex::new(store));
+
+    let svc = swap_service(asset_id, wallet, store.clone())?;
+
+    assert_eq!(svc.refund_expired_swaps().await?, 0);
+    chain.mine_blocks(9);
+    // A refund that does not reach the chain is not recorded, and is retried on the next poll.
+    chain.reject_broadcasts(Some("node offline".to_string()));
+    assert_eq!(svc.refund_expired_swaps().await?, 0);
+    {
+        let store = store.lock().expect("store mutex poisoned");
+        let swap = store.get_swap("swap-refunded")?.context("swap missing")?;
+        assert_eq!(swap.status, SwapStatus::Funded);
+        assert_eq!(swap.refund_txid, None);
+        assert_eq!(
+            store.get_swap_tx("swap-refunded", SwapTxKind::Refund)?,
+            None
+        );
+    }
+    chain.reject_broadcasts(None);
+    assert_eq!(svc.refund_expired_swaps().await?, 2);
+    assert_eq!(svc.refund_expired_swaps().await?, 0);
+
+    let refund_txid = |swap_id: &str| -> Result<Txid> {
+        let swap = store
+            .lock()
+            .expect("store mutex poisoned")
+            .get_swap(swap_id)?
+            .context("swap missing")?;
+        assert_eq!(swap.status, SwapStatus::RefundPending);
+        Ok(Txid::from_str(
+            &swap.refund_txid.context("refund_txid missing")?,
+        )?)
+    };
+    let refunded_txid = refund_txid("swap-refunded")?;
+    let raced_txid = refund_txid("swap-raced")?;
+    let mempool = chain.mempool_txids();
+    assert!(mempool.contains(&refunded_txid));
+    assert!(mempool.contains(&raced_txid));
+
+    // The buyer's claim wins the race against our refund.
+    assert!(chain.evict(&raced_txid));
+    let claim_tx = claim_tx_from_witness_script(
+        &raced.spec.witness_script(),
+        &raced.funding,
+        &claimer_address,
+        &claimer_secret_key,
+        [6u8; 32],
+        500,
+    )?;
+    chain.broadcast(&claim_tx)?;
+    assert_eq!(svc.sync_htlc_spends().await?, 1);
+
+    // A dropped refund is rebroadcast and only counts once it confirms.
+    assert!(chain.evict(&refunded_txid));
+    assert_eq!(svc.drive_swaps().await?, 0);
+    assert!(chain.mempool_txids().contains(&refunded_txid));
+    assert_eq!(svc.sync_htlc_spends().await?, 0);
+    chain.mine_blocks(1);
+    assert_eq!(svc.drive_swaps().await?, 1);
+    assert_eq!(svc.drive_swaps().await?, 0);
+
+    let store = store.lock().expect("store mutex poisoned");
+    let swap = store.get_swap("swap-refunded")?.context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::Refunded);
+    assert_eq!(
+        store.get_swap_tx("swap-refunded", SwapTxKind::Refund)?,
+        None
+    );
+    assert_eq!(
+        store
+            .list_swap_transitions("swap-refunded")?
+            .iter()
+            .map(|t| (t.from_status, t.to_status))
+            .collect::<Vec<_>>(),
+        vec![
+            (None, SwapStatus::Funded),
+            (Some(SwapStatus::Funded), SwapStatus::RefundPending),
+            (Some(SwapStatus::RefundPending), SwapStatus::Refunded),
+        ]
+    );
+
+    let swap = store.get_swap("swap-raced")?.context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::Claimed);
+    assert_eq!(swap.claim_txid, Some(claim_tx.txid().to_string()));
+    assert_eq!(swap.ln_preimage_hex, Some(hex::encode([6u8; 32])));
+    assert_eq!(store.get_swap_tx("swap-raced", SwapTxKind::Refund)?, None);
+
+    let swap = store.get_swap("swap-broken")?.context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::Funded);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pending_refund_is_replaced_when_fees_rise() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let policy_asset = wallet.policy_asset();
+    let asset_id = AssetId::from_str(&"01".repeat(32))?;
+    let htlc = fund_htlc(
+        &chain,
+        HtlcSpec {
+            payment_hash: sha256_preimage(&[5u8; 32]),
+            claimer_pubkey_hash160: [3u8; 20],
+            refunder_pubkey_hash160: pubkey_hash160_from_p2wpkh_address(&wallet.address_at(0)?)?,
+            refund_lock_height: 110,
+        },
+        asset_id,
+        policy_asset,
+    )?;
+    chain.mine_blocks(10);
+
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    let record = swap_record("swap-refunded", &htlc);
+    store.insert_quote(&quote_record(&record))?;
+    store.insert_swap(&record)?;
+    let store = Arc::new(Mutex::new(store));
+    let svc = swap_service(asset_id, wallet, store.clone())?;
+    let refund = || -> Result<Transaction> {
+        let tx_hex = store
+            .lock()
+            .expect("store mutex poisoned")
+            .get_swap_tx("swap-refunded", SwapTxKind::Refund)?
+            .context("refund tx missing")?;
+        Ok(deserialize(&hex::decode(tx_hex)?)?)
+    };
+
+    // Without an estimate the refund pays the fallback rate.
+    assert_eq!(svc.refund_expired_swaps().await?, 1);
+    let original = refund()?;
+    assert!(tx_fee_rate(&original) >= MIN_FEE_RATE_SAT_VB);
+    assert!(tx_fee_rate(&original) < 1.0);
+    svc.drive_swaps().await?;
+    assert_eq!(refund()?.txid(), original.txid());
+
+    chain.set_fee_rate(Some(1.0));
+    assert_eq!(svc.drive_swaps().await?, 0);
+    let replacement = refund()?;
+    assert_ne!(replacement.txid(), original.txid());
+    assert!(tx_fee_rate(&replacement) >= 1.0);
+    assert_eq!(chain.mempool_txids(), vec![replacement.txid()]);
+    let swap = store
+        .lock()
+        .expect("store mutex poisoned")
+        .get_swap("swap-refunded")?
+        .context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::RefundPending);
+    assert_eq!(swap.refund_txid, Some(replacement.txid().to_string()));
+
+    chain.mine_blocks(1);
+    assert_eq!(svc.drive_swaps().await?, 1);
+    let swap = store
+        .lock()
+        .expect("store mutex poisoned")
+        .get_swap("swap-refunded")?
+        .context("swap missing")?;
+    assert_eq!(swap.status, SwapStatus::Refunded);
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
 use anyhow::{Context as _, Result};
 
-use ln_liquid_swap::swap::store::SqliteStore;
-use ln_liquid_swap::swap::{QuoteRecord, SwapDirection, SwapRecord, SwapStatus};
+use ln_liquid_swap::swap::store::{PageCursor, QuoteFilter, SqliteStore, SwapFilter};
+use ln_liquid_swap::swap::{
+    ApiKeyRecord, ApiKeyRole, HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus,
+};
 
 fn sample_quote(quote_id: &str) -> QuoteRecord {
     QuoteRecord {
@@ -17,7 +19,19 @@ fn sample_quote(quote_id: &str) -> QuoteRecord {
         refund_delta_blocks: 144,
         invoice_expiry_secs: 3600,
         max_min_funding_confs: 6,
+        supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+        min_asset_amount: 0,
+        max_asset_amount: 0,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        price_source: "offer".to_string(),
+        source_price_msat_per_asset_unit: 1_000,
+        spread_bps: 0,
+        priced_at: 0,
         swap_id: None,
+        buyer_id: None,
+        created_at: 0,
+        expires_at: 0,
     }
 }
 
@@ -43,7 +57,13 @@ fn sample_swap(swap_id: &str, quote_id: &str, status: SwapStatus) -> SwapRecord
         ln_payment_id: None,
         ln_preimage_hex: None,
         claim_txid: None,
+        refund_txid: None,
         status,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
     }
 }
 
@@ -62,6 +82,19 @@ fn sqlite_store_insert_get_update_list() -> Result<()> {
         .context("quote-a missing")?;
     assert_eq!(got_q.quote_id, "quote-a");
 
+    assert!(store.reserve_quote("quote-a", "swap-x", "buyer")?);
+    assert!(!store.reserve_quote("quote-a", "swap-y", "buyer")?);
+    store.release_quote("quote-a", "swap-y")?;
+    assert!(!store.reserve_quote("quote-a", "swap-y", "buyer")?);
+    store.release_quote("quote-a", "swap-x")?;
+    assert_eq!(
+        store
+            .get_quote("quote-a")?
+            .context("quote-a missing")?
+            .swap_id,
+        None
+    );
+
     let a = sample_swap("swap-a", "quote-a", SwapStatus::Created);
     store.insert_swap(&a).context("insert swap-a")?;
 
@@ -102,13 +135,38 @@ fn sqlite_store_insert_get_update_list() -> Result<()> {
     assert_eq!(got.status, SwapStatus::Claimed);
     assert_eq!(got.claim_txid.as_deref(), Some("claim-a"));
 
-    let b = sample_swap("swap-b", "quote-a", SwapStatus::Created);
+    let mut b = sample_swap("swap-b", "quote-a", SwapStatus::Created);
+    b.buyer_claim_pubkey_hex = Some(format!("02{}", "11".repeat(32)));
+    b.htlc_version = HtlcVersion::Taproot;
+    b.htlc_blinding_key_hex = Some("22".repeat(32));
     store.insert_swap(&b).context("insert swap-b")?;
 
     let swaps = store.list_swaps().context("list swaps")?;
     assert_eq!(swaps.len(), 2);
     assert_eq!(swaps[0].swap_id, "swap-a");
+    assert_eq!(swaps[0].buyer_claim_pubkey_hex, None);
     assert_eq!(swaps[1].swap_id, "swap-b");
+    assert_eq!(swaps[1].buyer_claim_pubkey_hex, b.buyer_claim_pubkey_hex);
+    assert_eq!(swaps[0].htlc_version, HtlcVersion::P2wsh);
+    assert_eq!(swaps[1].htlc_version, HtlcVersion::Taproot);
+    assert_eq!(swaps[0].htlc_blinding_key_hex, None);
+    assert_eq!(swaps[1].htlc_blinding_key_hex, b.htlc_blinding_key_hex);
+
+    let created = store.list_swaps_by_status(&[SwapStatus::Created, SwapStatus::Funded])?;
+    assert_eq!(
+        created
+            .iter()
+            .map(|s| s.swap_id.as_str())
+            .collect::<Vec<_>>(),
+        vec!["swap-b"]
+    );
+    assert_eq!(
+        store
+            .list_swaps_by_status(&[SwapStatus::Claimed, SwapStatus::Created])?
+            .len(),
+        2
+    );
+    assert!(store.list_swaps_by_status(&[])?.is_empty());
 
     let err = store
         .update_swap_status("missing", SwapStatus::Failed)
@@ -117,3 +175,196 @@ fn sqlite_store_insert_get_update_list() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn sqlite_store_list_pages_filter_and_paginate() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let mut store =
+        SqliteStore::open(dir.path().join("swap_store.sqlite3")).context("open sqlite store")?;
+
+    for (i, id) in ["a", "b", "c", "d"].iter().enumerate() {
+        let mut q = sample_quote(&format!("quote-{id}"));
+        q.asset_id = "asset-x".to_string();
+        q.created_at = 100 + i as u64;
+        if *id == "b" {
+            q.direction = SwapDirection::LiquidToLn;
+        }
+        store.insert_quote(&q).context("insert quote")?;
+
+        let status = if *id == "c" {
+            SwapStatus::Claimed
+        } else {
+            SwapStatus::Created
+        };
+        let mut s = sample_swap(&format!("swap-{id}"), &q.quote_id, status);
+        s.asset_id = if *id == "d" { "asset-y" } else { "asset-x" }.to_string();
+        s.created_at = 100 + i as u64;
+        store.insert_swap(&s).context("insert swap")?;
+    }
+    let mut tie = sample_swap("swap-e", "quote-d", SwapStatus::Created);
+    tie.asset_id = "asset-x".to_string();
+    tie.created_at = 103;
+    store.insert_swap(&tie).context("insert swap-e")?;
+
+    let ids = |swaps: Vec<SwapRecord>| swaps.into_iter().map(|s| s.swap_id).collect::<Vec<_>>();
+
+    let all = SwapFilter::default();
+    let page = store.list_swaps_page(&all, None, 2)?;
+    assert_eq!(ids(page.clone()), ["swap-e", "swap-d"]);
+    let last = page.last().context("page empty")?;
+    let cursor = PageCursor {
+        created_at: last.created_at,
+        id: last.swap_id.clone(),
+    };
+    let page = store.list_swaps_page(&all, Some(&cursor), 2)?;
+    assert_eq!(ids(page), ["swap-c", "swap-b"]);
+
+    let created = SwapFilter {
+        status: Some(SwapStatus::Created),
+        asset_id: Some("asset-x".to_string()),
+        ..SwapFilter::default()
+    };
+    assert_eq!(
+        ids(store.list_swaps_page(&created, None, 10)?),
+        ["swap-e", "swap-b", "swap-a"]
+    );
+
+    let window = SwapFilter {
+        created_after: Some(101),
+        created_before: Some(103),
+        ..SwapFilter::default()
+    };
+    assert_eq!(
+        ids(store.list_swaps_page(&window, None, 10)?),
+        ["swap-c", "swap-b"]
+    );
+
+    let quotes = store.list_quotes_page(
+        &QuoteFilter {
+            direction: Some(SwapDirection::LnToLiquid),
+            ..QuoteFilter::default()
+        },
+        None,
+        10,
+    )?;
+    assert_eq!(
+        quotes.into_iter().map(|q| q.quote_id).collect::<Vec<_>>(),
+        ["quote-d", "quote-c", "quote-a"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sqlite_store_inventory_reservations_follow_quotes_and_swaps() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    let reserved = |store: &SqliteStore, now: u64, asset: &str| -> Result<u64> {
+        Ok(store
+            .reserved_inventory(now)?
+            .get(asset)
+            .copied()
+            .unwrap_or(0))
+    };
+
+    for quote_id in ["quote-a", "quote-b", "quote-c"] {
+        let mut quote = sample_quote(quote_id);
+        quote.expires_at = 100;
+        store.insert_quote_with_reservations(
+            &quote,
+            &[("asset".to_string(), 1_000), ("lbtc".to_string(), 10_000)],
+        )?;
+    }
+    assert_eq!(reserved(&store, 99, "asset")?, 3_000);
+    assert_eq!(reserved(&store, 99, "lbtc")?, 30_000);
+    // Unused quotes lapse at expiry.
+    assert_eq!(reserved(&store, 100, "asset")?, 0);
+
+    // A taken quote keeps the reservation past quote expiry while the swap is funded.
+    assert!(store.reserve_quote("quote-a", "swap-a", "buyer")?);
+    assert!(store.reserve_quote("quote-b", "swap-b", "buyer")?);
+    assert_eq!(reserved(&store, 100, "asset")?, 2_000);
+    store.release_quote("quote-b", "swap-b")?;
+    assert_eq!(
+        store.get_quote("quote-b")?.context("quote-b")?.buyer_id,
+        None
+    );
+    assert_eq!(
+        store
+            .get_quote("quote-a")?
+            .context("quote-a")?
+            .buyer_id
+            .as_deref(),
+        Some("buyer")
+    );
+    assert_eq!(reserved(&store, 100, "asset")?, 1_000);
+    assert_eq!(reserved(&store, 100, "lbtc")?, 10_000);
+
+    // Once the swap is stored its funding tx has left the wallet balance.
+    store.insert_swap(&sample_swap("swap-a", "quote-a", SwapStatus::Created))?;
+    assert!(store.reserved_inventory(100)?.is_empty());
+    let open = store.open_notional("buyer")?;
+    assert_eq!((open.total_msat, open.buyer_msat), (1_000_000, 1_000_000));
+    assert_eq!(store.open_notional("other")?.buyer_msat, 0);
+    store.update_swap_status("swap-a", SwapStatus::Refunded)?;
+    assert_eq!(store.open_notional("buyer")?.total_msat, 0);
+
+    Ok(())
+}
+
+#[test]
+fn sqlite_store_api_keys_roundtrip_and_revoke() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+
+    let key = |key_id: &str, role: ApiKeyRole, created_at: u64| ApiKeyRecord {
+        key_id: key_id.to_string(),
+        secret_hash: format!("hash:{key_id}"),
+        role,
+        owner: "tenant-a".to_string(),
+        created_at,
+        revoked_at: None,
+    };
+    store.insert_api_key(&key("ak_b", ApiKeyRole::Buyer, 20))?;
+    store.insert_api_key(&key("ak_a", ApiKeyRole::Seller, 10))?;
+    assert!(
+        store
+            .insert_api_key(&key("ak_a", ApiKeyRole::Buyer, 30))
+            .is_err()
+    );
+
+    assert_eq!(
+        store.get_api_key("ak_b")?,
+        Some(key("ak_b", ApiKeyRole::Buyer, 20))
+    );
+    assert_eq!(store.get_api_key("ak_missing")?, None);
+
+    let revoked = store.revoke_api_key("ak_b", 50)?.context("ak_b")?;
+    assert_eq!(revoked.revoked_at, Some(50));
+    let revoked = store.revoke_api_key("ak_b", 60)?.context("ak_b")?;
+    assert_eq!(revoked.revoked_at, Some(50));
+    assert_eq!(store.revoke_api_key("ak_missing", 60)?, None);
+
+    let keys = store
+        .list_api_keys()?
+        .into_iter()
+        .map(|k| (k.key_id, k.role, k.revoked_at))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        vec![
+            ("ak_a".to_string(), ApiKeyRole::Seller, None),
+            ("ak_b".to_string(), ApiKeyRole::Buyer, Some(50)),
+        ]
+    );
+
+    let mut swap = sample_swap("swap-a", "quote-a", SwapStatus::Created);
+    swap.owner = "tenant-a".to_string();
+    store.insert_swap(&swap)?;
+    assert_eq!(
+        store.get_swap("swap-a")?.context("swap-a")?.owner,
+        "tenant-a"
+    );
+
+    Ok(())
+}
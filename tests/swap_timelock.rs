@@ -0,0 +1,172 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::AssetId;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::backend::LightningBackend as _;
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::SwapDirection;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::timelock::{check_buyer_invoice, check_invoice_expiry, margin_blocks};
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+fn offer(asset_id: AssetId, direction: SwapDirection, refund_delta_blocks: u32) -> AssetOffer {
+    AssetOffer {
+        asset_id,
+        supported_directions: vec![direction],
+        price_msat_per_asset_unit: 1_000,
+        fee_subsidy_sats: 2_000,
+        refund_delta_blocks,
+        min_asset_amount: 1,
+        max_asset_amount: 0,
+    }
+}
+
+fn quote_request(asset_id: AssetId, direction: pb::SwapDirection) -> pb::CreateQuoteRequest {
+    pb::CreateQuoteRequest {
+        direction: direction as i32,
+        asset_id: asset_id.to_string(),
+        asset_amount: 100,
+        min_funding_confs: 1,
+    }
+}
+
+#[test]
+fn margins_are_counted_in_liquid_blocks() -> Result<()> {
+    assert_eq!(margin_blocks(100, 3600), 40);
+    assert_eq!(margin_blocks(100, 3601), 39);
+    assert_eq!(margin_blocks(10, 3600), -50);
+
+    check_invoice_expiry(70, 3600, 10)?;
+    let err = check_invoice_expiry(69, 3600, 10).expect_err("margin below minimum");
+    assert!(err.to_string().contains("leaves 9 blocks"), "{err}");
+
+    // 9 Bitcoin blocks take as long as 90 Liquid blocks.
+    check_buyer_invoice(100, 600, 9, 10)?;
+    let err = check_buyer_invoice(100, 600, 10, 10).expect_err("cltv delta too long");
+    assert!(
+        err.to_string().contains("min_final_cltv_expiry_delta"),
+        "{err}"
+    );
+    let err = check_buyer_invoice(100, 6000, 1, 10).expect_err("invoice outlives the timelock");
+    assert!(err.to_string().contains("invoice expires"), "{err}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unsafe_invoices_are_rejected() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(SimulatedLiquidChain::new()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let buyer_liquid_address = wallet.address_at(1)?.to_string();
+    let short = AssetId::from_str(&"01".repeat(32))?;
+    let reverse = AssetId::from_str(&"02".repeat(32))?;
+
+    let cfg = SwapServiceConfig {
+        offers: OfferBook::new([
+            offer(short, SwapDirection::LnToLiquid, 60),
+            offer(reverse, SwapDirection::LiquidToLn, 100),
+        ])?,
+        invoice_expiry_secs: 3600,
+        min_timelock_margin_blocks: 10,
+        payment_safety_blocks: 0,
+        quote_ttl_secs: 600,
+        pricing: PricingPolicy::default(),
+        check_inventory: false,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        seller_key_index: 0,
+        buyer_key_index: 1,
+        seller_token: "seller-token".to_string(),
+        buyer_token: "buyer-token".to_string(),
+    };
+    let err = cfg
+        .check_offer_timelock(&offer(short, SwapDirection::LnToLiquid, 60))
+        .expect_err("offer invoices outlive the timelock");
+    assert!(
+        format!("{err:#}").contains("refund_delta_blocks 60"),
+        "{err:#}"
+    );
+    cfg.check_offer_timelock(&offer(short, SwapDirection::LnToLiquid, 70))?;
+
+    let svc = SwapServiceImpl::new(
+        cfg,
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        Arc::new(Mutex::new(SqliteStore::open(
+            dir.path().join("swap_store.sqlite3"),
+        )?)),
+    );
+
+    let err = svc
+        .create_quote(with_auth(
+            quote_request(short, pb::SwapDirection::LnToLiquid),
+            "seller-token",
+        )?)
+        .await
+        .expect_err("quote must be rejected");
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        err.message().contains("refund_delta_blocks"),
+        "{}",
+        err.message()
+    );
+
+    // The fake node's invoices use a 144 block min_final_cltv_expiry_delta.
+    let buyer_ln = FakeLightningNode::new();
+    for (expiry_secs, message) in [
+        (600, "min_final_cltv_expiry_delta"),
+        (7200, "invoice expires"),
+    ] {
+        let quote = svc
+            .create_quote(with_auth(
+                quote_request(reverse, pb::SwapDirection::LiquidToLn),
+                "seller-token",
+            )?)
+            .await?
+            .into_inner();
+        let invoice = buyer_ln
+            .create_invoice(quote.total_price_msat, "swap".to_string(), expiry_secs)
+            .await?;
+        let err = svc
+            .create_swap(with_auth(
+                pb::CreateSwapRequest {
+                    quote_id: quote.quote_id,
+                    buyer_liquid_address: buyer_liquid_address.clone(),
+                    buyer_bolt11_invoice: invoice,
+                    ..Default::default()
+                },
+                "buyer-token",
+            )?)
+            .await
+            .expect_err("buyer invoice must be rejected");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(err.message().contains(message), "{}", err.message());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::str::FromStr as _;
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, Script, Transaction, TxOut, TxOutWitness,
+};
+
+use ln_liquid_swap::lightning::backend::LightningBackend as _;
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::lightning::invoice::payment_hash_from_bolt11;
+use ln_liquid_swap::liquid::htlc::HtlcSpec;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::swap::verify::{SwapExpectations, verify_swap};
+
+fn explicit_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+struct Fixture {
+    swap: pb::Swap,
+    expected: SwapExpectations,
+    funding_tx: Transaction,
+}
+
+async fn fixture() -> Result<Fixture> {
+    let asset_id =
+        AssetId::from_str("0101010101010101010101010101010101010101010101010101010101010101")?;
+    let policy_asset =
+        AssetId::from_str("0202020202020202020202020202020202020202020202020202020202020202")?;
+
+    let invoice = FakeLightningNode::new()
+        .create_invoice(5_000_000, "swap:swap-a".to_string(), 3600)
+        .await?;
+    let spec = HtlcSpec {
+        payment_hash: payment_hash_from_bolt11(&invoice)?,
+        claimer_pubkey_hash160: [1u8; 20],
+        refunder_pubkey_hash160: [2u8; 20],
+        refund_lock_height: 300,
+    };
+    let witness_script = spec.witness_script();
+    let htlc_address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+
+    let funding_tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            explicit_output(&htlc_address, asset_id, 5_000),
+            explicit_output(&htlc_address, policy_asset, 2_000),
+        ],
+    };
+
+    let swap = pb::Swap {
+        swap_id: "swap-a".to_string(),
+        direction: pb::SwapDirection::LnToLiquid as i32,
+        parties: None,
+        bolt11_invoice: invoice,
+        payment_hash: hex::encode(spec.payment_hash),
+        status: pb::SwapStatus::Funded as i32,
+        liquid: Some(pb::LiquidHtlc {
+            asset_id: asset_id.to_string(),
+            asset_amount: 5_000,
+            fee_subsidy_sats: 2_000,
+            refund_lock_height: spec.refund_lock_height,
+            p2wsh_address: htlc_address.to_string(),
+            witness_script: witness_script.to_bytes(),
+            funding_txid: funding_tx.txid().to_string(),
+            asset_vout: 0,
+            lbtc_vout: 1,
+            min_funding_confs: 1,
+            buyer_claim_pubkey: Vec::new(),
+            htlc_version: pb::HtlcVersion::P2wsh as i32,
+            blinding_key: Vec::new(),
+        }),
+        quote_id: "quote-a".to_string(),
+        created_at: 0,
+    };
+
+    let expected = SwapExpectations {
+        user_pubkey_hash160: spec.claimer_pubkey_hash160,
+        asset_id,
+        asset_amount: 5_000,
+        total_price_msat: 5_000_000,
+        fee_subsidy_sats: 2_000,
+        policy_asset,
+        tip_height: 200,
+        min_refund_blocks: 12,
+    };
+
+    Ok(Fixture {
+        swap,
+        expected,
+        funding_tx,
+    })
+}
+
+fn verify_err(f: &Fixture) -> String {
+    format!(
+        "{:#}",
+        verify_swap(&f.swap, &f.expected, &f.funding_tx).expect_err("verification must fail")
+    )
+}
+
+#[tokio::test]
+async fn verify_swap_accepts_matching_htlc() -> Result<()> {
+    let f = fixture().await?;
+    let spec = verify_swap(&f.swap, &f.expected, &f.funding_tx).context("verify swap")?;
+    assert_eq!(spec.refund_lock_height, 300);
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_swap_rejects_mismatches() -> Result<()> {
+    let mut f = fixture().await?;
+    f.expected.user_pubkey_hash160 = [9u8; 20];
+    assert!(verify_err(&f).contains("claim key does not belong to the user"));
+
+    let mut f = fixture().await?;
+    f.expected.tip_height = 295;
+    assert!(verify_err(&f).contains("too close to tip"));
+
+    let mut f = fixture().await?;
+    f.swap.bolt11_invoice = FakeLightningNode::new()
+        .create_invoice(5_000_000, "other".to_string(), 3600)
+        .await?;
+    assert!(verify_err(&f).contains("payment hash does not match"));
+
+    let mut f = fixture().await?;
+    f.expected.total_price_msat = 4_000_000;
+    assert!(verify_err(&f).contains("amount mismatch"));
+
+    let mut f = fixture().await?;
+    f.swap.liquid.as_mut().context("liquid")?.p2wsh_address =
+        Address::p2wsh(&Script::new(), None, &AddressParams::ELEMENTS).to_string();
+    assert!(verify_err(&f).contains("does not commit to witness_script"));
+
+    let mut f = fixture().await?;
+    f.funding_tx.output[0].value = Value::Explicit(4_999);
+    f.swap.liquid.as_mut().context("liquid")?.funding_txid = f.funding_tx.txid().to_string();
+    assert!(verify_err(&f).contains("value mismatch"));
+
+    let mut f = fixture().await?;
+    f.funding_tx.output.swap(0, 1);
+    f.swap.liquid.as_mut().context("liquid")?.funding_txid = f.funding_tx.txid().to_string();
+    assert!(verify_err(&f).contains("asset mismatch"));
+
+    let mut f = fixture().await?;
+    f.funding_tx.version = 1;
+    assert!(verify_err(&f).contains("does not match funding_txid"));
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use lwk_wollet::ElementsNetwork;
+use lwk_wollet::elements::confidential::{Asset, Nonce, Value};
+use lwk_wollet::elements::{
+    Address, AddressParams, AssetId, LockTime, Transaction, TxOut, TxOutWitness,
+};
+use tokio::sync::mpsc;
+use tonic::Request;
+use tonic::metadata::MetadataValue;
+
+use ln_liquid_swap::lightning::fake::FakeLightningNode;
+use ln_liquid_swap::liquid::chain::LiquidChain as _;
+use ln_liquid_swap::liquid::htlc::{HtlcSpec, sha256_preimage};
+use ln_liquid_swap::liquid::sim::SimulatedLiquidChain;
+use ln_liquid_swap::liquid::wallet::LiquidWallet;
+use ln_liquid_swap::proto::v1 as pb;
+use ln_liquid_swap::proto::v1::swap_service_server::SwapService as _;
+use ln_liquid_swap::swap::offer::{AssetOffer, OfferBook};
+use ln_liquid_swap::swap::pricing::PricingPolicy;
+use ln_liquid_swap::swap::service::{SwapServiceConfig, SwapServiceImpl};
+use ln_liquid_swap::swap::store::SqliteStore;
+use ln_liquid_swap::swap::{HtlcVersion, QuoteRecord, SwapDirection, SwapRecord, SwapStatus};
+
+fn explicit_output(address: &Address, asset: AssetId, value: u64) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: address.script_pubkey(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+fn with_auth<T>(message: T, token: &str) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    let value = MetadataValue::try_from(format!("Bearer {token}")).context("auth metadata")?;
+    request.metadata_mut().insert("authorization", value);
+    Ok(request)
+}
+
+async fn next_event(
+    events: &mut mpsc::Receiver<Result<pb::SwapEvent, tonic::Status>>,
+    status: pb::SwapStatus,
+) -> Result<pb::SwapEvent> {
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .with_context(|| format!("timeout waiting for {status:?}"))?
+            .context("stream ended")??;
+        if event.swap.as_ref().map(|s| s.status) == Some(status as i32) {
+            return Ok(event);
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn watch_swap_streams_confirmations_and_status_changes() -> Result<()> {
+    let dir = tempfile::tempdir().context("create tempdir")?;
+    let chain = SimulatedLiquidChain::new();
+    chain.mine_blocks(100);
+
+    let wallet = LiquidWallet::with_chain(
+        lwk_test_util::TEST_MNEMONIC,
+        lwk_test_util::TEST_MNEMONIC_SLIP77,
+        Box::new(chain.clone()),
+        &dir.path().join("wallet"),
+        ElementsNetwork::default_regtest(),
+    )
+    .context("create wallet")?;
+    let policy_asset = wallet.policy_asset();
+    let asset_id =
+        AssetId::from_str("0101010101010101010101010101010101010101010101010101010101010101")?;
+
+    let preimage = [5u8; 32];
+    let spec = HtlcSpec {
+        payment_hash: sha256_preimage(&preimage),
+        claimer_pubkey_hash160: [3u8; 20],
+        refunder_pubkey_hash160: [4u8; 20],
+        refund_lock_height: 500,
+    };
+    let address = spec.p2wsh_address(&AddressParams::ELEMENTS);
+    let funding_txid = chain.broadcast(&Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![
+            explicit_output(&address, asset_id, 5_000),
+            explicit_output(&address, policy_asset, 2_000),
+        ],
+    })?;
+
+    let record = SwapRecord {
+        swap_id: "swap-watch".to_string(),
+        quote_id: "quote-watch".to_string(),
+        direction: SwapDirection::LnToLiquid,
+        bolt11_invoice: "invoice:swap-watch".to_string(),
+        payment_hash: hex::encode(spec.payment_hash),
+        asset_id: asset_id.to_string(),
+        asset_amount: 5_000,
+        total_price_msat: 5_000_000,
+        buyer_liquid_address: "buyer_liquid_address:swap-watch".to_string(),
+        fee_subsidy_sats: 2_000,
+        refund_lock_height: spec.refund_lock_height,
+        p2wsh_address: address.to_string(),
+        witness_script_hex: hex::encode(spec.witness_script().to_bytes()),
+        funding_txid: funding_txid.to_string(),
+        asset_vout: 0,
+        lbtc_vout: 1,
+        min_funding_confs: 2,
+        ln_payment_id: None,
+        ln_preimage_hex: None,
+        claim_txid: None,
+        refund_txid: None,
+        status: SwapStatus::Created,
+        buyer_claim_pubkey_hex: None,
+        htlc_version: HtlcVersion::P2wsh,
+        htlc_blinding_key_hex: None,
+        owner: "buyer".to_string(),
+        created_at: 0,
+    };
+    let mut store = SqliteStore::open(dir.path().join("swap_store.sqlite3"))?;
+    store.insert_quote(&QuoteRecord {
+        quote_id: record.quote_id.clone(),
+        offer_id: "offer:swap-watch".to_string(),
+        direction: record.direction,
+        asset_id: record.asset_id.clone(),
+        asset_amount: record.asset_amount,
+        min_funding_confs: record.min_funding_confs,
+        total_price_msat: record.total_price_msat,
+        price_msat_per_asset_unit: 1000,
+        fee_subsidy_sats: record.fee_subsidy_sats,
+        refund_delta_blocks: 100,
+        invoice_expiry_secs: 3600,
+        max_min_funding_confs: 6,
+        supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+        min_asset_amount: 0,
+        max_asset_amount: 0,
+        max_open_notional_msat: 0,
+        max_buyer_open_notional_msat: 0,
+        price_source: "offer".to_string(),
+        source_price_msat_per_asset_unit: 1_000,
+        spread_bps: 0,
+        priced_at: 0,
+        swap_id: Some(record.swap_id.clone()),
+        buyer_id: Some("buyer".to_string()),
+        created_at: 0,
+        expires_at: 0,
+    })?;
+    store.insert_swap(&record)?;
+    let store = Arc::new(Mutex::new(store));
+
+    let svc = SwapServiceImpl::new(
+        SwapServiceConfig {
+            offers: OfferBook::new([AssetOffer {
+                asset_id: asset_id,
+                supported_directions: vec![SwapDirection::LnToLiquid, SwapDirection::LiquidToLn],
+                price_msat_per_asset_unit: 1000,
+                fee_subsidy_sats: 2_000,
+                refund_delta_blocks: 100,
+                min_asset_amount: 0,
+                max_asset_amount: 0,
+            }])?,
+            invoice_expiry_secs: 3600,
+            min_timelock_margin_blocks: 0,
+            payment_safety_blocks: 0,
+            quote_ttl_secs: 600,
+            pricing: PricingPolicy::default(),
+            check_inventory: false,
+            max_open_notional_msat: 0,
+            max_buyer_open_notional_msat: 0,
+            seller_key_index: 0,
+            buyer_key_index: 1,
+            seller_token: "seller-token".to_string(),
+            buyer_token: "buyer-token".to_string(),
+        },
+        Arc::new(FakeLightningNode::new()),
+        Arc::new(Mutex::new(wallet)),
+        store.clone(),
+    );
+
+    let err = svc
+        .watch_swap(with_auth(
+            pb::WatchSwapRequest {
+                swap_id: "swap-missing".to_string(),
+            },
+            "buyer-token",
+        )?)
+        .await
+        .expect_err("missing swap must be rejected");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let mut events = svc
+        .watch_swap(with_auth(
+            pb::WatchSwapRequest {
+                swap_id: record.swap_id.clone(),
+            },
+            "buyer-token",
+        )?)
+        .await?
+        .into_inner()
+        .into_inner();
+
+    let event = next_event(&mut events, pb::SwapStatus::Created).await?;
+    assert_eq!(event.funding_confirmations, 0);
+
+    // Watchers read the tip the swap executor last synced.
+    chain.mine_blocks(1);
+    assert_eq!(svc.drive_swaps().await?, 0);
+    loop {
+        let event = next_event(&mut events, pb::SwapStatus::Created).await?;
+        if event.funding_confirmations == 1 {
+            break;
+        }
+    }
+
+    chain.mine_blocks(1);
+    assert_eq!(svc.drive_swaps().await?, 1);
+    let event = next_event(&mut events, pb::SwapStatus::Funded).await?;
+    assert_eq!(event.funding_confirmations, 2);
+
+    // Back-to-back transitions each get their own event.
+    {
+        let mut store = store.lock().expect("store mutex poisoned");
+        store.upsert_swap_payment(
+            &record.swap_id,
+            "payment-watch",
+            &hex::encode(preimage),
+            SwapStatus::Paid,
+        )?;
+        store.upsert_swap_claim(&record.swap_id, "claim-watch", SwapStatus::Claimed)?;
+    }
+    let mut statuses = Vec::new();
+    while let Some(event) = tokio::time::timeout(Duration::from_secs(10), events.recv())
+        .await
+        .context("timeout waiting for stream end")?
+    {
+        let event = event?;
+        assert_eq!(event.funding_confirmations, 2);
+        statuses.push(event.swap.context("event without swap")?.status);
+    }
+    assert_eq!(
+        statuses,
+        [pb::SwapStatus::Paid as i32, pb::SwapStatus::Claimed as i32],
+        "each transition gets its own event and the stream ends after Claimed"
+    );
+
+    Ok(())
+}